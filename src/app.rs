@@ -1,16 +1,21 @@
 //! Application container for Symora
 
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use crate::cli::OutputContext;
+use crate::cli::{OutputCapture, OutputContext, OutputFormat};
 use crate::config;
+use crate::error::EditError;
 use crate::models::config::SymoraConfig;
+use crate::models::lsp::FileChangeWithEdits;
 use crate::services::ast_query::{AstQueryService, DefaultAstQueryService};
 use crate::services::config::{ConfigService, DefaultConfigService};
 use crate::services::daemon_lsp::DaemonLspService;
+use crate::services::journal::Journal;
 use crate::services::lsp::{DefaultLspService, LspService};
 use crate::services::project::{DefaultProjectService, ProjectService};
+use crate::services::workspace_edit::{AppliedFile, apply_workspace_edit};
 
 pub struct App {
     root: PathBuf,
@@ -29,14 +34,22 @@ impl App {
     }
 
     pub async fn with_daemon(use_daemon: bool) -> anyhow::Result<Self> {
-        let root = std::env::current_dir()?;
+        Self::with_root(std::env::current_dir()?, use_daemon).await
+    }
 
+    /// Create an app for the project at `root`
+    pub async fn with_root(root: PathBuf, use_daemon: bool) -> anyhow::Result<Self> {
         tracing::debug!("Initializing Symora at {:?}", root);
 
-        let output = OutputContext::new(root.clone());
         let config_service = Arc::new(DefaultConfigService::new(&root));
         let config = config_service.load(false).await.unwrap_or_default();
 
+        let format = config.output.format.parse().unwrap_or_else(|e| {
+            tracing::warn!("{}, falling back to json", e);
+            OutputFormat::Json
+        });
+        let output = OutputContext::new(root.clone()).with_format(format, use_color(&config));
+
         // Initialize global config singleton (thread-safe, no unsafe)
         config::init(&config);
 
@@ -66,10 +79,40 @@ impl App {
         })
     }
 
+    /// Override the configured output format (e.g. from `--format`)
+    pub fn with_output_format(mut self, format: Option<OutputFormat>) -> Self {
+        if let Some(format) = format {
+            let color = use_color(&self.config);
+            self.output = self.output.with_format(format, color);
+        }
+        self
+    }
+
+    /// Clone of this app sharing its services (and daemon connections),
+    /// whose output is captured instead of printed
+    pub(crate) fn with_captured_output(&self) -> (Self, OutputCapture) {
+        let (output, capture) = self.output.capturing();
+        let app = Self {
+            root: self.root.clone(),
+            output,
+            lsp: Arc::clone(&self.lsp),
+            ast: Arc::clone(&self.ast),
+            project: Arc::clone(&self.project),
+            config_service: Arc::clone(&self.config_service),
+            config: self.config.clone(),
+            daemon_mode: self.daemon_mode,
+        };
+        (app, capture)
+    }
+
     pub fn root(&self) -> &Path {
         &self.root
     }
 
+    pub fn output(&self) -> &OutputContext {
+        &self.output
+    }
+
     pub fn config(&self) -> &SymoraConfig {
         &self.config
     }
@@ -81,4 +124,32 @@ impl App {
     pub fn is_initialized(&self) -> bool {
         self.root.join(".symora").exists()
     }
+
+    pub fn journal(&self) -> Journal {
+        Journal::new(&self.root)
+    }
+
+    /// Apply a workspace edit atomically and record it in the undo journal
+    pub fn apply_edit(
+        &self,
+        command: &str,
+        changes: &[FileChangeWithEdits],
+    ) -> Result<Vec<AppliedFile>, EditError> {
+        let applied = apply_workspace_edit(changes)?;
+        self.record_edit(command, &applied);
+        Ok(applied)
+    }
+
+    /// Record files changed outside [`Self::apply_edit`] as one journal step
+    pub fn record_edit(&self, command: &str, applied: &[AppliedFile]) {
+        if let Err(e) = self.journal().record(command, applied) {
+            tracing::warn!("Failed to record '{}' in the undo journal: {}", command, e);
+        }
+    }
+}
+
+/// Colors are used only when enabled in config, stdout is a terminal and
+/// `NO_COLOR` is not set
+fn use_color(config: &SymoraConfig) -> bool {
+    config.output.color && std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
 }
@@ -9,7 +9,9 @@ use serde::Serialize;
 
 use crate::app::App;
 use crate::cli::ParsedLocation;
-use crate::cli::response::LocationOutput;
+use crate::cli::expect::{self, Expectation};
+use crate::cli::response::{LocationOutput, ResourceOperationOutput};
+use crate::models::lsp::{Position, Range};
 
 #[derive(Args, Debug)]
 pub struct ActionsArgs {
@@ -21,7 +23,7 @@ pub struct ActionsArgs {
 pub enum ActionsCommand {
     /// List available code actions at position
     List {
-        /// File path with position (file:line:column)
+        /// Location (file:line:column, file#Class/method or Class/method)
         location: String,
 
         /// Filter by action kind (quickfix, refactor, source)
@@ -31,7 +33,7 @@ pub enum ActionsCommand {
 
     /// Apply a code action
     Apply {
-        /// File path with position (file:line:column)
+        /// Location (file:line:column, file#Class/method or Class/method)
         location: String,
 
         /// Action index from list (0-based)
@@ -49,6 +51,10 @@ pub enum ActionsCommand {
         /// Actually execute the changes (default: dry-run showing diff)
         #[arg(long)]
         execute: bool,
+
+        /// Refuse unless the line at the location still holds this text (or hash:<hex>)
+        #[arg(long)]
+        expect: Option<Expectation>,
     },
 }
 
@@ -72,6 +78,12 @@ struct ActionOutput {
 #[derive(Serialize)]
 struct ApplyResponse {
     action: String,
+    /// Server command run after the action's edit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+    /// Whether `command` ran; a dry run only reports it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command_executed: Option<bool>,
     dry_run: bool,
     changes: Vec<FileChangeOutput>,
 }
@@ -79,6 +91,8 @@ struct ApplyResponse {
 #[derive(Serialize)]
 struct FileChangeOutput {
     file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation: Option<ResourceOperationOutput>,
     edits: Vec<EditOutput>,
 }
 
@@ -101,7 +115,7 @@ pub async fn execute(args: ActionsArgs, app: &App) -> Result<()> {
 
     match args.command {
         ActionsCommand::List { location, kind } => {
-            let loc = ParsedLocation::parse(&location)?.to_absolute()?;
+            let loc = ParsedLocation::resolve(&location, app).await?;
 
             match app.lsp.code_actions(&loc.file, loc.line, loc.column).await {
                 Ok(actions) => {
@@ -151,8 +165,13 @@ pub async fn execute(args: ActionsArgs, app: &App) -> Result<()> {
             preferred,
             kind,
             execute: do_execute,
+            expect,
         } => {
-            let loc = ParsedLocation::parse(&location)?.to_absolute()?;
+            let loc = ParsedLocation::resolve(&location, app).await?;
+            let line = Range::point(Position::new(loc.line.saturating_sub(1), 0));
+            if !expect::guard(ctx, &loc.file, &line, expect.as_ref())? {
+                return Ok(());
+            }
 
             // Get available actions
             let actions = match app.lsp.code_actions(&loc.file, loc.line, loc.column).await {
@@ -217,13 +236,21 @@ pub async fn execute(args: ActionsArgs, app: &App) -> Result<()> {
             };
 
             // Apply action
-            match app.lsp.apply_code_action(&loc.file, action).await {
+            match app
+                .lsp
+                .apply_code_action(&loc.file, action, do_execute)
+                .await
+            {
                 Ok(result) => {
                     let changes: Vec<FileChangeOutput> = result
                         .changes
                         .iter()
                         .map(|fc| FileChangeOutput {
                             file: ctx.relative_path(&fc.file),
+                            operation: fc
+                                .operation
+                                .as_ref()
+                                .map(|op| ResourceOperationOutput::new(op, ctx.root())),
                             edits: fc
                                 .edits
                                 .iter()
@@ -240,22 +267,16 @@ pub async fn execute(args: ActionsArgs, app: &App) -> Result<()> {
                         })
                         .collect();
 
-                    if do_execute {
-                        // Apply changes to files
-                        for change in &result.changes {
-                            if let Err(e) = apply_edits_to_file(&change.file, &change.edits).await {
-                                ctx.print_error(&format!(
-                                    "Failed to apply changes to {}: {}",
-                                    change.file.display(),
-                                    e
-                                ));
-                                return Ok(());
-                            }
-                        }
+                    let command = format!("actions apply {} ({})", location, action.title);
+                    if do_execute && let Err(e) = app.apply_edit(&command, &result.changes) {
+                        ctx.print_error(&format!("Failed to apply changes: {}", e));
+                        return Ok(());
                     }
 
                     let response = ApplyResponse {
                         action: action.title.clone(),
+                        command_executed: result.command.as_ref().map(|_| result.command_executed),
+                        command: result.command,
                         dry_run: !do_execute,
                         changes,
                     };
@@ -268,60 +289,3 @@ pub async fn execute(args: ActionsArgs, app: &App) -> Result<()> {
 
     Ok(())
 }
-
-/// Apply text edits to a file
-async fn apply_edits_to_file(
-    file: &std::path::Path,
-    edits: &[crate::models::lsp::TextEdit],
-) -> Result<()> {
-    use std::io::Write;
-
-    let content = tokio::fs::read_to_string(file).await?;
-    let lines: Vec<&str> = content.lines().collect();
-
-    // Sort edits in reverse order (bottom to top) to preserve positions
-    let mut sorted_edits: Vec<_> = edits.iter().collect();
-    sorted_edits.sort_by(|a, b| {
-        let a_pos = (a.range.start.line, a.range.start.character);
-        let b_pos = (b.range.start.line, b.range.start.character);
-        b_pos.cmp(&a_pos) // Reverse order
-    });
-
-    // Convert to byte offsets and apply
-    let mut result = content.clone();
-
-    for edit in sorted_edits {
-        let start_offset =
-            line_col_to_offset(&lines, edit.range.start.line, edit.range.start.character);
-        let end_offset = line_col_to_offset(&lines, edit.range.end.line, edit.range.end.character);
-
-        if let (Some(start), Some(end)) = (start_offset, end_offset) {
-            result = format!("{}{}{}", &result[..start], edit.new_text, &result[end..]);
-        }
-    }
-
-    // Write back to file
-    let mut file = std::fs::File::create(file)?;
-    file.write_all(result.as_bytes())?;
-
-    Ok(())
-}
-
-/// Convert line:column to byte offset
-fn line_col_to_offset(lines: &[&str], line: u32, col: u32) -> Option<usize> {
-    let mut offset = 0;
-
-    for (i, l) in lines.iter().enumerate() {
-        if i == line as usize {
-            return Some(offset + col as usize);
-        }
-        offset += l.len() + 1; // +1 for newline
-    }
-
-    // Handle end of file
-    if line as usize == lines.len() {
-        Some(offset)
-    } else {
-        None
-    }
-}
@@ -1,21 +1,27 @@
 //! Batch command implementation
 //!
 //! Execute multiple commands in a single request for efficiency.
-//! Commands are read from stdin in JSON format.
+//! Commands are read from stdin in JSON format, one object per line, and
+//! accept the same arguments as the corresponding CLI command.
+//!
+//! Parameters can reference the results of earlier steps with `$<step>.<path>`,
+//! e.g. `{"command": "hover", "location": "$0.definition"}`. A `[*]` path
+//! segment fans the step out over every item, e.g.
+//! `{"command": "hover", "location": "$1.references[*]"}`.
 
 use std::io::{self, BufRead};
 
-use anyhow::Result;
-use clap::Args;
-use serde::{Deserialize, Serialize};
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{Args, CommandFactory, Parser};
+use serde::Serialize;
+use serde_json::{Map, Value};
 
 use crate::app::App;
-use crate::cli::ParsedLocation;
-use crate::models::lsp::FindSymbolsOptions;
+use crate::cli::{Cli, execute_command};
 
 #[derive(Args, Debug)]
 pub struct BatchArgs {
-    /// Execute commands in parallel when possible
+    /// Execute independent commands in parallel when possible
     #[arg(long)]
     pub parallel: bool,
 
@@ -24,20 +30,402 @@ pub struct BatchArgs {
     pub fail_fast: bool,
 }
 
-/// A single batch command
-#[derive(Debug, Deserialize)]
-#[serde(tag = "command", rename_all = "snake_case")]
-pub enum BatchCommand {
-    FindSymbol { file: String },
-    FindRefs { location: String },
-    FindDef { location: String },
-    FindTypedef { location: String },
-    FindImpl { location: String },
-    Hover { location: String },
-    Diagnostics { file: String },
-    Rename { location: String, new_name: String },
-    CallsIncoming { location: String },
-    CallsOutgoing { location: String },
+/// A single batch command, given as one JSON object per line.
+///
+/// `command` names a CLI subcommand path (`find_refs`, `search text`,
+/// `edit_insert_after`); the remaining fields are that command's arguments,
+/// keyed by argument name, e.g. `{"command": "search_text", "pattern": "TODO",
+/// "file_type": "rust", "limit": 5}`.
+#[derive(Debug, Clone)]
+pub struct BatchCommand {
+    pub command: String,
+    pub params: Map<String, Value>,
+}
+
+impl BatchCommand {
+    /// Parse a JSON object line
+    pub fn parse(line: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(line).context("Invalid JSON")?;
+        let Value::Object(mut params) = value else {
+            bail!("Batch command must be a JSON object");
+        };
+        let command = match params.shift_remove("command") {
+            Some(Value::String(command)) => command,
+            _ => bail!("Batch command requires a \"command\" string field"),
+        };
+        Ok(Self { command, params })
+    }
+
+    /// Build the equivalent CLI argument list (including the binary name)
+    pub fn to_args(&self) -> Result<Vec<String>> {
+        let root = Cli::command();
+        let tokens: Vec<&str> = self
+            .command
+            .split(|c: char| c == '_' || c.is_whitespace())
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        let mut args = vec![root.get_name().to_string()];
+        let mut cmd = &root;
+        let mut i = 0;
+        while i < tokens.len() {
+            // Longest match first, so `insert_after` resolves to `insert-after`
+            let (next, sub) = (i + 1..=tokens.len())
+                .rev()
+                .find_map(|j| {
+                    cmd.find_subcommand(tokens[i..j].join("-"))
+                        .map(|sub| (j, sub))
+                })
+                .ok_or_else(|| anyhow!("Unknown batch command '{}'", self.command))?;
+            args.push(sub.get_name().to_string());
+            cmd = sub;
+            i = next;
+        }
+
+        if i == 0 {
+            bail!("Batch command name cannot be empty");
+        }
+        if matches!(args[1].as_str(), "batch" | "daemon") {
+            bail!("'{}' cannot be used inside batch", args[1]);
+        }
+        if cmd.has_subcommands() {
+            let names: Vec<_> = cmd.get_subcommands().map(|c| c.get_name()).collect();
+            bail!(
+                "Incomplete batch command '{}': expected one of {}",
+                self.command,
+                names.join(", ")
+            );
+        }
+
+        let mut positionals: Vec<(usize, Vec<String>)> = Vec::new();
+        for (key, value) in &self.params {
+            let long = key.replace('_', "-");
+            let arg = cmd
+                .get_arguments()
+                .find(|a| a.get_id() == key.as_str() || a.get_long() == Some(long.as_str()))
+                .ok_or_else(|| {
+                    anyhow!("Unknown parameter '{}' for command '{}'", key, self.command)
+                })?;
+            let values = param_values(key, value)?;
+
+            if arg.is_positional() {
+                positionals.push((arg.get_index().unwrap_or(0), values));
+                continue;
+            }
+
+            let flag = match (arg.get_long(), arg.get_short()) {
+                (Some(long), _) => format!("--{long}"),
+                (None, Some(short)) => format!("-{short}"),
+                (None, None) => bail!("Parameter '{}' cannot be set in batch", key),
+            };
+            if arg.get_action().takes_values() {
+                for v in values {
+                    args.push(flag.clone());
+                    args.push(v);
+                }
+            } else if value.as_bool() == Some(true) {
+                args.push(flag);
+            }
+        }
+
+        // `--` keeps positional values such as "-pattern" from being read as flags
+        positionals.sort_by_key(|(index, _)| *index);
+        if !positionals.is_empty() {
+            args.push("--".to_string());
+        }
+        args.extend(positionals.into_iter().flat_map(|(_, values)| values));
+
+        Ok(args)
+    }
+}
+
+/// Subcommand paths that write files; a path covers all of its subcommands
+const MUTATING: &[&[&str]] = &[
+    &["actions", "apply"],
+    &["check-edit"],
+    &["config", "edit"],
+    &["config", "init"],
+    &["edit"],
+    &["fix"],
+    &["format"],
+    &["init"],
+    &["rename"],
+    &["undo"],
+];
+
+impl BatchCommand {
+    /// Whether the command may write files (dry runs included)
+    fn is_mutating(&self) -> bool {
+        let Ok(args) = self.to_args() else {
+            return false;
+        };
+        let path: Vec<&str> = args[1..]
+            .iter()
+            .map(String::as_str)
+            .take_while(|a| !a.starts_with('-'))
+            .collect();
+        MUTATING.iter().any(|m| path.starts_with(m))
+    }
+
+    /// Steps whose results this command references
+    fn dependencies(&self) -> Vec<usize> {
+        let mut steps: Vec<usize> = self
+            .params
+            .values()
+            .flat_map(|value| match value {
+                Value::Array(items) => items.iter().collect(),
+                value => vec![value],
+            })
+            .filter_map(Value::as_str)
+            .filter(|s| ResultRef::is_reference(s))
+            .filter_map(|s| ResultRef::parse(s).ok())
+            .map(|r| r.step)
+            .collect();
+        steps.sort_unstable();
+        steps.dedup();
+        steps
+    }
+
+    /// Substitute result references with values from earlier steps.
+    ///
+    /// A reference inside an array parameter splices all of its values into
+    /// that array. A top-level `[*]` reference fans the step out into one
+    /// command per item; several such references are zipped together.
+    fn resolve(&self, index: usize, outcomes: &[Option<StepOutcome>]) -> Result<Resolved> {
+        let reference = |s: &str| -> Result<(ResultRef, Vec<Value>)> {
+            let r = ResultRef::parse(s)?;
+            let values = r.resolve(step_result(outcomes, index, r.step)?, s)?;
+            Ok((r, values))
+        };
+
+        let mut fixed = Map::new();
+        let mut fanned: Vec<(String, Vec<Value>)> = Vec::new();
+        for (key, value) in &self.params {
+            match value {
+                Value::String(s) if ResultRef::is_reference(s) => {
+                    let (r, mut values) = reference(s)?;
+                    if r.fans_out() {
+                        fanned.push((key.clone(), values));
+                    } else {
+                        fixed.insert(key.clone(), values.remove(0));
+                    }
+                }
+                Value::Array(items) => {
+                    let mut spliced = Vec::with_capacity(items.len());
+                    for item in items {
+                        match item.as_str() {
+                            Some(s) if ResultRef::is_reference(s) => {
+                                spliced.extend(reference(s)?.1);
+                            }
+                            _ => spliced.push(item.clone()),
+                        }
+                    }
+                    fixed.insert(key.clone(), Value::Array(spliced));
+                }
+                _ => {
+                    fixed.insert(key.clone(), value.clone());
+                }
+            }
+        }
+
+        if fanned.is_empty() {
+            return Ok(Resolved::Single(Self {
+                command: self.command.clone(),
+                params: fixed,
+            }));
+        }
+
+        let count = fanned[0].1.len();
+        if fanned.iter().any(|(_, values)| values.len() != count) {
+            bail!("Fan-out references in one step must expand to the same number of items");
+        }
+
+        let commands = (0..count)
+            .map(|i| {
+                let mut params = fixed.clone();
+                for (key, values) in &fanned {
+                    params.insert(key.clone(), values[i].clone());
+                }
+                Self {
+                    command: self.command.clone(),
+                    params,
+                }
+            })
+            .collect();
+        Ok(Resolved::FanOut(commands))
+    }
+}
+
+/// Convert a JSON parameter into CLI argument values
+fn param_values(key: &str, value: &Value) -> Result<Vec<String>> {
+    match value {
+        Value::Null => Ok(vec![]),
+        Value::String(s) => Ok(vec![s.clone()]),
+        Value::Number(n) => Ok(vec![n.to_string()]),
+        Value::Bool(b) => Ok(vec![b.to_string()]),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                Value::String(s) => Ok(s.clone()),
+                Value::Number(n) => Ok(n.to_string()),
+                Value::Bool(b) => Ok(b.to_string()),
+                _ => bail!("Parameter '{}' must contain only scalar values", key),
+            })
+            .collect(),
+        Value::Object(_) => bail!("Parameter '{}' cannot be an object", key),
+    }
+}
+
+/// Outcome of one batch step: its result data or an error message
+type StepOutcome = std::result::Result<Value, String>;
+
+/// A batch step after result references are substituted
+#[derive(Debug)]
+enum Resolved {
+    Single(BatchCommand),
+    /// One command per item of a `[*]` reference
+    FanOut(Vec<BatchCommand>),
+}
+
+/// Reference to an earlier step's result, e.g. `$0.definition`,
+/// `$1.references[0]` or `$1.references[*]`.
+#[derive(Debug, Clone, PartialEq)]
+struct ResultRef {
+    step: usize,
+    path: Vec<PathSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+    /// Every element of an array (`[*]`, `[]` or `.*`)
+    Each,
+}
+
+impl ResultRef {
+    /// Whether a parameter string is a reference (`$` followed by a step index)
+    fn is_reference(s: &str) -> bool {
+        s.strip_prefix('$')
+            .and_then(|rest| rest.chars().next())
+            .is_some_and(|c| c.is_ascii_digit())
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        let rest = s
+            .strip_prefix('$')
+            .ok_or_else(|| anyhow!("Invalid reference '{}'", s))?;
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let step = rest[..split]
+            .parse()
+            .map_err(|_| anyhow!("Invalid step index in reference '{}'", s))?;
+
+        let normalized = rest[split..]
+            .replace("[]", ".*")
+            .replace('[', ".")
+            .replace(']', "");
+        let path = match normalized.strip_prefix('.') {
+            Some(path) => path
+                .split('.')
+                .map(|segment| match segment {
+                    "" => bail!("Empty path segment in reference '{}'", s),
+                    "*" => Ok(PathSegment::Each),
+                    _ => Ok(segment
+                        .parse()
+                        .map(PathSegment::Index)
+                        .unwrap_or_else(|_| PathSegment::Key(segment.to_string()))),
+                })
+                .collect::<Result<_>>()?,
+            None if normalized.is_empty() => Vec::new(),
+            None => bail!("Invalid reference '{}': expected '.' after step index", s),
+        };
+
+        Ok(Self { step, path })
+    }
+
+    fn fans_out(&self) -> bool {
+        self.path.contains(&PathSegment::Each)
+    }
+
+    /// Walk the path, expanding every `Each` segment
+    fn resolve(&self, result: &Value, raw: &str) -> Result<Vec<Value>> {
+        let mut current = vec![result];
+        for segment in &self.path {
+            let mut next = Vec::with_capacity(current.len());
+            for value in current {
+                match (segment, value) {
+                    (PathSegment::Each, Value::Array(items)) => next.extend(items),
+                    (PathSegment::Each, _) => {
+                        bail!("Reference '{}' expands a value that is not an array", raw)
+                    }
+                    (PathSegment::Index(i), Value::Array(items)) => {
+                        next.push(items.get(*i).ok_or_else(|| {
+                            anyhow!("Reference '{}': index {} out of range", raw, i)
+                        })?)
+                    }
+                    (segment, Value::Object(map)) => {
+                        let key = match segment {
+                            PathSegment::Key(key) => key.clone(),
+                            PathSegment::Index(i) => i.to_string(),
+                            PathSegment::Each => unreachable!(),
+                        };
+                        next.push(
+                            map.get(&key).ok_or_else(|| {
+                                anyhow!("Reference '{}': no field '{}'", raw, key)
+                            })?,
+                        );
+                    }
+                    _ => bail!("Reference '{}' does not match the step result", raw),
+                }
+            }
+            current = next;
+        }
+
+        current
+            .into_iter()
+            .map(|value| match reference_value(value) {
+                Value::Null => bail!("Reference '{}' resolved to null", raw),
+                value => Ok(value),
+            })
+            .collect()
+    }
+}
+
+/// Convert a referenced value into a parameter value.
+///
+/// Locations (and objects carrying a `location`, such as symbols or callers)
+/// become `file:line:column` strings so they can be passed to any position
+/// argument.
+fn reference_value(value: &Value) -> Value {
+    let as_location = |v: &Value| -> Option<String> {
+        let file = v.get("file")?.as_str()?;
+        let line = v.get("line")?.as_u64()?;
+        let column = v.get("column").and_then(Value::as_u64).unwrap_or(1);
+        Some(format!("{}:{}:{}", file, line, column))
+    };
+
+    as_location(value)
+        .or_else(|| value.get("location").and_then(as_location))
+        .map(Value::String)
+        .unwrap_or_else(|| value.clone())
+}
+
+/// Look up the result of a referenced step
+fn step_result(outcomes: &[Option<StepOutcome>], index: usize, step: usize) -> Result<&Value> {
+    if step >= index {
+        bail!(
+            "Step {} can only reference earlier steps, not step {}",
+            index,
+            step
+        );
+    }
+    match outcomes.get(step) {
+        Some(Some(Ok(value))) => Ok(value),
+        Some(Some(Err(_))) => bail!("Referenced step {} failed", step),
+        _ => bail!("Referenced step {} did not run", step),
+    }
 }
 
 /// Result of a batch command execution
@@ -49,12 +437,31 @@ struct BatchResult {
     success: bool,
     /// Result data (if success)
     #[serde(skip_serializing_if = "Option::is_none")]
-    result: Option<serde_json::Value>,
+    result: Option<Value>,
     /// Error message (if failed)
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
 }
 
+impl BatchResult {
+    fn new(index: usize, outcome: StepOutcome) -> Self {
+        match outcome {
+            Ok(data) => Self {
+                index,
+                success: true,
+                result: Some(data),
+                error: None,
+            },
+            Err(e) => Self {
+                index,
+                success: false,
+                result: None,
+                error: Some(e),
+            },
+        }
+    }
+}
+
 /// Full batch response
 #[derive(Debug, Serialize)]
 struct BatchResponse {
@@ -71,14 +478,14 @@ struct BatchResponse {
 pub async fn execute(args: BatchArgs, app: &App) -> Result<()> {
     let ctx = &app.output;
 
-    // Read commands from stdin
+    // Read commands from stdin; malformed lines are reported as failed steps
     let stdin = io::stdin();
-    let commands: Vec<BatchCommand> = stdin
+    let commands: Vec<Result<BatchCommand, String>> = stdin
         .lock()
         .lines()
         .map_while(Result::ok)
         .filter(|line| !line.trim().is_empty())
-        .filter_map(|line| serde_json::from_str(&line).ok())
+        .map(|line| BatchCommand::parse(&line).map_err(|e| format!("{:#}", e)))
         .collect();
 
     if commands.is_empty() {
@@ -89,71 +496,29 @@ pub async fn execute(args: BatchArgs, app: &App) -> Result<()> {
         return Ok(());
     }
 
-    let mut results = Vec::with_capacity(commands.len());
+    let mut outcomes: Vec<Option<StepOutcome>> = vec![None; commands.len()];
 
-    if args.parallel {
-        // Execute commands in parallel using tokio::join_all
-        let futures: Vec<_> = commands
+    for wave in schedule(&commands, args.parallel) {
+        let futures = wave
             .iter()
-            .enumerate()
-            .map(|(i, cmd)| async move {
-                let result = execute_single_command(cmd, app).await;
-                (i, result)
-            })
-            .collect();
+            .map(|&i| run_step(i, &commands[i], &outcomes, args.parallel, app));
+        let done = futures::future::join_all(futures).await;
 
-        let parallel_results = futures::future::join_all(futures).await;
-
-        for (index, result) in parallel_results {
-            match result {
-                Ok(data) => {
-                    results.push(BatchResult {
-                        index,
-                        success: true,
-                        result: Some(data),
-                        error: None,
-                    });
-                }
-                Err(e) => {
-                    results.push(BatchResult {
-                        index,
-                        success: false,
-                        result: None,
-                        error: Some(e.to_string()),
-                    });
-                    if args.fail_fast {
-                        break;
-                    }
-                }
-            }
+        let failed = done.iter().any(Result::is_err);
+        for (&i, outcome) in wave.iter().zip(done) {
+            outcomes[i] = Some(outcome);
         }
-    } else {
-        // Execute commands sequentially
-        for (index, cmd) in commands.iter().enumerate() {
-            match execute_single_command(cmd, app).await {
-                Ok(data) => {
-                    results.push(BatchResult {
-                        index,
-                        success: true,
-                        result: Some(data),
-                        error: None,
-                    });
-                }
-                Err(e) => {
-                    results.push(BatchResult {
-                        index,
-                        success: false,
-                        result: None,
-                        error: Some(e.to_string()),
-                    });
-                    if args.fail_fast {
-                        break;
-                    }
-                }
-            }
+        if failed && args.fail_fast {
+            break;
         }
     }
 
+    let results: Vec<_> = outcomes
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, outcome)| outcome.map(|o| BatchResult::new(index, o)))
+        .collect();
+
     let successes = results.iter().filter(|r| r.success).count();
     let failures = results.len() - successes;
 
@@ -169,196 +534,366 @@ pub async fn execute(args: BatchArgs, app: &App) -> Result<()> {
     Ok(())
 }
 
-/// Execute a single batch command
-async fn execute_single_command(
-    cmd: &BatchCommand,
-    app: &App,
-) -> Result<serde_json::Value, anyhow::Error> {
-    let ctx = &app.output;
-
-    match cmd {
-        BatchCommand::FindSymbol { file } => {
-            let path = std::env::current_dir()?.join(file);
-            let symbols = app
-                .lsp
-                .find_symbols(&path, FindSymbolsOptions::default())
-                .await?;
+/// Group steps into waves that can run concurrently.
+///
+/// Sequential batches run one step per wave. Parallel batches place each
+/// step one wave after the latest step it references, so independent steps
+/// still run together. Steps that write files are barriers: they run alone,
+/// after every earlier step and before every later one, so two writes never
+/// read-modify-write the same file (or the undo journal) at once.
+fn schedule(commands: &[Result<BatchCommand, String>], parallel: bool) -> Vec<Vec<usize>> {
+    if !parallel {
+        return (0..commands.len()).map(|i| vec![i]).collect();
+    }
 
-            Ok(serde_json::json!({
-                "count": symbols.len(),
-                "symbols": symbols.iter().map(|s| serde_json::json!({
-                    "name": s.name,
-                    "kind": s.kind.to_string(),
-                    "file": s.location.file.display().to_string(),
-                    "line": s.location.line,
-                    "column": s.location.column,
-                })).collect::<Vec<_>>()
-            }))
+    let mut levels: Vec<usize> = Vec::with_capacity(commands.len());
+    let mut waves: Vec<Vec<usize>> = Vec::new();
+    // First wave the next step may join
+    let mut barrier = 0;
+    for (i, cmd) in commands.iter().enumerate() {
+        let mut level = cmd
+            .as_ref()
+            .ok()
+            .and_then(|c| {
+                c.dependencies()
+                    .into_iter()
+                    .filter(|&d| d < i)
+                    .map(|d| levels[d] + 1)
+                    .max()
+            })
+            .unwrap_or(0)
+            .max(barrier);
+        if cmd.as_ref().is_ok_and(BatchCommand::is_mutating) {
+            level = level.max(waves.len());
+            barrier = level + 1;
+        }
+        levels.push(level);
+        if waves.len() <= level {
+            waves.resize_with(level + 1, Vec::new);
         }
+        waves[level].push(i);
+    }
+    waves
+}
 
-        BatchCommand::FindRefs { location } => {
-            let loc = ParsedLocation::parse(location)?.to_absolute()?;
-            let refs = app
-                .lsp
-                .find_references(&loc.file, loc.line, loc.column)
-                .await?;
-
-            // Filter to project-only references
-            let project_refs: Vec<_> = refs
-                .iter()
-                .filter(|l| ctx.is_project_path(&l.file))
-                .collect();
+/// Resolve a step's references and run it, once or once per fan-out item
+async fn run_step(
+    index: usize,
+    step: &Result<BatchCommand, String>,
+    outcomes: &[Option<StepOutcome>],
+    parallel: bool,
+    app: &App,
+) -> StepOutcome {
+    let cmd = step.as_ref().map_err(Clone::clone)?;
+    // Fanned-out writes run one at a time, like separate writing steps
+    let parallel = parallel && !cmd.is_mutating();
+    let resolved = cmd
+        .resolve(index, outcomes)
+        .map_err(|e| format!("{:#}", e))?;
+
+    match resolved {
+        Resolved::Single(cmd) => execute_single_command(&cmd, app)
+            .await
+            .map_err(|e| e.to_string()),
+        Resolved::FanOut(cmds) => {
+            let mut done = Vec::with_capacity(cmds.len());
+            if parallel {
+                let futures = cmds.iter().map(|c| execute_single_command(c, app));
+                done = futures::future::join_all(futures).await;
+            } else {
+                for c in &cmds {
+                    done.push(execute_single_command(c, app).await);
+                }
+            }
 
+            let results: Vec<_> = done
+                .into_iter()
+                .enumerate()
+                .map(|(i, r)| BatchResult::new(i, r.map_err(|e| e.to_string())))
+                .collect();
             Ok(serde_json::json!({
-                "count": project_refs.len(),
-                "references": project_refs.iter().map(|r| serde_json::json!({
-                    "file": r.file.display().to_string(),
-                    "line": r.line,
-                    "column": r.column,
-                })).collect::<Vec<_>>()
+                "count": results.len(),
+                "results": results,
             }))
         }
+    }
+}
 
-        BatchCommand::FindDef { location } => {
-            let loc = ParsedLocation::parse(location)?.to_absolute()?;
-            let def = app
-                .lsp
-                .goto_definition(&loc.file, loc.line, loc.column)
-                .await?;
-
-            match def {
-                Some(d) => Ok(serde_json::json!({
-                    "definition": {
-                        "file": d.file.display().to_string(),
-                        "line": d.line,
-                        "column": d.column,
-                    }
-                })),
-                None => Ok(serde_json::json!({
-                    "definition": null,
-                    "message": "No definition found"
-                })),
-            }
+/// Execute a single batch command through the regular CLI dispatcher.
+///
+/// The step runs against a clone of `app` that shares its LSP service (and
+/// daemon connections) but captures output, so the result is exactly what
+/// the command prints on its own.
+async fn execute_single_command(cmd: &BatchCommand, app: &App) -> Result<Value> {
+    let cli = Cli::try_parse_from(cmd.to_args()?)
+        .map_err(|e| anyhow!(e.render().to_string().trim().to_string()))?;
+
+    let (step_app, capture) = app.with_captured_output();
+    execute_command(cli.command, &step_app).await?;
+
+    let mut value = capture.take_last().unwrap_or(Value::Null);
+    if let Some(obj) = value.as_object_mut() {
+        if obj.get("success") == Some(&Value::Bool(false)) {
+            let error = obj
+                .get("error")
+                .and_then(Value::as_str)
+                .unwrap_or("Command failed");
+            bail!("{}", error);
         }
+        obj.shift_remove("success");
+    }
+    Ok(value)
+}
 
-        BatchCommand::FindTypedef { location } => {
-            let loc = ParsedLocation::parse(location)?.to_absolute()?;
-            let def = app
-                .lsp
-                .goto_type_definition(&loc.file, loc.line, loc.column)
-                .await?;
-
-            match def {
-                Some(d) => Ok(serde_json::json!({
-                    "definition": {
-                        "file": d.file.display().to_string(),
-                        "line": d.line,
-                        "column": d.column,
-                    }
-                })),
-                None => Ok(serde_json::json!({
-                    "definition": null,
-                    "message": "No type definition found"
-                })),
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        BatchCommand::FindImpl { location } => {
-            let loc = ParsedLocation::parse(location)?.to_absolute()?;
-            let impls = app
-                .lsp
-                .find_implementations(&loc.file, loc.line, loc.column)
-                .await?;
+    fn args(line: &str) -> Vec<String> {
+        BatchCommand::parse(line).unwrap().to_args().unwrap()
+    }
 
-            Ok(serde_json::json!({
-                "count": impls.len(),
-                "implementations": impls.iter().map(|i| serde_json::json!({
-                    "file": i.file.display().to_string(),
-                    "line": i.line,
-                    "column": i.column,
-                })).collect::<Vec<_>>()
-            }))
-        }
+    #[test]
+    fn test_to_args_positional() {
+        assert_eq!(
+            args(r#"{"command": "find_refs", "location": "src/main.rs:10:5", "limit": 20}"#),
+            vec![
+                "symora",
+                "find",
+                "refs",
+                "--limit",
+                "20",
+                "--",
+                "src/main.rs:10:5"
+            ]
+        );
+    }
 
-        BatchCommand::Hover { location } => {
-            let loc = ParsedLocation::parse(location)?.to_absolute()?;
-            let hover = app.lsp.hover(&loc.file, loc.line, loc.column).await?;
-
-            match hover {
-                Some(h) => Ok(serde_json::json!({
-                    "content": h.content,
-                })),
-                None => Ok(serde_json::json!({
-                    "content": null,
-                    "message": "No hover information"
-                })),
-            }
-        }
+    #[test]
+    fn test_to_args_multi_word_subcommand() {
+        assert_eq!(
+            args(
+                r#"{"command": "edit_insert_after", "file": "a.rs", "symbol": "main", "text": "x"}"#
+            ),
+            vec![
+                "symora",
+                "edit",
+                "insert-after",
+                "--symbol",
+                "main",
+                "--text",
+                "x",
+                "--",
+                "a.rs"
+            ]
+        );
+    }
 
-        BatchCommand::Diagnostics { file } => {
-            let path = std::env::current_dir()?.join(file);
-            let diags = app.lsp.diagnostics(&path).await?;
+    #[test]
+    fn test_to_args_flags_and_spaces() {
+        assert_eq!(
+            args(
+                r#"{"command": "search text", "pattern": "-x", "ignore_case": true, "word": false}"#
+            ),
+            vec!["symora", "search", "text", "--ignore-case", "--", "-x"]
+        );
+    }
 
-            Ok(serde_json::json!({
-                "count": diags.len(),
-                "diagnostics": diags.iter().map(|d| serde_json::json!({
-                    "message": d.message,
-                    "severity": format!("{:?}", d.severity),
-                    "line": d.range.start.line + 1,
-                    "column": d.range.start.character + 1,
-                })).collect::<Vec<_>>()
-            }))
-        }
+    #[test]
+    fn test_to_args_rejects_unknown() {
+        let err = |line: &str| {
+            BatchCommand::parse(line)
+                .and_then(|c| c.to_args())
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(err(r#"{"command": "nope"}"#).contains("Unknown batch command"));
+        assert!(err(r#"{"command": "find"}"#).contains("Incomplete batch command"));
+        assert!(err(r#"{"command": "hover", "bogus": 1}"#).contains("Unknown parameter"));
+        assert!(err(r#"{"command": "batch"}"#).contains("cannot be used inside batch"));
+        assert!(err(r#"{"location": "a.rs:1:1"}"#).contains("\"command\""));
+    }
 
-        BatchCommand::Rename { location, new_name } => {
-            let loc = ParsedLocation::parse(location)?.to_absolute()?;
-            let result = app
-                .lsp
-                .rename(&loc.file, loc.line, loc.column, new_name)
-                .await?;
+    #[test]
+    fn test_to_args_parse_roundtrip() {
+        let cmd = BatchCommand::parse(
+            r#"{"command": "diagnostics", "file": "src/main.rs", "severity": ["error", "warning"]}"#,
+        )
+        .unwrap();
+        assert!(Cli::try_parse_from(cmd.to_args().unwrap()).is_ok());
+
+        let cmd = BatchCommand::parse(
+            r#"{"command": "format", "target": "src/main.rs:3-9", "dry_run": true}"#,
+        )
+        .unwrap();
+        assert!(Cli::try_parse_from(cmd.to_args().unwrap()).is_ok());
+
+        let cmd = BatchCommand::parse(
+            r#"{"command": "fix", "paths": ["src/a.ts", "web"], "kinds": ["organizeImports", "fixAll"]}"#,
+        )
+        .unwrap();
+        assert!(Cli::try_parse_from(cmd.to_args().unwrap()).is_ok());
+
+        let cmd = BatchCommand::parse(
+            r#"{"command": "find_occurrences", "location": "src/main.rs:10:5", "kind": ["write"]}"#,
+        )
+        .unwrap();
+        assert!(Cli::try_parse_from(cmd.to_args().unwrap()).is_ok());
+
+        let cmd = BatchCommand::parse(
+            r#"{"command": "find_def", "location": "src/main.rs:10:5", "all": true}"#,
+        )
+        .unwrap();
+        assert!(Cli::try_parse_from(cmd.to_args().unwrap()).is_ok());
+
+        let cmd = BatchCommand::parse(
+            r#"{"command": "tokens", "file": "src/main.rs", "range": "10-40", "modifier": ["mutable"]}"#,
+        )
+        .unwrap();
+        assert!(Cli::try_parse_from(cmd.to_args().unwrap()).is_ok());
+    }
 
-            Ok(serde_json::json!({
-                "changes": result.changes.iter().map(|c| serde_json::json!({
-                    "file": c.file.display().to_string(),
-                    "edit_count": c.edit_count,
-                })).collect::<Vec<_>>()
-            }))
-        }
+    fn outcomes() -> Vec<Option<StepOutcome>> {
+        vec![
+            Some(Ok(serde_json::json!({
+                "definition": {"file": "src/lib.rs", "line": 3, "column": 8},
+            }))),
+            Some(Ok(serde_json::json!({
+                "count": 2,
+                "references": [
+                    {"file": "src/a.rs", "line": 1, "column": 2},
+                    {"file": "src/b.rs", "line": 5, "column": 9},
+                ],
+            }))),
+            Some(Err("boom".to_string())),
+        ]
+    }
 
-        BatchCommand::CallsIncoming { location } => {
-            let loc = ParsedLocation::parse(location)?.to_absolute()?;
-            let calls = app
-                .lsp
-                .incoming_calls(&loc.file, loc.line, loc.column)
-                .await?;
+    #[test]
+    fn test_result_ref_parse() {
+        assert!(ResultRef::is_reference("$0.definition"));
+        assert!(!ResultRef::is_reference("$HOME"));
+        assert!(!ResultRef::is_reference("src/main.rs:1:1"));
+
+        let r = ResultRef::parse("$1.references[*].file").unwrap();
+        assert_eq!(r.step, 1);
+        assert_eq!(
+            r.path,
+            vec![
+                PathSegment::Key("references".to_string()),
+                PathSegment::Each,
+                PathSegment::Key("file".to_string()),
+            ]
+        );
+        assert!(r.fans_out());
+        assert_eq!(
+            ResultRef::parse("$2.items[0]").unwrap().path[1],
+            PathSegment::Index(0)
+        );
+        assert!(ResultRef::parse("$0").unwrap().path.is_empty());
+        assert!(ResultRef::parse("$0..x").is_err());
+        assert!(ResultRef::parse("$0x").is_err());
+    }
 
-            Ok(serde_json::json!({
-                "count": calls.len(),
-                "calls": calls.iter().map(|c| serde_json::json!({
-                    "name": c.name,
-                    "file": c.location.file.display().to_string(),
-                    "line": c.location.line,
-                    "column": c.location.column,
-                })).collect::<Vec<_>>()
-            }))
-        }
+    #[test]
+    fn test_resolve_single_reference() {
+        let cmd =
+            BatchCommand::parse(r#"{"command": "hover", "location": "$0.definition"}"#).unwrap();
+        assert_eq!(cmd.dependencies(), vec![0]);
+        let Resolved::Single(cmd) = cmd.resolve(3, &outcomes()).unwrap() else {
+            panic!("expected a single command");
+        };
+        assert_eq!(cmd.params["location"], "src/lib.rs:3:8");
+    }
 
-        BatchCommand::CallsOutgoing { location } => {
-            let loc = ParsedLocation::parse(location)?.to_absolute()?;
-            let calls = app
-                .lsp
-                .outgoing_calls(&loc.file, loc.line, loc.column)
-                .await?;
+    #[test]
+    fn test_resolve_fan_out() {
+        let cmd = BatchCommand::parse(
+            r#"{"command": "find_refs", "location": "$1.references[*]", "limit": 5}"#,
+        )
+        .unwrap();
+        assert_eq!(cmd.dependencies(), vec![1]);
+
+        let Resolved::FanOut(cmds) = cmd.resolve(3, &outcomes()).unwrap() else {
+            panic!("expected fan-out");
+        };
+        let locations: Vec<_> = cmds.iter().map(|c| c.params["location"].clone()).collect();
+        assert_eq!(locations, vec!["src/a.rs:1:2", "src/b.rs:5:9"]);
+        assert!(cmds.iter().all(|c| c.params["limit"] == 5));
+    }
 
-            Ok(serde_json::json!({
-                "count": calls.len(),
-                "calls": calls.iter().map(|c| serde_json::json!({
-                    "name": c.name,
-                    "file": c.location.file.display().to_string(),
-                    "line": c.location.line,
-                    "column": c.location.column,
-                })).collect::<Vec<_>>()
-            }))
-        }
+    #[test]
+    fn test_resolve_splices_arrays() {
+        let cmd = BatchCommand::parse(
+            r#"{"command": "inspect_selection", "locations": ["$0.definition", "$1.references[*]"]}"#,
+        )
+        .unwrap();
+        let Resolved::Single(cmd) = cmd.resolve(3, &outcomes()).unwrap() else {
+            panic!("expected a single command");
+        };
+        assert_eq!(
+            cmd.params["locations"],
+            serde_json::json!(["src/lib.rs:3:8", "src/a.rs:1:2", "src/b.rs:5:9"])
+        );
+    }
+
+    #[test]
+    fn test_resolve_errors() {
+        let err = |line: &str, index: usize| {
+            let cmd = BatchCommand::parse(line).unwrap();
+            format!("{:#}", cmd.resolve(index, &outcomes()).unwrap_err())
+        };
+
+        assert!(err(r#"{"command": "hover", "location": "$2.x"}"#, 3).contains("failed"));
+        assert!(err(r#"{"command": "hover", "location": "$1.count"}"#, 1).contains("earlier"));
+        assert!(err(r#"{"command": "hover", "location": "$0.missing"}"#, 3).contains("no field"));
+        assert!(err(r#"{"command": "hover", "location": "$0[*]"}"#, 3).contains("not an array"));
+    }
+
+    #[test]
+    fn test_schedule_waves() {
+        let commands: Vec<_> = [
+            r#"{"command": "find_def", "location": "a.rs:1:1"}"#,
+            r#"{"command": "find_refs", "location": "$0.definition"}"#,
+            r#"{"command": "hover", "location": "b.rs:1:1"}"#,
+            r#"{"command": "hover", "location": "$1.references[*]"}"#,
+        ]
+        .iter()
+        .map(|line| BatchCommand::parse(line).map_err(|e| e.to_string()))
+        .collect();
+
+        assert_eq!(
+            schedule(&commands, true),
+            vec![vec![0, 2], vec![1], vec![3]]
+        );
+        assert_eq!(schedule(&commands, false).len(), 4);
+    }
+
+    #[test]
+    fn test_schedule_mutating_barriers() {
+        let commands: Vec<_> = [
+            r#"{"command": "hover", "location": "a.rs:1:1"}"#,
+            r#"{"command": "edit_insert_after", "location": "a.rs:1:1", "text": "x"}"#,
+            r#"{"command": "format", "target": "a.rs", "dry_run": true}"#,
+            r#"{"command": "hover", "location": "b.rs:1:1"}"#,
+            r#"{"command": "find_refs", "location": "b.rs:1:1"}"#,
+            r#"{"command": "rename", "location": "b.rs:1:1", "new_name": "y"}"#,
+        ]
+        .iter()
+        .map(|line| BatchCommand::parse(line).map_err(|e| e.to_string()))
+        .collect();
+
+        let mutating: Vec<_> = commands
+            .iter()
+            .map(|c| c.as_ref().unwrap().is_mutating())
+            .collect();
+        assert_eq!(mutating, [false, true, true, false, false, true]);
+
+        // Writes run alone; the reads between them still run together
+        assert_eq!(
+            schedule(&commands, true),
+            vec![vec![0], vec![1], vec![2], vec![3, 4], vec![5]]
+        );
     }
 }
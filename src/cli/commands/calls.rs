@@ -21,7 +21,7 @@ pub struct CallsArgs {
 pub enum CallsCommand {
     /// Find incoming calls (who calls this function?)
     Incoming {
-        /// File path with position (file:line:column)
+        /// Location (file:line:column, file#Class/method or Class/method)
         location: String,
 
         /// Maximum results (default from config: lsp.calls_limit)
@@ -31,7 +31,7 @@ pub enum CallsCommand {
 
     /// Find outgoing calls (what does this function call?)
     Outgoing {
-        /// File path with position (file:line:column)
+        /// Location (file:line:column, file#Class/method or Class/method)
         location: String,
 
         /// Maximum results (default from config: lsp.calls_limit)
@@ -80,7 +80,7 @@ async fn execute_calls(
     app: &App,
 ) -> Result<()> {
     let ctx = &app.output;
-    let loc = ParsedLocation::parse(location)?.to_absolute()?;
+    let loc = ParsedLocation::resolve(location, app).await?;
 
     let result = match direction {
         Direction::Incoming => {
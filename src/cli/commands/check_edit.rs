@@ -0,0 +1,229 @@
+//! Check-edit command implementation
+//!
+//! Run an editing command, then compare diagnostics of the files it changed
+//! before and after, reporting only introduced and resolved ones.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use anyhow::{Result, anyhow};
+use clap::{Args, Parser};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::app::App;
+use crate::cli::commands::diagnostics::{compare, file_output};
+use crate::cli::response::{DiagnosticsDiffOutput, FileDiagnosticsOutput};
+use crate::cli::{Cli, Commands};
+use crate::models::diagnostic::DiagnosticsReport;
+use crate::models::symbol::Language;
+use crate::services::diff::line_map;
+use crate::services::journal::JournalEntry;
+
+#[derive(Args, Debug)]
+pub struct CheckEditArgs {
+    /// Editing command to run, e.g. `rename src/lib.rs:10:5 new_name`
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    pub command: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct CheckEditResponse {
+    command: String,
+    files: Vec<String>,
+    /// Output of the editing command
+    result: Value,
+    /// Some diagnostics did not arrive in time and may be outdated
+    stale: bool,
+    #[serde(flatten)]
+    diff: DiagnosticsDiffOutput,
+}
+
+/// Content of one changed file before the first and after the last entry
+struct ChangedFile {
+    before: Option<String>,
+    after: Option<String>,
+}
+
+pub async fn execute(args: CheckEditArgs, app: &App) -> Result<()> {
+    let ctx = &app.output;
+    let command = args.command.join(" ");
+
+    let cli = Cli::try_parse_from(
+        std::iter::once("symora").chain(args.command.iter().map(String::as_str)),
+    )
+    .map_err(|e| anyhow!(e.render().to_string().trim().to_string()))?;
+    if matches!(
+        cli.command,
+        Commands::Batch(_) | Commands::Daemon(_) | Commands::CheckEdit(_)
+    ) {
+        ctx.print_error(&format!(
+            "'{}' cannot be used with check-edit",
+            args.command[0]
+        ));
+        return Ok(());
+    }
+
+    let journal = app.journal();
+    let last_id = journal.entries()?.last().map_or(0, |e| e.id);
+
+    let (step_app, capture) = app.with_captured_output();
+    crate::cli::execute_command(cli.command, &step_app).await?;
+    let mut result = capture.take_last().unwrap_or(Value::Null);
+    if let Some(obj) = result.as_object_mut() {
+        if obj.get("success") == Some(&Value::Bool(false)) {
+            let error = obj
+                .get("error")
+                .and_then(Value::as_str)
+                .unwrap_or("Command failed");
+            ctx.print_error(error);
+            return Ok(());
+        }
+        obj.shift_remove("success");
+    }
+
+    let entries: Vec<JournalEntry> = journal
+        .entries()?
+        .into_iter()
+        .filter(|e| e.id > last_id)
+        .collect();
+    let changed = changed_files(&entries);
+
+    let source_files = |content: fn(&ChangedFile) -> &Option<String>| {
+        changed
+            .iter()
+            .filter(|(path, _)| Language::from_path(&app.root().join(path)) != Language::Unknown)
+            .filter_map(|(path, file)| content(file).clone().map(|c| (app.root().join(path), c)))
+            .collect::<Vec<(PathBuf, String)>>()
+    };
+    let before_docs = source_files(|f| &f.before);
+    let after_docs = source_files(|f| &f.after);
+
+    let collected = async {
+        let before = app.lsp.content_diagnostics(&before_docs).await?;
+        let after = app.lsp.content_diagnostics(&after_docs).await?;
+        Ok::<_, crate::error::LspError>((before, after))
+    };
+    let (before, after) = match collected.await {
+        Ok(reports) => reports,
+        Err(e) => {
+            ctx.print_error(&format!(
+                "Edit applied, but collecting diagnostics failed: {}",
+                e
+            ));
+            return Ok(());
+        }
+    };
+
+    let maps: HashMap<&str, Vec<usize>> = changed
+        .iter()
+        .filter_map(|(path, f)| {
+            Some((
+                path.as_str(),
+                line_map(f.before.as_ref()?, f.after.as_ref()?),
+            ))
+        })
+        .collect();
+    let shift = |file: &str, line: u32| match maps.get(file) {
+        Some(map) => shifted_line(map, line),
+        None => line,
+    };
+
+    let stale = before.iter().chain(&after).any(|r| r.stale);
+    let diff = compare(
+        outputs(app, &before_docs, &before),
+        outputs(app, &after_docs, &after),
+        shift,
+    );
+
+    ctx.print_success_flat(CheckEditResponse {
+        command,
+        files: changed.into_keys().collect(),
+        result,
+        stale,
+        diff,
+    });
+
+    Ok(())
+}
+
+/// Merge journal entries, oldest first, into one change per file
+fn changed_files(entries: &[JournalEntry]) -> BTreeMap<String, ChangedFile> {
+    let mut changed: BTreeMap<String, ChangedFile> = BTreeMap::new();
+    for entry in entries {
+        for file in &entry.files {
+            changed
+                .entry(file.path.clone())
+                .and_modify(|c| c.after = file.after.clone())
+                .or_insert_with(|| ChangedFile {
+                    before: file.before.clone(),
+                    after: file.after.clone(),
+                });
+        }
+    }
+    changed
+}
+
+/// 1-indexed `line` of the old text mapped through `map` from [`line_map`]
+fn shifted_line(map: &[usize], line: u32) -> u32 {
+    let index = line.saturating_sub(1) as usize;
+    let new_index = match map.get(index) {
+        Some(&n) => n,
+        // Past the end of the old text: keep the distance from the end
+        None => {
+            let end = map.len() - 1;
+            map[end] + (index - end)
+        }
+    };
+    new_index as u32 + 1
+}
+
+fn outputs(
+    app: &App,
+    docs: &[(PathBuf, String)],
+    reports: &[DiagnosticsReport],
+) -> Vec<FileDiagnosticsOutput> {
+    docs.iter()
+        .zip(reports)
+        .map(|((path, _), report)| file_output(app.output.relative_path(path), &report.diagnostics))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::journal::JournalFile;
+
+    fn entry(id: u64, path: &str, before: Option<&str>, after: Option<&str>) -> JournalEntry {
+        JournalEntry {
+            id,
+            timestamp: String::new(),
+            command: String::new(),
+            files: vec![JournalFile {
+                path: path.to_string(),
+                before: before.map(String::from),
+                after: after.map(String::from),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_changed_files_merges_entries() {
+        let changed = changed_files(&[
+            entry(1, "a.rs", Some("v1"), Some("v2")),
+            entry(2, "a.rs", Some("v2"), Some("v3")),
+            entry(3, "b.rs", None, Some("new")),
+        ]);
+        assert_eq!(changed["a.rs"].before.as_deref(), Some("v1"));
+        assert_eq!(changed["a.rs"].after.as_deref(), Some("v3"));
+        assert_eq!(changed["b.rs"].before, None);
+    }
+
+    #[test]
+    fn test_shifted_line() {
+        let map = line_map("a\nb\nc\n", "x\na\nc\n");
+        assert_eq!(shifted_line(&map, 1), 2);
+        assert_eq!(shifted_line(&map, 3), 3);
+        assert_eq!(shifted_line(&map, 6), 6);
+    }
+}
@@ -1,18 +1,26 @@
 //! Diagnostics command implementation
 
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Args;
+use serde::Serialize;
 
 use crate::app::App;
-use crate::cli::response::{DiagnosticOutput, DiagnosticsResponse};
-use crate::models::diagnostic::DiagnosticSeverity;
+use crate::cli::response::{
+    DiagnosticOutput, DiagnosticsDiffOutput, DiagnosticsResponse, FileDiagnosticsOutput,
+    LanguageErrorOutput, SeverityCounts, WorkspaceDiagnosticsResponse,
+};
+use crate::infra::file_filter::{FileFilter, FileFilterConfig};
+use crate::infra::lsp::servers;
+use crate::models::diagnostic::{Diagnostic, DiagnosticSeverity};
+use crate::models::symbol::Language;
 
 #[derive(Args, Debug)]
 pub struct DiagnosticsArgs {
-    /// File path to check
-    pub file: PathBuf,
+    /// File or directory to check (default: the whole project)
+    pub file: Option<PathBuf>,
 
     /// Filter by severity (error, warning, info, hint)
     #[arg(long, short = 's', value_delimiter = ',')]
@@ -21,60 +29,124 @@ pub struct DiagnosticsArgs {
     /// Filter by source (e.g., rust-analyzer, eslint)
     #[arg(long)]
     pub source: Option<String>,
+
+    /// Saved JSON output of an earlier run; report only introduced and
+    /// resolved diagnostics
+    #[arg(long)]
+    pub baseline: Option<PathBuf>,
 }
 
-pub async fn execute(args: DiagnosticsArgs, app: &App) -> Result<()> {
-    let ctx = &app.output;
+/// Single-file diagnostics compared with a baseline
+#[derive(Serialize)]
+struct BaselineResponse {
+    file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<u32>,
+    stale: bool,
+    #[serde(flatten)]
+    diff: DiagnosticsDiffOutput,
+}
+
+/// Project or directory diagnostics compared with a baseline
+#[derive(Serialize)]
+struct WorkspaceBaselineResponse {
+    checked: usize,
+    #[serde(flatten)]
+    diff: DiagnosticsDiffOutput,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<LanguageErrorOutput>,
+}
+
+type FilesByLanguage = Vec<(Language, Vec<PathBuf>)>;
+
+struct DiagnosticFilter {
+    severity: Option<Vec<DiagnosticSeverity>>,
+    source: Option<String>,
+}
+
+impl DiagnosticFilter {
+    fn new(args: &DiagnosticsArgs) -> Self {
+        Self {
+            severity: args.severity.as_ref().map(|sevs| {
+                sevs.iter()
+                    .filter_map(|s| s.parse::<DiagnosticSeverity>().ok())
+                    .collect()
+            }),
+            source: args.source.clone(),
+        }
+    }
 
-    let abs_file = if args.file.is_absolute() {
-        args.file.clone()
-    } else {
-        app.root().join(&args.file)
+    fn matches(&self, d: &Diagnostic) -> bool {
+        self.accepts(Some(d.severity), d.source.as_deref())
+    }
+
+    fn matches_output(&self, d: &DiagnosticOutput) -> bool {
+        self.accepts(d.severity.parse().ok(), d.source.as_deref())
+    }
+
+    fn accepts(&self, severity: Option<DiagnosticSeverity>, source: Option<&str>) -> bool {
+        if let Some(ref filter) = self.severity
+            && !severity.is_some_and(|s| filter.contains(&s))
+        {
+            return false;
+        }
+        if let Some(ref wanted) = self.source
+            && source != Some(wanted.as_str())
+        {
+            return false;
+        }
+        true
+    }
+}
+
+pub async fn execute(args: DiagnosticsArgs, app: &App) -> Result<()> {
+    let target = match &args.file {
+        Some(file) if file.is_absolute() => file.clone(),
+        Some(file) => app.root().join(file),
+        None => app.root().to_path_buf(),
     };
+    if target.is_dir() {
+        return execute_workspace(&args, &target, app).await;
+    }
 
-    // Parse severity filters
-    let severity_filter: Option<Vec<DiagnosticSeverity>> = args.severity.as_ref().map(|sevs| {
-        sevs.iter()
-            .filter_map(|s| s.parse::<DiagnosticSeverity>().ok())
-            .collect()
-    });
+    let ctx = &app.output;
+    let filter = DiagnosticFilter::new(&args);
 
-    match app.lsp.diagnostics(&abs_file).await {
-        Ok(diagnostics) => {
-            let filtered: Vec<_> = diagnostics
+    match app.lsp.diagnostics(&target).await {
+        Ok(report) => {
+            let filtered: Vec<_> = report
+                .diagnostics
                 .into_iter()
-                .filter(|d| {
-                    if let Some(ref filter) = severity_filter
-                        && !filter.contains(&d.severity)
-                    {
-                        return false;
-                    }
-                    if let Some(ref source) = args.source
-                        && d.source.as_ref() != Some(source)
-                    {
-                        return false;
-                    }
-                    true
-                })
+                .filter(|d| filter.matches(d))
                 .collect();
 
+            let file = ctx.relative_path(&target);
+            if let Some(path) = &args.baseline {
+                let baseline = match load_baseline(path, &filter, |f| f == file) {
+                    Ok(baseline) => baseline,
+                    Err(e) => {
+                        ctx.print_error(&format!("{:#}", e));
+                        return Ok(());
+                    }
+                };
+                let current = vec![file_output(file.clone(), &filtered)];
+                ctx.print_success_flat(BaselineResponse {
+                    file,
+                    version: report.version,
+                    stale: report.stale,
+                    diff: compare(baseline, current, |_, line| line),
+                });
+                return Ok(());
+            }
+
             let response = DiagnosticsResponse {
-                file: ctx.relative_path(&args.file),
+                file,
                 count: filtered.len(),
-                diagnostics: filtered
-                    .iter()
-                    .map(|d| DiagnosticOutput {
-                        severity: d.severity.to_string(),
-                        message: d.message.clone(),
-                        line: d.display_line(),
-                        column: d.display_column(),
-                        end_line: d.display_end_line(),
-                        end_column: d.display_end_column(),
-                        code: d.code.clone(),
-                        source: d.source.clone(),
-                        tags: d.tags.iter().map(|t| t.to_string()).collect(),
-                    })
-                    .collect(),
+                version: report.version,
+                stale: report.stale,
+                diagnostics: filtered.iter().map(diagnostic_output).collect(),
             };
             ctx.print_success_flat(response);
         }
@@ -83,3 +155,329 @@ pub async fn execute(args: DiagnosticsArgs, app: &App) -> Result<()> {
 
     Ok(())
 }
+
+/// Check every source file under `dir`, one language server at a time
+async fn execute_workspace(args: &DiagnosticsArgs, dir: &Path, app: &App) -> Result<()> {
+    let ctx = &app.output;
+    let filter = DiagnosticFilter::new(args);
+    let (by_language, skipped) = discover_by_language(dir)?;
+
+    let prefix = ctx.relative_path(dir);
+    let in_scope = |file: &str| {
+        prefix.is_empty()
+            || file == prefix
+            || file
+                .strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+    };
+    let baseline = match args
+        .baseline
+        .as_deref()
+        .map(|path| load_baseline(path, &filter, in_scope))
+        .transpose()
+    {
+        Ok(baseline) => baseline,
+        Err(e) => {
+            ctx.print_error(&format!("{:#}", e));
+            return Ok(());
+        }
+    };
+
+    let mut checked = 0;
+    let mut by_file: BTreeMap<String, Vec<Diagnostic>> = BTreeMap::new();
+    let mut errors = Vec::new();
+    for (language, files) in &by_language {
+        match app.lsp.workspace_diagnostics(files).await {
+            Ok(diagnostics) => {
+                checked += files.len();
+                for d in diagnostics.into_iter().filter(|d| filter.matches(d)) {
+                    let file = ctx.relative_path(Path::new(&d.file_path));
+                    by_file.entry(file).or_default().push(d);
+                }
+            }
+            Err(e) => errors.push(LanguageErrorOutput {
+                language: language.to_string(),
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    let mut summary = SeverityCounts::default();
+    let files: Vec<FileDiagnosticsOutput> = by_file
+        .into_iter()
+        .map(|(file, mut diagnostics)| {
+            diagnostics.sort_by_key(|d| (d.range.start.line, d.range.start.character));
+            for d in &diagnostics {
+                match d.severity {
+                    DiagnosticSeverity::Error => summary.error += 1,
+                    DiagnosticSeverity::Warning => summary.warning += 1,
+                    DiagnosticSeverity::Information => summary.info += 1,
+                    DiagnosticSeverity::Hint => summary.hint += 1,
+                }
+            }
+            file_output(file, &diagnostics)
+        })
+        .collect();
+
+    if let Some(baseline) = baseline {
+        ctx.print_success_flat(WorkspaceBaselineResponse {
+            checked,
+            diff: compare(baseline, files, |_, line| line),
+            skipped: skipped.iter().map(|l| l.to_string()).collect(),
+            errors,
+        });
+        return Ok(());
+    }
+
+    ctx.print_success_flat(WorkspaceDiagnosticsResponse {
+        checked,
+        count: files.iter().map(|f| f.count).sum(),
+        summary,
+        files,
+        skipped: skipped.iter().map(|l| l.to_string()).collect(),
+        errors,
+    });
+
+    Ok(())
+}
+
+/// Source files under `dir` grouped by language, plus the languages skipped
+/// because their server is not installed
+fn discover_by_language(dir: &Path) -> Result<(FilesByLanguage, Vec<Language>)> {
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("Path not found: {}", dir.display()))?;
+    let filter = FileFilter::new(FileFilterConfig {
+        root: dir.clone(),
+        respect_gitignore: true,
+        respect_symora_ignore: true,
+        include_hidden: false,
+        ..Default::default()
+    });
+
+    let mut installed: HashMap<Language, bool> = HashMap::new();
+    let mut by_language: HashMap<Language, Vec<PathBuf>> = HashMap::new();
+    for file in filter.discover_files(&[]) {
+        let language = Language::from_path(&file);
+        if language == Language::Unknown {
+            continue;
+        }
+        if *installed
+            .entry(language)
+            .or_insert_with(|| servers::is_installed(language))
+        {
+            by_language.entry(language).or_default().push(file);
+        }
+    }
+
+    let mut skipped: Vec<Language> = installed
+        .into_iter()
+        .filter(|(_, available)| !available)
+        .map(|(language, _)| language)
+        .collect();
+    skipped.sort_by_key(|l| l.to_string());
+
+    let mut by_language: Vec<_> = by_language.into_iter().collect();
+    by_language.sort_by_key(|(language, _)| language.to_string());
+    for (_, files) in &mut by_language {
+        files.sort();
+    }
+    Ok((by_language, skipped))
+}
+
+/// Files of a saved single-file or workspace diagnostics output, restricted
+/// to `in_scope` files and the active filters
+fn load_baseline(
+    path: &Path,
+    filter: &DiagnosticFilter,
+    in_scope: impl Fn(&str) -> bool,
+) -> Result<Vec<FileDiagnosticsOutput>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read baseline {}", path.display()))?;
+    let value: serde_json::Value = serde_json::from_str(&content)
+        .with_context(|| format!("Baseline {} is not JSON output", path.display()))?;
+
+    let files: Vec<FileDiagnosticsOutput> = match value.get("files") {
+        Some(files) => serde_json::from_value(files.clone()),
+        None => serde_json::from_value(value).map(|f| vec![f]),
+    }
+    .with_context(|| format!("Baseline {} is not diagnostics output", path.display()))?;
+
+    Ok(files
+        .into_iter()
+        .filter(|f| in_scope(&f.file))
+        .map(|f| {
+            let diagnostics: Vec<_> = f
+                .diagnostics
+                .into_iter()
+                .filter(|d| filter.matches_output(d))
+                .collect();
+            FileDiagnosticsOutput {
+                file: f.file,
+                count: diagnostics.len(),
+                diagnostics,
+            }
+        })
+        .collect())
+}
+
+/// Match diagnostics of each file by severity, code, source and message.
+///
+/// `shift` maps a line of the old file to the new file. A candidate on the
+/// shifted line wins, then the nearest one; whatever stays unmatched was
+/// resolved (before) or introduced (after).
+pub(crate) fn compare(
+    before: Vec<FileDiagnosticsOutput>,
+    after: Vec<FileDiagnosticsOutput>,
+    shift: impl Fn(&str, u32) -> u32,
+) -> DiagnosticsDiffOutput {
+    let mut by_file: BTreeMap<String, (Vec<DiagnosticOutput>, Vec<DiagnosticOutput>)> =
+        BTreeMap::new();
+    for f in before {
+        by_file.entry(f.file).or_default().0.extend(f.diagnostics);
+    }
+    for f in after {
+        by_file.entry(f.file).or_default().1.extend(f.diagnostics);
+    }
+
+    let same = |a: &DiagnosticOutput, b: &DiagnosticOutput| {
+        a.severity == b.severity
+            && a.code == b.code
+            && a.source == b.source
+            && a.message == b.message
+    };
+
+    let mut diff = DiagnosticsDiffOutput::default();
+    for (file, (old, new)) in by_file {
+        let mut matched = vec![false; new.len()];
+        let mut resolved = Vec::new();
+        let mut pending = Vec::new();
+
+        for d in old {
+            let line = shift(&file, d.line);
+            let exact =
+                (0..new.len()).find(|&j| !matched[j] && same(&d, &new[j]) && new[j].line == line);
+            match exact {
+                Some(j) => matched[j] = true,
+                None => pending.push((d, line)),
+            }
+        }
+        for (d, line) in pending {
+            let nearest = (0..new.len())
+                .filter(|&j| !matched[j] && same(&d, &new[j]))
+                .min_by_key(|&j| new[j].line.abs_diff(line));
+            match nearest {
+                Some(j) => matched[j] = true,
+                None => resolved.push(d),
+            }
+        }
+
+        let total = new.len();
+        let introduced: Vec<_> = new
+            .into_iter()
+            .zip(matched)
+            .filter_map(|(d, m)| (!m).then_some(d))
+            .collect();
+        diff.unchanged_count += total - introduced.len();
+        diff.introduced_count += introduced.len();
+        diff.resolved_count += resolved.len();
+        if !introduced.is_empty() {
+            diff.introduced.push(file_outputs(file.clone(), introduced));
+        }
+        if !resolved.is_empty() {
+            diff.resolved.push(file_outputs(file, resolved));
+        }
+    }
+    diff
+}
+
+pub(crate) fn file_output(file: String, diagnostics: &[Diagnostic]) -> FileDiagnosticsOutput {
+    file_outputs(file, diagnostics.iter().map(diagnostic_output).collect())
+}
+
+fn file_outputs(file: String, diagnostics: Vec<DiagnosticOutput>) -> FileDiagnosticsOutput {
+    FileDiagnosticsOutput {
+        file,
+        count: diagnostics.len(),
+        diagnostics,
+    }
+}
+
+fn diagnostic_output(d: &Diagnostic) -> DiagnosticOutput {
+    DiagnosticOutput {
+        severity: d.severity.to_string(),
+        message: d.message.clone(),
+        line: d.display_line(),
+        column: d.display_column(),
+        end_line: d.display_end_line(),
+        end_column: d.display_end_column(),
+        code: d.code.clone(),
+        source: d.source.clone(),
+        tags: d.tags.iter().map(|t| t.to_string()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostic_filter() {
+        let filter = DiagnosticFilter {
+            severity: Some(vec![DiagnosticSeverity::Error]),
+            source: Some("rustc".to_string()),
+        };
+        let mut d = Diagnostic::new(
+            "a.rs".to_string(),
+            0,
+            0,
+            DiagnosticSeverity::Error,
+            "mismatched types".to_string(),
+        );
+        assert!(!filter.matches(&d));
+
+        d.source = Some("rustc".to_string());
+        assert!(filter.matches(&d));
+
+        d.severity = DiagnosticSeverity::Warning;
+        assert!(!filter.matches(&d));
+    }
+
+    fn output(line: u32, message: &str) -> DiagnosticOutput {
+        DiagnosticOutput {
+            severity: "error".to_string(),
+            message: message.to_string(),
+            line,
+            column: 1,
+            end_line: line,
+            end_column: 2,
+            code: Some("E0308".to_string()),
+            source: None,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_compare_shifted() {
+        let file = |diagnostics: Vec<DiagnosticOutput>| {
+            vec![file_outputs("src/a.rs".to_string(), diagnostics)]
+        };
+        let before = file(vec![
+            output(3, "mismatched"),
+            output(8, "mismatched"),
+            output(9, "gone"),
+        ]);
+        let after = file(vec![
+            output(5, "mismatched"),
+            output(10, "mismatched"),
+            output(2, "new"),
+        ]);
+
+        let diff = compare(before, after, |_, line| line + 2);
+        assert_eq!(diff.unchanged_count, 2);
+        assert_eq!(diff.introduced_count, 1);
+        assert_eq!(diff.introduced[0].diagnostics[0].message, "new");
+        assert_eq!(diff.resolved_count, 1);
+        assert_eq!(diff.resolved[0].diagnostics[0].message, "gone");
+    }
+}
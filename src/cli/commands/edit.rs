@@ -2,15 +2,28 @@
 //!
 //! Provides symbol-aware text editing operations.
 
+use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
 
 use anyhow::{Context, Result};
 use clap::{Args, Subcommand};
+use serde::Serialize;
 
 use crate::app::App;
-use crate::cli::ParsedLocation;
-use crate::models::lsp::FindSymbolsOptions;
+use crate::cli::expect::{self, Expectation};
+use crate::cli::response::LocationOutput;
+use crate::cli::{OutputContext, ParsedLocation};
+use crate::models::lsp::{
+    FileChangeWithEdits, FindSymbolsOptions, Position, Range, ResourceOperation, TextEdit,
+};
+use crate::services::diff::unified_diff;
+use crate::services::patch::{self as patches, FilePatch, HunkReport};
+use crate::services::text_buffer::TextBuffer;
+use crate::services::workspace_edit::prepare_workspace_edit;
+
+/// Context lines around each change in patch diffs
+const DIFF_CONTEXT: usize = 3;
 
 #[derive(Args, Debug)]
 pub struct EditArgs {
@@ -36,6 +49,50 @@ pub enum EditCommand {
         /// Dry run (show diff without applying)
         #[arg(long)]
         dry_run: bool,
+
+        /// Refuse unless the target still holds this text (or hash:<hex>)
+        #[arg(long)]
+        expect: Option<Expectation>,
+    },
+
+    /// Replace a unique snippet of text, located by content instead of position
+    ReplaceText {
+        /// File path
+        file: String,
+
+        /// Text to replace; must occur exactly once in the file or symbol
+        #[arg(long)]
+        old: String,
+
+        /// Replacement text
+        #[arg(long)]
+        new: String,
+
+        /// Only search within this symbol (e.g., "Class/method")
+        #[arg(short = 's', long)]
+        symbol: Option<String>,
+
+        /// Let any run of whitespace in --old match any run of whitespace
+        #[arg(short = 'w', long)]
+        ignore_whitespace: bool,
+
+        /// Dry run (show diff without applying)
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Apply a unified diff (multi-file; hunks are located by their content)
+    Patch {
+        /// Patch file, or - to read it from stdin
+        patch: String,
+
+        /// Leading path components to strip (default: a/ and b/ of git diffs)
+        #[arg(short = 'p', long)]
+        strip: Option<usize>,
+
+        /// Dry run (show diff without applying)
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Insert text after a symbol or position
@@ -59,6 +116,10 @@ pub enum EditCommand {
         /// Dry run (show diff without applying)
         #[arg(long)]
         dry_run: bool,
+
+        /// Refuse unless the target still holds this text (or hash:<hex>)
+        #[arg(long)]
+        expect: Option<Expectation>,
     },
 
     /// Insert text before a symbol or position
@@ -82,6 +143,10 @@ pub enum EditCommand {
         /// Dry run (show diff without applying)
         #[arg(long)]
         dry_run: bool,
+
+        /// Refuse unless the target still holds this text (or hash:<hex>)
+        #[arg(long)]
+        expect: Option<Expectation>,
     },
 
     /// Replace a symbol's body (by location or symbol path)
@@ -105,6 +170,10 @@ pub enum EditCommand {
         /// Dry run (show diff without applying)
         #[arg(long)]
         dry_run: bool,
+
+        /// Refuse unless the target still holds this text (or hash:<hex>)
+        #[arg(long)]
+        expect: Option<Expectation>,
     },
 }
 
@@ -117,6 +186,7 @@ pub async fn execute(args: EditArgs, app: &App) -> Result<()> {
             end,
             text,
             dry_run,
+            expect,
         } => {
             let start_loc = ParsedLocation::parse(&start)?.to_absolute()?;
             let end_loc = if let Some(end_str) = end {
@@ -124,29 +194,174 @@ pub async fn execute(args: EditArgs, app: &App) -> Result<()> {
             } else {
                 start_loc.clone()
             };
-
-            let result = apply_replace(
-                &start_loc.file,
+            let range = edit_range(
                 start_loc.line,
                 start_loc.column,
                 end_loc.line,
                 end_loc.column,
-                &text,
-                dry_run,
-            )?;
+            );
+            if !expect::guard(ctx, &start_loc.file, &range, expect.as_ref())? {
+                return Ok(());
+            }
+
+            let result = apply_replace(app, &start_loc.file, &range, &text, dry_run)?;
 
             ctx.print_success_flat(result);
         }
 
+        EditCommand::ReplaceText {
+            file,
+            old,
+            new,
+            symbol,
+            ignore_whitespace,
+            dry_run,
+        } => {
+            let (path, scope) = match symbol {
+                Some(symbol) => {
+                    let (path, target) =
+                        resolve_symbol(app, Some(file), None, Some(symbol)).await?;
+                    let loc = &target.location;
+                    let range = edit_range(
+                        loc.line,
+                        loc.column,
+                        loc.end_line.unwrap_or(loc.line),
+                        loc.end_column.unwrap_or(0),
+                    );
+                    (path, Some((target.name_path.unwrap_or(target.name), range)))
+                }
+                None => (absolute_path(app, &file), None),
+            };
+
+            let buffer =
+                TextBuffer::new(&fs::read_to_string(&path).context("Failed to read file")?);
+            let bytes = match &scope {
+                Some((_, range)) => buffer.offset(range.start)..buffer.offset(range.end),
+                None => 0..buffer.text().len(),
+            };
+            let old = buffer.normalize(&old);
+            if old.trim().is_empty() {
+                anyhow::bail!("--old must contain non-whitespace text");
+            }
+
+            let target = match &scope {
+                Some((name_path, _)) => format!("{} ({})", ctx.relative_path(&path), name_path),
+                None => ctx.relative_path(&path),
+            };
+            match find_matches(buffer.text(), &old, bytes, ignore_whitespace).as_slice() {
+                [] => ctx.print_error(&format!("No match for --old in {}", target)),
+                [(start, end)] => {
+                    let range = Range::new(buffer.position(*start), buffer.position(*end));
+                    let result = apply_replace(app, &path, &range, &new, dry_run)?;
+                    ctx.print_success_flat(result);
+                }
+                matches => {
+                    let locations: Vec<_> = matches
+                        .iter()
+                        .map(|(start, _)| {
+                            let pos = buffer.position(*start);
+                            LocationOutput::from_path(
+                                &path,
+                                pos.line + 1,
+                                pos.character + 1,
+                                ctx.root(),
+                            )
+                        })
+                        .collect();
+                    ctx.print_error_with(
+                        &format!(
+                            "--old matches {} times in {}. Add surrounding text or narrow with --symbol",
+                            matches.len(),
+                            target
+                        ),
+                        "matches",
+                        locations,
+                    );
+                }
+            }
+        }
+
+        EditCommand::Patch {
+            patch,
+            strip,
+            dry_run,
+        } => {
+            let diff = if patch == "-" {
+                std::io::read_to_string(std::io::stdin()).context("Failed to read patch")?
+            } else {
+                fs::read_to_string(absolute_path(app, &patch)).context("Failed to read patch")?
+            };
+            let patches = match patches::parse(&diff, strip) {
+                Ok(patches) => patches,
+                Err(e) => {
+                    ctx.print_error(&format!("Invalid patch: {}", e));
+                    return Ok(());
+                }
+            };
+
+            if let Some(path) = duplicate_path(ctx, &patches) {
+                ctx.print_error(&format!(
+                    "Patch touches {} in more than one section. Combine its hunks into one section.",
+                    path
+                ));
+                return Ok(());
+            }
+
+            let mut changes = Vec::new();
+            let mut files = Vec::new();
+            for file_patch in &patches {
+                let (change, output) = plan_patch(ctx, file_patch)?;
+                changes.push(change);
+                files.push(output);
+            }
+
+            let hunks: Vec<_> = files.iter().flat_map(|f| &f.hunks).collect();
+            let failed = hunks.iter().filter(|h| !h.applied).count();
+            if failed > 0 {
+                ctx.print_error_with(
+                    &format!(
+                        "{} of {} hunks failed to apply. No files were modified.",
+                        failed,
+                        hunks.len()
+                    ),
+                    "files",
+                    files,
+                );
+                return Ok(());
+            }
+
+            let result = if dry_run {
+                prepare_workspace_edit(&changes).map(|_| ())
+            } else {
+                app.apply_edit(&format!("edit patch {}", patch), &changes)
+                    .map(|_| ())
+            };
+            if let Err(e) = result {
+                ctx.print_error(&format!("Failed to apply changes: {}", e));
+                return Ok(());
+            }
+
+            ctx.print_success_flat(serde_json::json!({
+                "dry_run": dry_run,
+                "applied": !dry_run,
+                "hunk_count": hunks.len(),
+                "files": files,
+            }));
+        }
+
         EditCommand::InsertAfter {
             file,
             location,
             symbol,
             text,
             dry_run,
+            expect,
         } => {
-            let (file_path, line, col) = resolve_target(app, file, location, symbol).await?;
-            let result = apply_insert(&file_path, line, col, &text, false, dry_run)?;
+            let (file_path, line, col, range) = resolve_target(app, file, location, symbol).await?;
+            if !expect::guard(ctx, &file_path, &range, expect.as_ref())? {
+                return Ok(());
+            }
+            let result = apply_insert(app, &file_path, line, col, &text, false, dry_run)?;
             ctx.print_success_flat(result);
         }
 
@@ -156,9 +371,13 @@ pub async fn execute(args: EditArgs, app: &App) -> Result<()> {
             symbol,
             text,
             dry_run,
+            expect,
         } => {
-            let (file_path, line, col) = resolve_target(app, file, location, symbol).await?;
-            let result = apply_insert(&file_path, line, col, &text, true, dry_run)?;
+            let (file_path, line, col, range) = resolve_target(app, file, location, symbol).await?;
+            if !expect::guard(ctx, &file_path, &range, expect.as_ref())? {
+                return Ok(());
+            }
+            let result = apply_insert(app, &file_path, line, col, &text, true, dry_run)?;
             ctx.print_success_flat(result);
         }
 
@@ -168,6 +387,7 @@ pub async fn execute(args: EditArgs, app: &App) -> Result<()> {
             symbol,
             text,
             dry_run,
+            expect,
         } => {
             let (file_path, target_symbol) = resolve_symbol(app, file, location, symbol).await?;
 
@@ -176,9 +396,12 @@ pub async fn execute(args: EditArgs, app: &App) -> Result<()> {
             let end_line = target_symbol.location.end_line.unwrap_or(start_line);
             let end_col = target_symbol.location.end_column.unwrap_or(0);
 
-            let result = apply_replace(
-                &file_path, start_line, start_col, end_line, end_col, &text, dry_run,
-            )?;
+            let range = edit_range(start_line, start_col, end_line, end_col);
+            if !expect::guard(ctx, &file_path, &range, expect.as_ref())? {
+                return Ok(());
+            }
+
+            let result = apply_replace(app, &file_path, &range, &text, dry_run)?;
 
             ctx.print_success_flat(serde_json::json!({
                 "symbol": target_symbol.name,
@@ -192,18 +415,20 @@ pub async fn execute(args: EditArgs, app: &App) -> Result<()> {
     Ok(())
 }
 
-/// Resolve target position from file+symbol or location
+/// Resolve target position from file+symbol or location, along with the
+/// range `--expect` is checked against: the symbol, or the target line
 async fn resolve_target(
     app: &App,
     file: Option<String>,
     location: Option<String>,
     symbol_path: Option<String>,
-) -> Result<(std::path::PathBuf, u32, u32)> {
+) -> Result<(std::path::PathBuf, u32, u32, Range)> {
     use crate::models::symbol::Symbol;
 
     if let Some(loc_str) = location {
         let loc = ParsedLocation::parse(&loc_str)?.to_absolute()?;
-        return Ok((loc.file, loc.line, loc.column));
+        let range = edit_range(loc.line, loc.column, loc.line, loc.column);
+        return Ok((loc.file, loc.line, loc.column, range));
     }
 
     let file =
@@ -211,12 +436,7 @@ async fn resolve_target(
     let symbol_pattern =
         symbol_path.ok_or_else(|| anyhow::anyhow!("--symbol is required when using file"))?;
 
-    let path = std::path::Path::new(&file);
-    let abs_path = if path.is_absolute() {
-        path.to_path_buf()
-    } else {
-        app.root().join(path)
-    };
+    let abs_path = absolute_path(app, &file);
 
     let mut symbols = app
         .lsp
@@ -229,8 +449,14 @@ async fn resolve_target(
 
     let end_line = target.location.end_line.unwrap_or(target.location.line);
     let end_col = target.location.end_column.unwrap_or(1);
-
-    Ok((abs_path, end_line, end_col))
+    let range = edit_range(
+        target.location.line,
+        target.location.column,
+        end_line,
+        target.location.end_column.unwrap_or(0),
+    );
+
+    Ok((abs_path, end_line, end_col, range))
 }
 
 /// Resolve symbol from file+symbol or location
@@ -272,12 +498,7 @@ async fn resolve_symbol(
     let symbol_pattern =
         symbol_path.ok_or_else(|| anyhow::anyhow!("--symbol is required when using file"))?;
 
-    let path = std::path::Path::new(&file);
-    let abs_path = if path.is_absolute() {
-        path.to_path_buf()
-    } else {
-        app.root().join(path)
-    };
+    let abs_path = absolute_path(app, &file);
 
     let mut symbols = app
         .lsp
@@ -292,137 +513,257 @@ async fn resolve_symbol(
     Ok((abs_path, target))
 }
 
-/// Apply a replace edit to a file
-fn apply_replace(
-    file: &Path,
-    start_line: u32,
-    start_col: u32,
-    end_line: u32,
-    end_col: u32,
-    new_text: &str,
-    dry_run: bool,
-) -> Result<serde_json::Value> {
-    let content = fs::read_to_string(file).context("Failed to read file")?;
-    let lines: Vec<&str> = content.lines().collect();
+/// Result of planning one file of a patch
+#[derive(Serialize)]
+struct PatchFileOutput {
+    file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation: Option<&'static str>,
+    hunks: Vec<HunkReport>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    diff: String,
+}
 
-    // Convert 1-indexed to 0-indexed
-    let start_line_idx = (start_line.saturating_sub(1)) as usize;
-    let end_line_idx = (end_line.saturating_sub(1)) as usize;
-    let start_col_idx = (start_col.saturating_sub(1)) as usize;
-    let end_col_idx = if end_col == 0 {
-        // If end_col is 0, replace to end of line
-        lines.get(end_line_idx).map(|l| l.len()).unwrap_or(0)
+/// First file named by more than one section of the patch.
+///
+/// Each section is planned against the file as it is on disk, so a second
+/// section for the same file (or for either side of a rename) would be
+/// computed against stale content.
+fn duplicate_path(ctx: &OutputContext, patches: &[FilePatch]) -> Option<String> {
+    let mut seen = HashSet::new();
+    for patch in patches {
+        let paths: HashSet<_> = [&patch.old_path, &patch.new_path]
+            .into_iter()
+            .flatten()
+            .map(|p| ctx.root().join(p))
+            .collect();
+        if let Some(path) = paths.iter().find(|p| seen.contains(*p)) {
+            return Some(ctx.relative_path(path));
+        }
+        seen.extend(paths);
+    }
+    None
+}
+
+/// Locate the hunks of one file's patch and build its change
+fn plan_patch(
+    ctx: &OutputContext,
+    patch: &FilePatch,
+) -> Result<(FileChangeWithEdits, PatchFileOutput)> {
+    let old = patch.old_path.as_deref().map(|p| ctx.root().join(p));
+    let new = patch.new_path.as_deref().map(|p| ctx.root().join(p));
+    let (file, operation) = match (old, new) {
+        (None, Some(new)) => (
+            new,
+            Some(ResourceOperation::Create {
+                overwrite: false,
+                ignore_if_exists: false,
+            }),
+        ),
+        (Some(old), None) => (
+            old,
+            Some(ResourceOperation::Delete {
+                recursive: false,
+                ignore_if_not_exists: false,
+            }),
+        ),
+        (Some(old), Some(new)) if old != new => (
+            new,
+            Some(ResourceOperation::Rename {
+                old_file: old,
+                overwrite: false,
+                ignore_if_exists: false,
+            }),
+        ),
+        (Some(old), Some(_)) => (old, None),
+        (None, None) => anyhow::bail!("Patch has neither an old nor a new path"),
+    };
+
+    let content = match &operation {
+        Some(ResourceOperation::Create { .. }) => String::new(),
+        Some(ResourceOperation::Rename { old_file, .. }) => fs::read_to_string(old_file)
+            .with_context(|| format!("Failed to read {}", old_file.display()))?,
+        _ => fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?,
+    };
+    let patches::HunkEdits {
+        mut edits,
+        final_newline,
+        reports: hunks,
+    } = patches::apply_hunks(&content, &patch.hunks);
+    let deleted = matches!(operation, Some(ResourceOperation::Delete { .. }));
+    let updated = if deleted {
+        edits.clear();
+        String::new()
     } else {
-        (end_col.saturating_sub(1)) as usize
+        let mut buffer = TextBuffer::new(&content);
+        if let Some(final_newline) = final_newline {
+            buffer = buffer.with_final_newline(final_newline);
+        }
+        buffer
+            .apply(&edits)
+            .map_err(|e| anyhow::anyhow!("Invalid patch for {}: {}", file.display(), e))?
     };
 
-    // Validate ranges
-    if start_line_idx >= lines.len() {
-        anyhow::bail!("Start line {} is out of range", start_line);
-    }
-    if end_line_idx >= lines.len() {
-        anyhow::bail!("End line {} is out of range", end_line);
-    }
+    let relative = ctx.relative_path(&file);
+    let output = PatchFileOutput {
+        diff: unified_diff(&relative, &content, &updated, DIFF_CONTEXT),
+        file: relative,
+        operation: operation.as_ref().map(ResourceOperation::name),
+        hunks,
+    };
+    let change = FileChangeWithEdits {
+        operation,
+        final_newline,
+        ..FileChangeWithEdits::new(file, edits)
+    };
+    Ok((change, output))
+}
 
-    // Build the new content
-    let mut result = String::new();
-
-    // Add lines before the edit
-    for (i, line) in lines.iter().enumerate() {
-        if i < start_line_idx {
-            result.push_str(line);
-            result.push('\n');
-        } else if i == start_line_idx {
-            // Add content before the edit on the start line
-            let safe_start = start_col_idx.min(line.len());
-            result.push_str(&line[..safe_start]);
-
-            // Add the new text
-            result.push_str(new_text);
-
-            // If single line edit, add content after the edit
-            if start_line_idx == end_line_idx {
-                let safe_end = end_col_idx.min(line.len());
-                result.push_str(&line[safe_end..]);
-                result.push('\n');
-            }
-        } else if i > start_line_idx && i < end_line_idx {
-            // Skip lines within the edit range
-            continue;
-        } else if i == end_line_idx && start_line_idx != end_line_idx {
-            // Add content after the edit on the end line
-            let safe_end = end_col_idx.min(line.len());
-            result.push_str(&line[safe_end..]);
-            result.push('\n');
-        } else if i > end_line_idx {
-            result.push_str(line);
-            result.push('\n');
-        }
+/// Resolve a file argument against the project root
+fn absolute_path(app: &App, file: &str) -> std::path::PathBuf {
+    let path = Path::new(file);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        app.root().join(path)
     }
+}
 
-    // Remove trailing newline if original didn't have one
-    if !content.ends_with('\n') && result.ends_with('\n') {
-        result.pop();
+/// Byte ranges of non-overlapping occurrences of `old` within `scope` of
+/// `text`. With `ignore_whitespace`, each whitespace run in `old` matches any
+/// whitespace run (including line breaks) and surrounding whitespace is
+/// ignored.
+fn find_matches(
+    text: &str,
+    old: &str,
+    scope: std::ops::Range<usize>,
+    ignore_whitespace: bool,
+) -> Vec<(usize, usize)> {
+    let offset = scope.start;
+    let haystack = &text[scope];
+    if !ignore_whitespace {
+        return haystack
+            .match_indices(old)
+            .map(|(i, m)| (offset + i, offset + i + m.len()))
+            .collect();
     }
 
-    // Calculate what was replaced
-    let old_text = if start_line_idx == end_line_idx {
-        let line = lines[start_line_idx];
-        let safe_start = start_col_idx.min(line.len());
-        let safe_end = end_col_idx.min(line.len());
-        line[safe_start..safe_end].to_string()
-    } else {
-        let mut old = String::new();
-        for (idx, line) in lines
-            .iter()
-            .enumerate()
-            .take(end_line_idx + 1)
-            .skip(start_line_idx)
-        {
-            if idx == start_line_idx {
-                let safe_start = start_col_idx.min(line.len());
-                old.push_str(&line[safe_start..]);
-                old.push('\n');
-            } else if idx == end_line_idx {
-                let safe_end = end_col_idx.min(line.len());
-                old.push_str(&line[..safe_end]);
-            } else {
-                old.push_str(line);
-                old.push('\n');
+    let words: Vec<&str> = old.split_whitespace().collect();
+    let Some(first) = words.first() else {
+        return Vec::new();
+    };
+    let match_at = |start: usize| {
+        let mut pos = start + first.len();
+        for word in &words[1..] {
+            let rest = &haystack[pos..];
+            let trimmed = rest.trim_start();
+            if trimmed.len() == rest.len() || !trimmed.starts_with(word) {
+                return None;
             }
+            pos += rest.len() - trimmed.len() + word.len();
         }
-        old
+        Some(pos)
     };
 
-    if dry_run {
-        Ok(serde_json::json!({
-            "dry_run": true,
-            "file": file.display().to_string(),
-            "old_text": old_text,
-            "new_text": new_text,
-            "range": {
-                "start": {"line": start_line, "column": start_col},
-                "end": {"line": end_line, "column": end_col}
+    let mut matches = Vec::new();
+    let mut from = 0;
+    while let Some(i) = haystack[from..].find(first) {
+        let start = from + i;
+        match match_at(start) {
+            Some(end) => {
+                matches.push((offset + start, offset + end));
+                from = end;
             }
-        }))
-    } else {
-        fs::write(file, &result).context("Failed to write file")?;
+            None => from = start + first.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    matches
+}
 
-        Ok(serde_json::json!({
-            "applied": true,
+/// 0-indexed range of 1-indexed coordinates; an end column of 0 means the
+/// end of the line
+fn edit_range(start_line: u32, start_col: u32, end_line: u32, end_col: u32) -> Range {
+    let end_col = match end_col {
+        0 => u32::MAX,
+        col => col - 1,
+    };
+    Range::new(
+        Position::new(start_line.saturating_sub(1), start_col.saturating_sub(1)),
+        Position::new(end_line.saturating_sub(1), end_col),
+    )
+}
+
+/// Apply a replace edit to a file.
+///
+/// `range` is 0-indexed with character columns; columns past the end of a
+/// line stop at it, and the end may sit after the file's final newline.
+fn apply_replace(
+    app: &App,
+    file: &Path,
+    range: &Range,
+    new_text: &str,
+    dry_run: bool,
+) -> Result<serde_json::Value> {
+    let buffer = TextBuffer::new(&fs::read_to_string(file).context("Failed to read file")?);
+    let line_count = buffer.lines().count() as u32;
+
+    // Validate ranges
+    if range.start.line >= line_count {
+        anyhow::bail!("Start line {} is out of range", range.start.line + 1);
+    }
+    if range.end.line > line_count || (range.end.line == line_count && range.end.character > 0) {
+        anyhow::bail!("End line {} is out of range", range.end.line + 1);
+    }
+
+    let start = buffer.offset(range.start);
+    let end = buffer.offset(range.end).max(start);
+    let range = Range::new(buffer.position(start), buffer.position(end));
+
+    // Calculate what was replaced, and its hash to pass to --expect
+    let old_text = buffer.text()[start..end].replace("\r\n", "\n");
+    let (_, target) = expect::target_text(&buffer, &range);
+    let target_hash = expect::text_hash(&target);
+
+    let (start_line, start_col) = range.start.to_display();
+    let (end_line, end_col) = range.end.to_display();
+    let output = |applied: &str| {
+        serde_json::json!({
+            applied: true,
             "file": file.display().to_string(),
             "old_text": old_text,
+            "target_hash": target_hash,
             "new_text": new_text,
             "range": {
                 "start": {"line": start_line, "column": start_col},
                 "end": {"line": end_line, "column": end_col}
             }
-        }))
+        })
+    };
+
+    if dry_run {
+        return Ok(output("dry_run"));
     }
+
+    let command = format!(
+        "edit replace {}:{}:{}-{}:{}",
+        app.output.relative_path(file),
+        start_line,
+        start_col,
+        end_line,
+        end_col
+    );
+    let edit = TextEdit {
+        range,
+        new_text: new_text.to_string(),
+    };
+    write_edit(app, &command, file, edit)?;
+    Ok(output("applied"))
 }
 
 /// Apply an insert edit to a file
 fn apply_insert(
+    app: &App,
     file: &Path,
     line: u32,
     column: u32,
@@ -430,8 +771,8 @@ fn apply_insert(
     before: bool,
     dry_run: bool,
 ) -> Result<serde_json::Value> {
-    let content = fs::read_to_string(file).context("Failed to read file")?;
-    let lines: Vec<&str> = content.lines().collect();
+    let buffer = TextBuffer::new(&fs::read_to_string(file).context("Failed to read file")?);
+    let lines: Vec<&str> = buffer.lines().collect();
 
     // Convert 1-indexed to 0-indexed
     let line_idx = (line.saturating_sub(1)) as usize;
@@ -441,29 +782,6 @@ fn apply_insert(
         anyhow::bail!("Line {} is out of range", line);
     }
 
-    // Build the new content
-    let mut result = String::new();
-
-    for (i, line_content) in lines.iter().enumerate() {
-        if i == line_idx {
-            let safe_col = col_idx.min(line_content.len());
-            // Both before and after insert at the same position
-            // The difference is semantic (for user clarity)
-            result.push_str(&line_content[..safe_col]);
-            result.push_str(text);
-            result.push_str(&line_content[safe_col..]);
-            result.push('\n');
-        } else {
-            result.push_str(line_content);
-            result.push('\n');
-        }
-    }
-
-    // Remove trailing newline if original didn't have one
-    if !content.ends_with('\n') && result.ends_with('\n') {
-        result.pop();
-    }
-
     let mode = if before {
         "insert_before"
     } else {
@@ -479,7 +797,23 @@ fn apply_insert(
             "position": {"line": line, "column": column}
         }))
     } else {
-        fs::write(file, &result).context("Failed to write file")?;
+        let position = Position::new(line_idx as u32, col_idx as u32);
+        let command = format!(
+            "edit {} {}:{}:{}",
+            mode.replace('_', "-"),
+            app.output.relative_path(file),
+            line,
+            column
+        );
+        write_edit(
+            app,
+            &command,
+            file,
+            TextEdit {
+                range: Range::point(position),
+                new_text: text.to_string(),
+            },
+        )?;
 
         Ok(serde_json::json!({
             "applied": true,
@@ -490,3 +824,133 @@ fn apply_insert(
         }))
     }
 }
+
+/// Write a single edit through the journaled workspace edit applier
+fn write_edit(app: &App, command: &str, file: &Path, edit: TextEdit) -> Result<()> {
+    app.apply_edit(
+        command,
+        &[FileChangeWithEdits::new(file.to_path_buf(), vec![edit])],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_matches() {
+        let text = "fn a() {\n    call(x,  y);\n}\nfn b() { call(x, y); }\n";
+        let all = 0..text.len();
+
+        assert_eq!(
+            find_matches(text, "call(x, y)", all.clone(), false),
+            [(37, 47)]
+        );
+        assert!(find_matches(text, "call(x,\ny)", all.clone(), false).is_empty());
+
+        // Whitespace runs match any whitespace, including line breaks
+        let loose = find_matches(text, " call(x,\ny) ", all.clone(), true);
+        assert_eq!(loose, [(13, 24), (37, 47)]);
+        assert_eq!(&text[13..24], "call(x,  y)");
+
+        // Scoped to the first function
+        assert_eq!(find_matches(text, "call(x, y)", 0..27, true), [(13, 24)]);
+        assert!(find_matches(text, "  ", all, true).is_empty());
+    }
+
+    #[test]
+    fn test_plan_patch_applies_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        fs::write(dir.path().join("old.txt"), "keep\nmove\n").unwrap();
+        let ctx = OutputContext::new(dir.path().to_path_buf());
+        let diff = "\
+--- a/a.txt
++++ b/a.txt
+@@ -1,3 +1,3 @@
+ one
+-two
++TWO
+ three
+--- a/old.txt
++++ b/new.txt
+@@ -1,2 +1,2 @@
+ keep
+-move
++moved
+\\ No newline at end of file
+--- /dev/null
++++ b/created.txt
+@@ -0,0 +1 @@
++fresh
+";
+        let patches = patches::parse(diff, None).unwrap();
+        assert_eq!(duplicate_path(&ctx, &patches), None);
+
+        let mut changes = Vec::new();
+        for patch in &patches {
+            let (change, output) = plan_patch(&ctx, patch).unwrap();
+            assert!(output.hunks.iter().all(|h| h.applied));
+            changes.push(change);
+        }
+        crate::services::workspace_edit::apply_workspace_edit(&changes).unwrap();
+
+        let read = |name: &str| fs::read_to_string(dir.path().join(name)).unwrap();
+        assert_eq!(read("a.txt"), "one\nTWO\nthree\n");
+        assert_eq!(read("new.txt"), "keep\nmoved");
+        assert_eq!(read("created.txt"), "fresh\n");
+        assert!(!dir.path().join("old.txt").exists());
+    }
+
+    #[test]
+    fn test_duplicate_patch_paths() {
+        let ctx = OutputContext::new(std::path::PathBuf::from("/project"));
+        let section = |old: &str, new: &str| FilePatch {
+            old_path: Some(old.to_string()),
+            new_path: Some(new.to_string()),
+            hunks: Vec::new(),
+        };
+
+        // The same file in two sections
+        let twice = [section("a.rs", "a.rs"), section("a.rs", "a.rs")];
+        assert_eq!(duplicate_path(&ctx, &twice).as_deref(), Some("a.rs"));
+
+        // Edit, then rename away
+        let renamed = [section("a.rs", "a.rs"), section("a.rs", "b.rs")];
+        assert_eq!(duplicate_path(&ctx, &renamed).as_deref(), Some("a.rs"));
+
+        // Rename onto a file edited in another section
+        let onto = [section("a.rs", "b.rs"), section("b.rs", "b.rs")];
+        assert_eq!(duplicate_path(&ctx, &onto).as_deref(), Some("b.rs"));
+
+        let distinct = [section("a.rs", "a.rs"), section("b.rs", "c.rs")];
+        assert_eq!(duplicate_path(&ctx, &distinct), None);
+    }
+
+    #[tokio::test]
+    async fn test_replace_text_through_final_newline() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = App::with_root(dir.path().to_path_buf(), false)
+            .await
+            .unwrap();
+        let file = dir.path().join("a.rs");
+        fs::write(&file, "fn a() {}\n// end\n").unwrap();
+
+        let buffer = TextBuffer::new(&fs::read_to_string(&file).unwrap());
+        let all = 0..buffer.text().len();
+        let [(start, end)] = find_matches(buffer.text(), "// end\n", all, false)[..] else {
+            panic!("expected one match");
+        };
+        let range = Range::new(buffer.position(start), buffer.position(end));
+        assert_eq!(range.end, Position::new(2, 0));
+
+        let result = apply_replace(&app, &file, &range, "", false).unwrap();
+        assert_eq!(result["old_text"], "// end\n");
+        assert_eq!(fs::read_to_string(&file).unwrap(), "fn a() {}\n");
+
+        // Past the end of the file is still rejected
+        let beyond = Range::new(Position::new(0, 0), Position::new(2, 1));
+        assert!(apply_replace(&app, &file, &beyond, "", true).is_err());
+    }
+}
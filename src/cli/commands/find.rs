@@ -8,10 +8,12 @@ use clap::{Args, Subcommand};
 use crate::app::App;
 use crate::cli::ParsedLocation;
 use crate::cli::response::{
-    DefinitionResponse, LocationOutput, ReferencesResponse, SymbolOutput, SymbolsResponse,
+    DeclarationsResponse, DefinitionCandidateOutput, DefinitionResponse, DefinitionsResponse,
+    LocationOutput, OccurrenceOutput, OccurrencesResponse, ReferencesResponse, SymbolOutput,
+    SymbolsResponse,
 };
-use crate::models::lsp::FindSymbolsOptions;
-use crate::models::symbol::{Language, Symbol, SymbolKind};
+use crate::models::lsp::{DocumentHighlightKind, FindSymbolsOptions};
+use crate::models::symbol::{Language, Location, Symbol, SymbolKind};
 
 #[derive(Args, Debug)]
 pub struct FindArgs {
@@ -70,7 +72,7 @@ pub enum FindCommand {
 
     /// Find all references to a symbol at position
     Refs {
-        /// File path with position (file:line:column)
+        /// Location (file:line:column, file#Class/method or Class/method)
         location: String,
 
         /// Maximum results (default from config: lsp.refs_limit)
@@ -78,21 +80,45 @@ pub enum FindCommand {
         limit: Option<usize>,
     },
 
+    /// Find read and write occurrences of a symbol within its file
+    Occurrences {
+        /// Location (file:line:column, file#Class/method or Class/method)
+        location: String,
+
+        /// Filter by occurrence kind(s), comma-separated (read,write,text)
+        #[arg(long, value_delimiter = ',')]
+        kind: Vec<DocumentHighlightKind>,
+    },
+
     /// Go to definition of symbol at position
     Def {
-        /// File path with position (file:line:column)
+        /// Location (file:line:column, file#Class/method or Class/method)
+        location: String,
+
+        /// Return every candidate, best first, with the reason for its rank
+        #[arg(long, conflicts_with = "follow")]
+        all: bool,
+
+        /// Follow re-exports and aliases to the final definition
+        #[arg(long)]
+        follow: bool,
+    },
+
+    /// Go to declaration (headers, .d.ts files, trait method signatures)
+    Decl {
+        /// Location (file:line:column, file#Class/method or Class/method)
         location: String,
     },
 
     /// Go to type definition (find the type of a variable/expression)
     Typedef {
-        /// File path with position (file:line:column)
+        /// Location (file:line:column, file#Class/method or Class/method)
         location: String,
     },
 
     /// Find implementations of a trait/interface
     Impl {
-        /// File path with position (file:line:column)
+        /// Location (file:line:column, file#Class/method or Class/method)
         location: String,
 
         /// Maximum results (default from config: lsp.impl_limit)
@@ -214,7 +240,7 @@ pub async fn execute(args: FindArgs, app: &App) -> Result<()> {
 
         FindCommand::Refs { location, limit } => {
             let limit = limit.unwrap_or(cfg.lsp.refs_limit);
-            let loc = ParsedLocation::parse(&location)?.to_absolute()?;
+            let loc = ParsedLocation::resolve(&location, app).await?;
 
             match app
                 .lsp
@@ -222,18 +248,52 @@ pub async fn execute(args: FindArgs, app: &App) -> Result<()> {
                 .await
             {
                 Ok(locations) => {
-                    let project_refs: Vec<_> = locations
+                    let mut stream = ctx.stream("references");
+                    for l in locations
                         .iter()
                         .filter(|l| ctx.is_project_path(&l.file))
                         .take(limit)
+                    {
+                        stream.push(&LocationOutput::from_path(
+                            &l.file,
+                            l.line,
+                            l.column,
+                            ctx.root(),
+                        ));
+                    }
+                    let count = stream.len();
+                    stream.finish(serde_json::json!({ "count": count }));
+                }
+                Err(e) => ctx.print_error(&e.to_string()),
+            }
+        }
+
+        FindCommand::Occurrences { location, kind } => {
+            let loc = ParsedLocation::resolve(&location, app).await?;
+
+            match app
+                .lsp
+                .document_highlights(&loc.file, loc.line, loc.column)
+                .await
+            {
+                Ok(highlights) => {
+                    let mut highlights: Vec<_> = highlights
+                        .into_iter()
+                        .filter(|h| kind.is_empty() || kind.contains(&h.kind))
                         .collect();
+                    highlights.sort_by_key(|h| (h.range.start.line, h.range.start.character));
 
-                    let response = ReferencesResponse {
-                        count: project_refs.len(),
-                        references: project_refs
+                    let response = OccurrencesResponse {
+                        file: ctx.relative_path(&loc.file),
+                        count: highlights.len(),
+                        occurrences: highlights
                             .iter()
-                            .map(|l| {
-                                LocationOutput::from_path(&l.file, l.line, l.column, ctx.root())
+                            .map(|h| OccurrenceOutput {
+                                line: h.range.start.line + 1,
+                                column: h.range.start.character + 1,
+                                end_line: h.range.end.line + 1,
+                                end_column: h.range.end.character + 1,
+                                kind: h.kind.to_string(),
                             })
                             .collect(),
                     };
@@ -243,8 +303,40 @@ pub async fn execute(args: FindArgs, app: &App) -> Result<()> {
             }
         }
 
-        FindCommand::Def { location } => {
-            let loc = ParsedLocation::parse(&location)?.to_absolute()?;
+        FindCommand::Def {
+            location,
+            all: true,
+            ..
+        } => {
+            let loc = ParsedLocation::resolve(&location, app).await?;
+
+            match app.lsp.definitions(&loc.file, loc.line, loc.column).await {
+                Ok(candidates) => {
+                    let response = DefinitionsResponse {
+                        count: candidates.len(),
+                        definitions: candidates
+                            .iter()
+                            .map(|c| DefinitionCandidateOutput {
+                                location: LocationOutput::from_path(
+                                    &c.location.file,
+                                    c.location.line,
+                                    c.location.column,
+                                    ctx.root(),
+                                ),
+                                reason: c.reason.to_string(),
+                            })
+                            .collect(),
+                    };
+                    ctx.print_success_flat(response);
+                }
+                Err(e) => ctx.print_error(&e.to_string()),
+            }
+        }
+
+        FindCommand::Def {
+            location, follow, ..
+        } => {
+            let loc = ParsedLocation::resolve(&location, app).await?;
 
             match app
                 .lsp
@@ -252,13 +344,17 @@ pub async fn execute(args: FindArgs, app: &App) -> Result<()> {
                 .await
             {
                 Ok(Some(def)) => {
+                    let (def, chain) = if follow {
+                        follow_definition(def, app).await
+                    } else {
+                        (def, Vec::new())
+                    };
+                    let output = |l: &Location| {
+                        LocationOutput::from_path(&l.file, l.line, l.column, ctx.root())
+                    };
                     let response = DefinitionResponse {
-                        definition: Some(LocationOutput::from_path(
-                            &def.file,
-                            def.line,
-                            def.column,
-                            ctx.root(),
-                        )),
+                        definition: Some(output(&def)),
+                        chain: chain.iter().map(output).collect(),
                         message: None,
                     };
                     ctx.print_success_flat(response);
@@ -266,6 +362,7 @@ pub async fn execute(args: FindArgs, app: &App) -> Result<()> {
                 Ok(None) => {
                     let response = DefinitionResponse {
                         definition: None,
+                        chain: Vec::new(),
                         message: Some("No definition found".to_string()),
                     };
                     ctx.print_success_flat(response);
@@ -274,8 +371,32 @@ pub async fn execute(args: FindArgs, app: &App) -> Result<()> {
             }
         }
 
+        FindCommand::Decl { location } => {
+            let loc = ParsedLocation::resolve(&location, app).await?;
+
+            match app
+                .lsp
+                .goto_declaration(&loc.file, loc.line, loc.column)
+                .await
+            {
+                Ok(locations) => {
+                    let response = DeclarationsResponse {
+                        count: locations.len(),
+                        declarations: locations
+                            .iter()
+                            .map(|l| {
+                                LocationOutput::from_path(&l.file, l.line, l.column, ctx.root())
+                            })
+                            .collect(),
+                    };
+                    ctx.print_success_flat(response);
+                }
+                Err(e) => ctx.print_error(&e.to_string()),
+            }
+        }
+
         FindCommand::Typedef { location } => {
-            let loc = ParsedLocation::parse(&location)?.to_absolute()?;
+            let loc = ParsedLocation::resolve(&location, app).await?;
 
             match app
                 .lsp
@@ -290,6 +411,7 @@ pub async fn execute(args: FindArgs, app: &App) -> Result<()> {
                             def.column,
                             ctx.root(),
                         )),
+                        chain: Vec::new(),
                         message: None,
                     };
                     ctx.print_success_flat(response);
@@ -297,6 +419,7 @@ pub async fn execute(args: FindArgs, app: &App) -> Result<()> {
                 Ok(None) => {
                     let response = DefinitionResponse {
                         definition: None,
+                        chain: Vec::new(),
                         message: Some("No type definition found".to_string()),
                     };
                     ctx.print_success_flat(response);
@@ -307,7 +430,7 @@ pub async fn execute(args: FindArgs, app: &App) -> Result<()> {
 
         FindCommand::Impl { location, limit } => {
             let limit = limit.unwrap_or(cfg.lsp.impl_limit);
-            let loc = ParsedLocation::parse(&location)?.to_absolute()?;
+            let loc = ParsedLocation::resolve(&location, app).await?;
 
             match app
                 .lsp
@@ -335,6 +458,33 @@ pub async fn execute(args: FindArgs, app: &App) -> Result<()> {
     Ok(())
 }
 
+/// Hops before `--follow` gives up on a definition chain
+const MAX_FOLLOW_HOPS: usize = 8;
+
+/// Go to definition from `def` until it resolves to itself (or stops
+/// resolving), returning the final definition and the locations passed through
+async fn follow_definition(mut def: Location, app: &App) -> (Location, Vec<Location>) {
+    let same =
+        |a: &Location, b: &Location| a.file == b.file && a.line == b.line && a.column == b.column;
+
+    let mut chain: Vec<Location> = Vec::new();
+    for _ in 0..MAX_FOLLOW_HOPS {
+        let Ok(Some(next)) = app
+            .lsp
+            .goto_definition(&def.file, def.line, def.column)
+            .await
+        else {
+            break;
+        };
+        if same(&next, &def) || chain.iter().any(|c| same(c, &next)) {
+            break;
+        }
+        chain.push(std::mem::replace(&mut def, next));
+    }
+
+    (def, chain)
+}
+
 /// Parse comma-separated kind list into Vec<SymbolKind>
 fn parse_kind_list(
     kind_str: &Option<String>,
@@ -0,0 +1,223 @@
+//! Fix command implementation
+//!
+//! Apply whole-document source actions (organize imports, fix all, add
+//! missing imports) to files and directories in one step.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use serde::Serialize;
+
+use crate::app::App;
+use crate::infra::file_filter::{FileFilter, FileFilterConfig};
+use crate::infra::lsp::servers;
+use crate::models::lsp::CodeActionKind;
+use crate::models::symbol::Language;
+use crate::services::workspace_edit::{AppliedFile, apply_workspace_edit};
+
+#[derive(Args, Debug)]
+pub struct FixArgs {
+    /// Files or directories to fix
+    #[arg(required = true)]
+    pub paths: Vec<PathBuf>,
+
+    /// Source action kinds, applied in order (organizeImports, fixAll, addMissingImports)
+    #[arg(short, long, value_delimiter = ',', default_value = "organizeImports")]
+    pub kinds: Vec<CodeActionKind>,
+
+    /// Show the changes without applying them
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize)]
+struct FixResponse {
+    dry_run: bool,
+    kinds: Vec<String>,
+    checked: usize,
+    count: usize,
+    files: Vec<FixFileOutput>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<FixErrorOutput>,
+}
+
+#[derive(Serialize)]
+struct FixFileOutput {
+    file: String,
+    actions: Vec<FixActionOutput>,
+}
+
+#[derive(Serialize)]
+struct FixActionOutput {
+    kind: String,
+    title: String,
+    edit_count: usize,
+}
+
+#[derive(Serialize)]
+struct FixErrorOutput {
+    file: String,
+    error: String,
+}
+
+pub async fn execute(args: FixArgs, app: &App) -> Result<()> {
+    let ctx = &app.output;
+    let files = collect_files(&args.paths)?;
+
+    let mut applied = Vec::new();
+    let mut fixed = Vec::new();
+    let mut errors = Vec::new();
+    for file in &files {
+        match fix_file(file, &args.kinds, args.dry_run, &mut applied, app).await {
+            Ok(actions) if actions.is_empty() => {}
+            Ok(actions) => fixed.push(FixFileOutput {
+                file: ctx.relative_path(file),
+                actions,
+            }),
+            Err(e) => errors.push(FixErrorOutput {
+                file: ctx.relative_path(file),
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    let kinds: Vec<String> = args.kinds.iter().map(|k| k.to_string()).collect();
+    if !applied.is_empty() {
+        let paths: Vec<String> = args.paths.iter().map(|p| p.display().to_string()).collect();
+        let command = format!("fix {} ({})", paths.join(" "), kinds.join(","));
+        app.record_edit(&command, &applied);
+    }
+
+    ctx.print_success_flat(FixResponse {
+        dry_run: args.dry_run,
+        kinds,
+        checked: files.len(),
+        count: fixed.len(),
+        files: fixed,
+        errors,
+    });
+
+    Ok(())
+}
+
+/// Request and apply each kind in turn, so later kinds see earlier changes.
+///
+/// In a dry run every kind is computed against the unchanged file.
+async fn fix_file(
+    file: &Path,
+    kinds: &[CodeActionKind],
+    dry_run: bool,
+    applied: &mut Vec<AppliedFile>,
+    app: &App,
+) -> Result<Vec<FixActionOutput>> {
+    let mut actions = Vec::new();
+
+    for kind in kinds {
+        let available = app
+            .lsp
+            .source_actions(file, std::slice::from_ref(kind))
+            .await?;
+        let Some(action) = available
+            .iter()
+            .find(|a| a.is_preferred)
+            .or_else(|| available.first())
+        else {
+            continue;
+        };
+
+        let result = app.lsp.apply_code_action(file, action, !dry_run).await?;
+        if result.changes.is_empty() {
+            continue;
+        }
+        if !dry_run {
+            merge_applied(applied, apply_workspace_edit(&result.changes)?);
+        }
+
+        actions.push(FixActionOutput {
+            kind: kind.to_string(),
+            title: action.title.clone(),
+            edit_count: result.changes.iter().map(|c| c.edits.len()).sum(),
+        });
+    }
+
+    Ok(actions)
+}
+
+/// Keep the first original and the last update of each file
+fn merge_applied(all: &mut Vec<AppliedFile>, applied: Vec<AppliedFile>) {
+    for file in applied {
+        match all.iter_mut().find(|f| f.path == file.path) {
+            Some(existing) => existing.updated = file.updated,
+            None => all.push(file),
+        }
+    }
+}
+
+/// Expand directories to source files whose language server is installed
+fn collect_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut installed: HashMap<Language, bool> = HashMap::new();
+    let mut files = Vec::new();
+
+    for path in paths {
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("Path not found: {}", path.display()))?;
+        if !path.is_dir() {
+            files.push(path);
+            continue;
+        }
+
+        let filter = FileFilter::new(FileFilterConfig {
+            root: path.clone(),
+            respect_gitignore: true,
+            respect_symora_ignore: true,
+            include_hidden: false,
+            ..Default::default()
+        });
+        for file in filter.discover_files(&[]) {
+            let language = Language::from_path(&file);
+            if language == Language::Unknown {
+                continue;
+            }
+            let available = *installed
+                .entry(language)
+                .or_insert_with(|| servers::is_installed(language));
+            if available {
+                files.push(file);
+            }
+        }
+    }
+
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied(path: &str, original: &str, updated: &str) -> AppliedFile {
+        AppliedFile {
+            path: PathBuf::from(path),
+            original: Some(original.to_string()),
+            updated: Some(updated.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_merge_applied() {
+        let mut all = vec![applied("a.rs", "v1", "v2")];
+        merge_applied(
+            &mut all,
+            vec![applied("a.rs", "v2", "v3"), applied("b.rs", "b1", "b2")],
+        );
+
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].original.as_deref(), Some("v1"));
+        assert_eq!(all[0].updated.as_deref(), Some("v3"));
+        assert_eq!(all[1].path, PathBuf::from("b.rs"));
+    }
+}
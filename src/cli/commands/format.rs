@@ -0,0 +1,195 @@
+//! Format command implementation
+//!
+//! Document and range formatting through the language server, with
+//! indentation taken from `.editorconfig` or the `[format]` config section.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use clap::Args;
+use serde::Serialize;
+
+use crate::app::App;
+use crate::cli::ParsedLocation;
+use crate::models::lsp::{FileChangeWithEdits, FormattingOptions, Position, Range};
+use crate::services::diff::unified_diff;
+use crate::services::editorconfig::EditorConfig;
+use crate::services::workspace_edit::apply_text_edits;
+
+/// Context lines around each change in the diff
+const DIFF_CONTEXT: usize = 3;
+
+#[derive(Args, Debug)]
+pub struct FormatArgs {
+    /// File to format, optionally with a range
+    /// (file:line[:column]-line[:column], or file:line for a single line)
+    pub target: String,
+
+    /// Show the diff without applying it
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Indentation width (default: .editorconfig, then format.tab_size)
+    #[arg(long)]
+    pub tab_size: Option<u32>,
+
+    /// Indent with tabs
+    #[arg(long, conflicts_with = "use_spaces")]
+    pub use_tabs: bool,
+
+    /// Indent with spaces
+    #[arg(long)]
+    pub use_spaces: bool,
+}
+
+#[derive(Serialize)]
+struct FormatResponse {
+    file: String,
+    dry_run: bool,
+    changed: bool,
+    edit_count: usize,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    diff: String,
+}
+
+pub async fn execute(args: FormatArgs, app: &App) -> Result<()> {
+    let ctx = &app.output;
+
+    let (file, range) = parse_target(&args.target)?;
+    let file = ParsedLocation {
+        file,
+        line: 1,
+        column: 1,
+    }
+    .to_absolute()?
+    .file;
+    let options = formatting_options(&args, &file, app);
+
+    let edits = match app.lsp.format(&file, range, &options).await {
+        Ok(edits) => edits,
+        Err(e) => {
+            ctx.print_error(&e.to_string());
+            return Ok(());
+        }
+    };
+
+    let content = fs::read_to_string(&file).context("Failed to read file")?;
+    let formatted = match apply_text_edits(&content, &edits) {
+        Ok(formatted) => formatted,
+        Err(e) => {
+            ctx.print_error(&format!("Invalid formatting edits: {}", e));
+            return Ok(());
+        }
+    };
+    let changed = formatted != content;
+
+    if changed && !args.dry_run {
+        let command = format!("format {}", args.target);
+        let changes = [FileChangeWithEdits::new(file.clone(), edits.clone())];
+        if let Err(e) = app.apply_edit(&command, &changes) {
+            ctx.print_error(&format!("Failed to apply changes: {}", e));
+            return Ok(());
+        }
+    }
+
+    let relative = ctx.relative_path(&file);
+    ctx.print_success_flat(FormatResponse {
+        diff: unified_diff(&relative, &content, &formatted, DIFF_CONTEXT),
+        file: relative,
+        dry_run: args.dry_run,
+        changed,
+        edit_count: edits.len(),
+    });
+
+    Ok(())
+}
+
+/// Options from CLI flags, then `.editorconfig`, then config
+fn formatting_options(args: &FormatArgs, file: &Path, app: &App) -> FormattingOptions {
+    let defaults = &app.config().format;
+    let editorconfig = EditorConfig::resolve(file, app.root());
+
+    let insert_spaces = if args.use_tabs {
+        false
+    } else if args.use_spaces {
+        true
+    } else {
+        editorconfig.insert_spaces.unwrap_or(defaults.insert_spaces)
+    };
+
+    FormattingOptions {
+        tab_size: args
+            .tab_size
+            .or(editorconfig.tab_size())
+            .unwrap_or(defaults.tab_size),
+        insert_spaces,
+        trim_trailing_whitespace: editorconfig.trim_trailing_whitespace,
+        insert_final_newline: editorconfig.insert_final_newline,
+    }
+}
+
+/// Split `file[:line[:column]-line[:column]]` into the file and an LSP range.
+///
+/// A missing end column extends the range to the end of the end line.
+fn parse_target(input: &str) -> Result<(PathBuf, Option<Range>)> {
+    if let Some((start, end)) = input.rsplit_once('-')
+        && let Some((end_line, end_column)) = parse_position(end)
+        && let Ok(start) = ParsedLocation::parse(start)
+    {
+        if (end_line, end_column.unwrap_or(u32::MAX)) < (start.line, start.column) {
+            bail!("Invalid range '{}': end is before start", input);
+        }
+        let end = match end_column {
+            Some(column) => Position::from_cli(end_line, column),
+            None => Position::new(end_line, 0),
+        };
+        let range = Range::new(Position::from_cli(start.line, start.column), end);
+        return Ok((start.file, Some(range)));
+    }
+
+    match ParsedLocation::parse(input) {
+        Ok(loc) => {
+            let range = Range::new(Position::new(loc.line - 1, 0), Position::new(loc.line, 0));
+            Ok((loc.file, Some(range)))
+        }
+        Err(_) => Ok((PathBuf::from(input), None)),
+    }
+}
+
+/// `line[:column]` with 1-indexed positive values
+fn parse_position(s: &str) -> Option<(u32, Option<u32>)> {
+    let positive = |v: &str| v.parse::<u32>().ok().filter(|&n| n > 0);
+    match s.split_once(':') {
+        Some((line, column)) => Some((positive(line)?, Some(positive(column)?))),
+        None => Some((positive(s)?, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(sl: u32, sc: u32, el: u32, ec: u32) -> Option<Range> {
+        Some(Range::new(Position::new(sl, sc), Position::new(el, ec)))
+    }
+
+    #[test]
+    fn test_parse_target() {
+        let (file, r) = parse_target("src/main.rs").unwrap();
+        assert_eq!(file, PathBuf::from("src/main.rs"));
+        assert_eq!(r, None);
+
+        let (file, r) = parse_target("src/my-file.rs:10:5-20:3").unwrap();
+        assert_eq!(file, PathBuf::from("src/my-file.rs"));
+        assert_eq!(r, range(9, 4, 19, 2));
+
+        assert_eq!(parse_target("a.rs:10-20").unwrap().1, range(9, 0, 20, 0));
+        assert_eq!(parse_target("a.rs:7").unwrap().1, range(6, 0, 7, 0));
+        assert_eq!(
+            parse_target("build-2.rs").unwrap().0,
+            PathBuf::from("build-2.rs")
+        );
+        assert!(parse_target("a.rs:20-10").is_err());
+    }
+}
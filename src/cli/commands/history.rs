@@ -0,0 +1,86 @@
+//! History command implementation
+//!
+//! List mutations recorded in the undo journal, newest first.
+
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+
+use crate::app::App;
+use crate::cli::response::JournalEntryOutput;
+
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// Maximum number of entries to show
+    #[arg(short, long, default_value = "20")]
+    pub limit: usize,
+}
+
+#[derive(Serialize)]
+struct HistoryResponse {
+    count: usize,
+    total: usize,
+    entries: Vec<JournalEntryOutput>,
+}
+
+pub async fn execute(args: HistoryArgs, app: &App) -> Result<()> {
+    let ctx = &app.output;
+
+    match app.journal().entries() {
+        Ok(entries) => {
+            let output: Vec<_> = entries
+                .iter()
+                .rev()
+                .take(args.limit)
+                .map(JournalEntryOutput::from)
+                .collect();
+            ctx.print_success_flat(HistoryResponse {
+                count: output.len(),
+                total: entries.len(),
+                entries: output,
+            });
+        }
+        Err(e) => ctx.print_error(&format!("Failed to read undo journal: {}", e)),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::models::lsp::{FileChangeWithEdits, Position, Range, TextEdit};
+
+    #[tokio::test]
+    async fn test_history_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = App::with_root(dir.path().to_path_buf(), false)
+            .await
+            .unwrap();
+        let file = dir.path().join("a.rs");
+        fs::write(&file, "").unwrap();
+
+        for step in 1..=3 {
+            let edit = TextEdit {
+                range: Range::point(Position::new(0, 0)),
+                new_text: format!("{step}\n"),
+            };
+            app.apply_edit(
+                &format!("edit {step}"),
+                &[FileChangeWithEdits::new(file.clone(), vec![edit])],
+            )
+            .unwrap();
+        }
+
+        let (app, capture) = app.with_captured_output();
+        execute(HistoryArgs { limit: 2 }, &app).await.unwrap();
+        let response = capture.take_last().unwrap();
+        assert_eq!(response["count"], 2);
+        assert_eq!(response["total"], 3);
+        assert_eq!(response["entries"][0]["command"], "edit 3");
+        assert_eq!(response["entries"][1]["command"], "edit 2");
+        assert_eq!(response["entries"][0]["files"][0], "a.rs");
+    }
+}
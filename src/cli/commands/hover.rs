@@ -11,13 +11,13 @@ use crate::cli::response::{HoverResponse, LocationOutput};
 
 #[derive(Args, Debug)]
 pub struct HoverArgs {
-    /// File path with position (file:line:column)
+    /// Location (file:line:column, file#Class/method or Class/method)
     pub location: String,
 }
 
 pub async fn execute(args: HoverArgs, app: &App) -> Result<()> {
     let ctx = &app.output;
-    let loc = ParsedLocation::parse(&args.location)?.to_absolute()?;
+    let loc = ParsedLocation::resolve(&args.location, app).await?;
 
     match app.lsp.hover(&loc.file, loc.line, loc.column).await {
         Ok(Some(info)) => {
@@ -1,21 +1,26 @@
 //! Impact command implementation
 //!
-//! Analyze the impact of changing a symbol using LSP references.
+//! Analyze the impact of changing a symbol using LSP references and
+//! transitive incoming calls.
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use clap::Args;
 
 use crate::app::App;
 use crate::cli::ParsedLocation;
+use crate::cli::output::OutputContext;
 use crate::cli::response::{
-    ImpactFileOutput, ImpactReferenceOutput, ImpactResponse, LocationOutput,
+    ImpactCallerOutput, ImpactFileOutput, ImpactLayerOutput, ImpactReferenceOutput, ImpactResponse,
+    LocationOutput,
 };
+use crate::models::lsp::CallHierarchyItem;
 
 #[derive(Args, Debug)]
 pub struct ImpactArgs {
-    /// File path with position (file:line:column)
+    /// Location (file:line:column, file#Class/method or Class/method)
     pub location: String,
 
     /// Analysis depth (how many levels of callers to trace)
@@ -23,9 +28,15 @@ pub struct ImpactArgs {
     pub depth: u32,
 }
 
+/// Caller found during tracing, paired with the function it calls
+struct TracedCaller {
+    item: CallHierarchyItem,
+    callee: Option<String>,
+}
+
 pub async fn execute(args: ImpactArgs, app: &App) -> Result<()> {
     let ctx = &app.output;
-    let loc = ParsedLocation::parse(&args.location)?.to_absolute()?;
+    let loc = ParsedLocation::resolve(&args.location, app).await?;
 
     match app
         .lsp
@@ -62,12 +73,39 @@ pub async fn execute(args: ImpactArgs, app: &App) -> Result<()> {
                 })
                 .collect();
 
+            let limit = app.config().lsp.calls_limit;
+            let target_item = app
+                .lsp
+                .prepare_call_hierarchy(&loc.file, loc.line, loc.column)
+                .await
+                .ok()
+                .flatten()
+                .map(|item| (item.location.file, item.location.line, item.location.column));
+            let (traced, call_hierarchy_error) = trace_callers(
+                (loc.file.clone(), loc.line, loc.column),
+                target_item,
+                args.depth,
+                limit,
+                |file| ctx.is_project_path(file),
+                |(file, line, column)| async move {
+                    app.lsp
+                        .incoming_calls(&file, line, column)
+                        .await
+                        .map_err(|e| e.to_string())
+                },
+            )
+            .await;
+            let layers = build_layers(traced, ctx);
+
             let response = ImpactResponse {
                 target: LocationOutput::from_path(&loc.file, loc.line, loc.column, ctx.root()),
                 depth: args.depth,
                 total_references: project_refs.len(),
                 affected_files_count: affected_files.len(),
                 affected_files,
+                total_callers: layers.iter().map(|l| l.caller_count).sum(),
+                layers,
+                call_hierarchy_error,
             };
 
             ctx.print_success_flat(response);
@@ -77,3 +115,214 @@ pub async fn execute(args: ImpactArgs, app: &App) -> Result<()> {
 
     Ok(())
 }
+
+/// Position of a symbol: file, 1-indexed line and column
+type SymbolKey = (PathBuf, u32, u32);
+
+/// Breadth-first walk of incoming calls, one layer per depth level.
+///
+/// `incoming` looks up the callers of a symbol. Each symbol is visited at
+/// most once, so recursive and mutually recursive functions terminate.
+/// `target` is the cursor, which may sit anywhere in the symbol; callers are
+/// keyed by their call hierarchy item's location, so the target's own item
+/// (`target_item`, when the server prepared one) is what marks it visited. A
+/// failure on the target itself is reported; failures on deeper callers only
+/// prune that branch.
+async fn trace_callers<F, Fut>(
+    target: SymbolKey,
+    target_item: Option<SymbolKey>,
+    depth: u32,
+    limit: usize,
+    is_project: impl Fn(&Path) -> bool,
+    mut incoming: F,
+) -> (Vec<Vec<TracedCaller>>, Option<String>)
+where
+    F: FnMut(SymbolKey) -> Fut,
+    Fut: Future<Output = Result<Vec<CallHierarchyItem>, String>>,
+{
+    let mut layers: Vec<Vec<TracedCaller>> = Vec::new();
+    let mut visited: HashSet<SymbolKey> = HashSet::new();
+    visited.insert(target_item.unwrap_or_else(|| target.clone()));
+
+    let mut frontier: Vec<(SymbolKey, Option<String>)> = vec![(target, None)];
+
+    for level in 0..depth {
+        let mut layer = Vec::new();
+
+        for (key, name) in &frontier {
+            let calls = match incoming(key.clone()).await {
+                Ok(calls) => calls,
+                Err(e) if level == 0 => return (layers, Some(e)),
+                Err(_) => continue,
+            };
+
+            for item in calls.into_iter().take(limit) {
+                if !is_project(&item.location.file) {
+                    continue;
+                }
+                let key = (
+                    item.location.file.clone(),
+                    item.location.line,
+                    item.location.column,
+                );
+                if visited.insert(key) {
+                    layer.push(TracedCaller {
+                        item,
+                        callee: name.clone(),
+                    });
+                }
+            }
+        }
+
+        if layer.is_empty() {
+            break;
+        }
+
+        frontier = layer
+            .iter()
+            .map(|c| {
+                (
+                    (
+                        c.item.location.file.clone(),
+                        c.item.location.line,
+                        c.item.location.column,
+                    ),
+                    Some(c.item.name.clone()),
+                )
+            })
+            .collect();
+        layers.push(layer);
+    }
+
+    (layers, None)
+}
+
+fn build_layers(traced: Vec<Vec<TracedCaller>>, ctx: &OutputContext) -> Vec<ImpactLayerOutput> {
+    traced
+        .into_iter()
+        .enumerate()
+        .map(|(i, layer)| {
+            let files: BTreeSet<String> = layer
+                .iter()
+                .map(|c| ctx.relative_path(&c.item.location.file))
+                .collect();
+
+            let callers: Vec<_> = layer
+                .into_iter()
+                .map(|c| ImpactCallerOutput {
+                    name: c.item.name,
+                    kind: c.item.kind.to_string(),
+                    location: LocationOutput::from_path(
+                        &c.item.location.file,
+                        c.item.location.line,
+                        c.item.location.column,
+                        ctx.root(),
+                    ),
+                    callee: c.callee,
+                    call_site: c.item.call_site.as_ref().map(|site| {
+                        LocationOutput::from_path(&site.file, site.line, site.column, ctx.root())
+                    }),
+                })
+                .collect();
+
+            ImpactLayerOutput {
+                depth: i as u32 + 1,
+                caller_count: callers.len(),
+                callers,
+                files: files.into_iter().collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::symbol::{Location, SymbolKind};
+
+    fn item(name: &str, line: u32) -> CallHierarchyItem {
+        CallHierarchyItem {
+            name: name.to_string(),
+            kind: SymbolKind::Function,
+            location: Location::point(PathBuf::from("/p/lib.rs"), line, 1),
+            call_site: None,
+        }
+    }
+
+    /// Trace over a fixed call graph of `callee line -> callers`, from a
+    /// cursor inside the target rather than at its selection start
+    async fn trace(
+        graph: &[(u32, Vec<CallHierarchyItem>)],
+        depth: u32,
+    ) -> (Vec<Vec<String>>, Option<String>) {
+        let graph: HashMap<u32, Vec<CallHierarchyItem>> = graph.iter().cloned().collect();
+        let (layers, error) = trace_callers(
+            (PathBuf::from("/p/lib.rs"), 1, 5),
+            Some((PathBuf::from("/p/lib.rs"), 1, 1)),
+            depth,
+            10,
+            |file| file.starts_with("/p"),
+            |(_, line, _)| {
+                let calls = graph
+                    .get(&line)
+                    .cloned()
+                    .ok_or_else(|| format!("no call hierarchy at line {line}"));
+                async move { calls }
+            },
+        )
+        .await;
+        let names = layers
+            .into_iter()
+            .map(|layer| layer.into_iter().map(|c| c.item.name).collect())
+            .collect();
+        (names, error)
+    }
+
+    #[tokio::test]
+    async fn test_trace_callers_dedups_shared_callers() {
+        // target <- a, b; a <- shared; b <- shared
+        let graph = [
+            (1, vec![item("a", 10), item("b", 20)]),
+            (10, vec![item("shared", 30)]),
+            (20, vec![item("shared", 30)]),
+            (30, vec![]),
+        ];
+        let (layers, error) = trace(&graph, 5).await;
+        assert_eq!(layers, [vec!["a", "b"], vec!["shared"]]);
+        assert_eq!(error, None);
+    }
+
+    #[tokio::test]
+    async fn test_trace_callers_terminates_on_cycles() {
+        // target <- a <- b <- a (mutual recursion), b also calls the target,
+        // which is reported at its item location, not the cursor
+        let graph = [
+            (1, vec![item("a", 10)]),
+            (10, vec![item("b", 20)]),
+            (20, vec![item("a", 10), item("target", 1)]),
+        ];
+        let (layers, _) = trace(&graph, 10).await;
+        assert_eq!(layers, [vec!["a"], vec!["b"]]);
+    }
+
+    #[tokio::test]
+    async fn test_trace_callers_depth_cutoff() {
+        let graph = [
+            (1, vec![item("a", 10)]),
+            (10, vec![item("b", 20)]),
+            (20, vec![item("c", 30)]),
+        ];
+        assert_eq!(trace(&graph, 2).await.0, [vec!["a"], vec!["b"]]);
+        assert!(trace(&graph, 0).await.0.is_empty());
+
+        // Missing hierarchy below the target prunes the branch silently
+        let (layers, error) = trace(&graph, 5).await;
+        assert_eq!(layers, [vec!["a"], vec!["b"], vec!["c"]]);
+        assert_eq!(error, None);
+
+        // A failure on the target itself is reported
+        let (layers, error) = trace(&[], 3).await;
+        assert!(layers.is_empty());
+        assert_eq!(error.as_deref(), Some("no call hierarchy at line 1"));
+    }
+}
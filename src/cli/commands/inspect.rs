@@ -0,0 +1,337 @@
+//! Inspect command implementation
+//!
+//! Editor-style document annotations from LSP: inlay hints, folding ranges,
+//! selection ranges and code lenses.
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Result, bail};
+use clap::{Args, Subcommand};
+
+use crate::app::App;
+use crate::cli::ParsedLocation;
+use crate::cli::response::{
+    FoldOutput, FoldsResponse, HintOutput, HintsResponse, LensOutput, LensResponse, RangeOutput,
+    SelectionOutput, SelectionResponse,
+};
+use crate::models::lsp::{Position, Range};
+
+#[derive(Args, Debug)]
+pub struct InspectArgs {
+    #[command(subcommand)]
+    pub command: InspectCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum InspectCommand {
+    /// Inlay hints (inferred types, parameter names)
+    Hints {
+        /// File path to inspect
+        file: PathBuf,
+
+        /// Restrict to a line range (e.g., 10-40 or 25)
+        #[arg(long)]
+        lines: Option<LineRange>,
+
+        /// Filter by hint kind (type, parameter)
+        #[arg(long)]
+        kind: Option<String>,
+    },
+
+    /// Folding ranges (blocks, comments, imports, regions)
+    Folds {
+        /// File path to inspect
+        file: PathBuf,
+
+        /// Restrict to ranges overlapping a line range (e.g., 10-40)
+        #[arg(long)]
+        lines: Option<LineRange>,
+
+        /// Filter by folding kind (comment, imports, region, other)
+        #[arg(long)]
+        kind: Option<String>,
+    },
+
+    /// Selection ranges (innermost to outermost) at one or more positions
+    Selection {
+        /// Positions in a single file (file:line:column)
+        #[arg(required = true)]
+        locations: Vec<String>,
+    },
+
+    /// Code lenses (reference counts, run/debug actions)
+    Lens {
+        /// File path to inspect
+        file: PathBuf,
+
+        /// Restrict to a line range (e.g., 10-40)
+        #[arg(long)]
+        lines: Option<LineRange>,
+    },
+}
+
+/// Inclusive 1-indexed line range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl LineRange {
+    fn contains(&self, line: u32) -> bool {
+        line >= self.start && line <= self.end
+    }
+
+    fn overlaps(&self, start: u32, end: u32) -> bool {
+        start <= self.end && end >= self.start
+    }
+}
+
+impl FromStr for LineRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |v: &str| -> Result<u32, String> {
+            match v.trim().parse::<u32>() {
+                Ok(0) | Err(_) => Err(format!(
+                    "Invalid line '{}': must be a positive integer (1-indexed)",
+                    v
+                )),
+                Ok(n) => Ok(n),
+            }
+        };
+
+        let (start, end) = match s.split_once('-') {
+            Some((a, b)) => (parse(a)?, parse(b)?),
+            None => {
+                let n = parse(s)?;
+                (n, n)
+            }
+        };
+
+        if start > end {
+            return Err(format!("Invalid line range '{}': start > end", s));
+        }
+
+        Ok(Self { start, end })
+    }
+}
+
+pub async fn execute(args: InspectArgs, app: &App) -> Result<()> {
+    match args.command {
+        InspectCommand::Hints { file, lines, kind } => execute_hints(&file, lines, kind, app).await,
+        InspectCommand::Folds { file, lines, kind } => execute_folds(&file, lines, kind, app).await,
+        InspectCommand::Selection { locations } => execute_selection(&locations, app).await,
+        InspectCommand::Lens { file, lines } => execute_lens(&file, lines, app).await,
+    }
+}
+
+async fn execute_hints(
+    file: &Path,
+    lines: Option<LineRange>,
+    kind: Option<String>,
+    app: &App,
+) -> Result<()> {
+    let ctx = &app.output;
+    let abs_file = ctx.absolute_path(file);
+
+    let range = match lines {
+        Some(l) => Range::new(Position::new(l.start - 1, 0), Position::new(l.end, 0)),
+        None => {
+            let content = tokio::fs::read_to_string(&abs_file).await?;
+            let line_count = content.lines().count() as u32;
+            Range::new(Position::new(0, 0), Position::new(line_count, 0))
+        }
+    };
+
+    match app.lsp.inlay_hints(&abs_file, range).await {
+        Ok(hints) => {
+            let hints: Vec<_> = hints
+                .into_iter()
+                .filter(|h| lines.is_none_or(|l| l.contains(h.position.line + 1)))
+                .filter(|h| {
+                    kind.as_ref()
+                        .is_none_or(|k| h.kind.to_string().eq_ignore_ascii_case(k))
+                })
+                .map(|h| {
+                    let (line, column) = h.position.to_display();
+                    HintOutput {
+                        line,
+                        column,
+                        kind: h.kind.to_string(),
+                        label: h.label,
+                    }
+                })
+                .collect();
+
+            ctx.print_success_flat(HintsResponse {
+                file: ctx.relative_path(&abs_file),
+                count: hints.len(),
+                hints,
+            });
+        }
+        Err(e) => ctx.print_error(&e.to_string()),
+    }
+
+    Ok(())
+}
+
+async fn execute_folds(
+    file: &Path,
+    lines: Option<LineRange>,
+    kind: Option<String>,
+    app: &App,
+) -> Result<()> {
+    let ctx = &app.output;
+    let abs_file = ctx.absolute_path(file);
+
+    match app.lsp.folding_ranges(&abs_file).await {
+        Ok(ranges) => {
+            let ranges: Vec<_> = ranges
+                .into_iter()
+                .filter(|r| lines.is_none_or(|l| l.overlaps(r.start_line + 1, r.end_line + 1)))
+                .filter(|r| {
+                    kind.as_ref()
+                        .is_none_or(|k| r.kind.to_string().eq_ignore_ascii_case(k))
+                })
+                .map(|r| FoldOutput {
+                    start_line: r.start_line + 1,
+                    end_line: r.end_line + 1,
+                    kind: r.kind.to_string(),
+                    collapsed_text: r.collapsed_text,
+                })
+                .collect();
+
+            ctx.print_success_flat(FoldsResponse {
+                file: ctx.relative_path(&abs_file),
+                count: ranges.len(),
+                ranges,
+            });
+        }
+        Err(e) => ctx.print_error(&e.to_string()),
+    }
+
+    Ok(())
+}
+
+async fn execute_selection(locations: &[String], app: &App) -> Result<()> {
+    let ctx = &app.output;
+
+    let locations = locations
+        .iter()
+        .map(|l| ParsedLocation::parse(l)?.to_absolute())
+        .collect::<Result<Vec<_>>>()?;
+    let (file, positions) = selection_positions(&locations)?;
+
+    match app.lsp.selection_ranges(&file, positions.clone()).await {
+        Ok(ranges) => {
+            let selections: Vec<_> = positions
+                .iter()
+                .zip(ranges.iter())
+                .map(|(&(line, column), sel)| SelectionOutput {
+                    line,
+                    column,
+                    ranges: sel.to_ranges().iter().map(RangeOutput::from).collect(),
+                })
+                .collect();
+
+            ctx.print_success_flat(SelectionResponse {
+                file: ctx.relative_path(&file),
+                count: selections.len(),
+                selections,
+            });
+        }
+        Err(e) => ctx.print_error(&e.to_string()),
+    }
+
+    Ok(())
+}
+
+/// File and 1-indexed `(line, column)` positions of a selection request, as
+/// `LspService::selection_ranges` expects them
+fn selection_positions(locations: &[ParsedLocation]) -> Result<(PathBuf, Vec<(u32, u32)>)> {
+    let Some(first) = locations.first() else {
+        bail!("At least one location is required");
+    };
+    if locations.iter().any(|loc| loc.file != first.file) {
+        bail!("All selection positions must be in the same file");
+    }
+    let positions = locations.iter().map(|loc| (loc.line, loc.column)).collect();
+    Ok((first.file.clone(), positions))
+}
+
+async fn execute_lens(file: &Path, lines: Option<LineRange>, app: &App) -> Result<()> {
+    let ctx = &app.output;
+    let abs_file = ctx.absolute_path(file);
+
+    match app.lsp.code_lens(&abs_file).await {
+        Ok(lenses) => {
+            let lenses: Vec<_> = lenses
+                .into_iter()
+                .filter(|l| lines.is_none_or(|r| r.contains(l.range.start.line + 1)))
+                .map(|l| LensOutput {
+                    range: RangeOutput::from(&l.range),
+                    title: l.command.as_ref().map(|c| c.title.clone()),
+                    command: l.command.map(|c| c.command),
+                })
+                .collect();
+
+            ctx.print_success_flat(LensResponse {
+                file: ctx.relative_path(&abs_file),
+                count: lenses.len(),
+                lenses,
+            });
+        }
+        Err(e) => ctx.print_error(&e.to_string()),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_range_parse() {
+        assert_eq!(
+            "10-40".parse::<LineRange>().unwrap(),
+            LineRange { start: 10, end: 40 }
+        );
+        assert_eq!(
+            "25".parse::<LineRange>().unwrap(),
+            LineRange { start: 25, end: 25 }
+        );
+        assert!("0-5".parse::<LineRange>().is_err());
+        assert!("40-10".parse::<LineRange>().is_err());
+        assert!("a-b".parse::<LineRange>().is_err());
+    }
+
+    #[test]
+    fn test_selection_positions_stay_one_indexed() {
+        let loc = |file: &str, line, column| ParsedLocation {
+            file: PathBuf::from(file),
+            line,
+            column,
+        };
+
+        let (file, positions) =
+            selection_positions(&[loc("/p/a.rs", 1, 1), loc("/p/a.rs", 12, 8)]).unwrap();
+        assert_eq!(file, PathBuf::from("/p/a.rs"));
+        assert_eq!(positions, [(1, 1), (12, 8)]);
+
+        assert!(selection_positions(&[loc("/p/a.rs", 1, 1), loc("/p/b.rs", 1, 1)]).is_err());
+        assert!(selection_positions(&[]).is_err());
+    }
+
+    #[test]
+    fn test_line_range_overlaps() {
+        let range = LineRange { start: 10, end: 20 };
+        assert!(range.contains(10));
+        assert!(!range.contains(21));
+        assert!(range.overlaps(5, 10));
+        assert!(range.overlaps(1, 100));
+        assert!(!range.overlaps(21, 30));
+    }
+}
@@ -5,16 +5,24 @@
 pub mod actions;
 pub mod batch;
 pub mod calls;
+pub mod check_edit;
 pub mod config;
 pub mod daemon;
 pub mod diagnostics;
 pub mod doctor;
 pub mod edit;
 pub mod find;
+pub mod fix;
+pub mod format;
+pub mod history;
 pub mod hover;
 pub mod impact;
 pub mod init;
+pub mod inspect;
 pub mod rename;
 pub mod search;
 pub mod signature;
 pub mod status;
+pub mod tokens;
+pub mod types;
+pub mod undo;
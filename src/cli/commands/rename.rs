@@ -1,17 +1,25 @@
 //! Rename command - LSP-powered symbol renaming
 
+use std::collections::HashSet;
+
 use anyhow::Result;
 use clap::Args;
 use serde::Serialize;
 
 use crate::app::App;
 use crate::cli::ParsedLocation;
+use crate::cli::response::ResourceOperationOutput;
 use crate::models::lsp::FindSymbolsOptions;
 use crate::models::symbol::Symbol;
+use crate::services::diff::unified_diff;
+use crate::services::workspace_edit::prepare_workspace_edit;
+
+/// Context lines around each change in dry-run diffs
+const DIFF_CONTEXT: usize = 3;
 
 #[derive(Args, Debug)]
 pub struct RenameArgs {
-    /// File path with position (file:line:column)
+    /// Location (file:line:column, file#Class/method or Class/method)
     pub location: String,
 
     /// New name for the symbol
@@ -37,11 +45,16 @@ struct RenameResponse {
 struct FileChangeOutput {
     file: String,
     edit_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation: Option<ResourceOperationOutput>,
+    /// Unified diff of the file (dry run only)
+    #[serde(skip_serializing_if = "String::is_empty")]
+    diff: String,
 }
 
 pub async fn execute(args: RenameArgs, app: &App) -> Result<()> {
     let ctx = &app.output;
-    let loc = ParsedLocation::parse(&args.location)?.to_absolute()?;
+    let loc = ParsedLocation::resolve(&args.location, app).await?;
 
     if let Err(e) = loc.validate_position_async().await {
         ctx.print_error(&e.to_string());
@@ -72,15 +85,58 @@ pub async fn execute(args: RenameArgs, app: &App) -> Result<()> {
         .await
     {
         Ok(result) => {
+            let previews = if args.dry_run {
+                match prepare_workspace_edit(&result.changes) {
+                    Ok(files) => files,
+                    Err(e) => {
+                        ctx.print_error(&format!("Failed to preview rename: {}", e));
+                        return Ok(());
+                    }
+                }
+            } else {
+                Vec::new()
+            };
+
+            // Each file's diff covers all of its changes, so show it once
+            let mut diffed = HashSet::new();
             let changes: Vec<FileChangeOutput> = result
                 .changes
                 .iter()
-                .map(|fc| FileChangeOutput {
-                    file: ctx.relative_path(&fc.file),
-                    edit_count: fc.edit_count,
+                .map(|fc| {
+                    let file = ctx.relative_path(&fc.file);
+                    let diff = previews
+                        .iter()
+                        .find(|f| f.path == fc.file)
+                        .filter(|_| diffed.insert(&fc.file))
+                        .map(|f| {
+                            unified_diff(
+                                &file,
+                                f.original.as_deref().unwrap_or_default(),
+                                f.updated.as_deref().unwrap_or_default(),
+                                DIFF_CONTEXT,
+                            )
+                        })
+                        .unwrap_or_default();
+                    FileChangeOutput {
+                        file,
+                        edit_count: fc.edits.len(),
+                        operation: fc
+                            .operation
+                            .as_ref()
+                            .map(|op| ResourceOperationOutput::new(op, ctx.root())),
+                        diff,
+                    }
                 })
                 .collect();
 
+            let command = format!("rename {} {}", args.location, args.new_name);
+            if !args.dry_run
+                && let Err(e) = app.apply_edit(&command, &result.changes)
+            {
+                ctx.print_error(&format!("Failed to apply rename: {}", e));
+                return Ok(());
+            }
+
             let response = RenameResponse {
                 old_name,
                 new_name: args.new_name,
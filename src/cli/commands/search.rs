@@ -11,10 +11,12 @@ use std::process::Stdio;
 use anyhow::Result;
 use clap::{Args, Subcommand};
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
 use tokio::process::Command;
 
 use crate::app::App;
 use crate::infra::ast::{format_query_error, get_node_types, supported_languages};
+use crate::models::lsp::PositionEncoding;
 use crate::models::symbol::Language;
 
 #[derive(Args, Debug)]
@@ -83,12 +85,6 @@ pub enum SearchCommand {
 
 // === Response Types ===
 
-#[derive(Serialize)]
-struct TextSearchResponse {
-    count: usize,
-    matches: Vec<TextMatchOutput>,
-}
-
 #[derive(Serialize)]
 struct TextMatchOutput {
     file: String,
@@ -281,66 +277,12 @@ async fn execute_text_search(
 
     cmd.arg(pattern).arg(&search_path);
 
-    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
-
-    let output = cmd.output().await;
-
-    match output {
-        Ok(output) => {
-            let exit_code = output.status.code().unwrap_or(-1);
+    cmd.stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
 
-            match exit_code {
-                0 => {
-                    // Success - matches found, parse JSON output
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    let matches = parse_ripgrep_output(&stdout, ctx.root(), limit);
-                    let response = TextSearchResponse {
-                        count: matches.len(),
-                        matches,
-                    };
-                    ctx.print_success_flat(response);
-                }
-                1 => {
-                    // No matches found - valid success with empty results
-                    let response = TextSearchResponse {
-                        count: 0,
-                        matches: vec![],
-                    };
-                    ctx.print_success_flat(response);
-                }
-                2 => {
-                    // Error - invalid regex or other ripgrep error
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    let stderr = stderr.trim();
-                    if stderr.is_empty() {
-                        ctx.print_error("Search failed: invalid pattern or configuration");
-                    } else {
-                        // Extract just the error message (strip "rg: " prefix if present)
-                        let error_msg = stderr
-                            .lines()
-                            .find(|l| l.contains("error:") || l.starts_with("rg:"))
-                            .map(|l| l.trim_start_matches("rg: "))
-                            .unwrap_or(stderr);
-                        ctx.print_error(&format!("Search failed: {}", error_msg));
-                    }
-                }
-                _ => {
-                    // Other exit codes - check stderr for context
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    if stderr.contains("not found") || stderr.contains("No such file") {
-                        ctx.print_error(
-                            "ripgrep (rg) not found. Install: brew install ripgrep (macOS) or cargo install ripgrep",
-                        );
-                    } else {
-                        ctx.print_error(&format!(
-                            "Search failed (exit code {}): {}",
-                            exit_code,
-                            stderr.trim()
-                        ));
-                    }
-                }
-            }
-        }
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
         Err(e) => {
             if e.kind() == std::io::ErrorKind::NotFound {
                 ctx.print_error(
@@ -349,71 +291,139 @@ async fn execute_text_search(
             } else {
                 ctx.print_error(&format!("Failed to execute ripgrep: {}", e));
             }
+            return Ok(());
         }
-    }
+    };
 
-    Ok(())
-}
+    // Drain stderr concurrently so a chatty ripgrep cannot block on a full pipe
+    let stderr_task = child.stderr.take().map(|mut stderr| {
+        tokio::spawn(async move {
+            let mut buf = String::new();
+            let _ = stderr.read_to_string(&mut buf).await;
+            buf
+        })
+    });
+
+    // Stream matches as ripgrep reports them; stop early once the limit is hit
+    let mut stream = ctx.stream("matches");
+    let mut truncated = false;
+    if let Some(stdout) = child.stdout.take() {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(m) = parse_ripgrep_line(&line, ctx.root()) {
+                stream.push(&m);
+                if limit > 0 && stream.len() >= limit {
+                    truncated = true;
+                    let _ = child.start_kill();
+                    break;
+                }
+            }
+        }
+    }
 
-fn parse_ripgrep_output(
-    stdout: &str,
-    root: &std::path::Path,
-    limit: usize,
-) -> Vec<TextMatchOutput> {
-    let capacity = if limit == 0 { 1000 } else { limit.min(1000) };
-    let mut matches = Vec::with_capacity(capacity);
+    let status = child.wait().await;
+    let stderr = match stderr_task {
+        Some(task) => task.await.unwrap_or_default(),
+        None => String::new(),
+    };
 
-    for line in stdout.lines() {
-        if limit > 0 && matches.len() >= limit {
-            break;
+    let exit_code = match status {
+        Ok(_) if truncated => 0,
+        Ok(status) => status.code().unwrap_or(-1),
+        Err(e) => {
+            ctx.print_error(&format!("Failed to execute ripgrep: {}", e));
+            return Ok(());
         }
+    };
 
-        if line.is_empty() {
-            continue;
+    match exit_code {
+        // 0: matches found, 1: no matches - both valid results
+        0 | 1 => {
+            let count = stream.len();
+            stream.finish(serde_json::json!({ "count": count }));
         }
-
-        if let Ok(msg) = serde_json::from_str::<RgMessage>(line)
-            && msg.msg_type == "match"
-            && let Some(data) = msg.data
-        {
-            let file = data
-                .path
-                .map(|p| {
-                    let path = std::path::Path::new(&p.text);
-                    if let Ok(rel) = path.strip_prefix(root) {
-                        rel.display().to_string()
-                    } else {
-                        p.text
-                    }
-                })
-                .unwrap_or_default();
-
-            let text = data
-                .lines
-                .map(|l| l.text.trim_end().to_string())
-                .unwrap_or_default();
-
-            let line_number = data.line_number.unwrap_or(0);
-
-            let (column, matched) = data
-                .submatches
-                .and_then(|subs| subs.into_iter().next())
-                .map(|sub| (sub.start + 1, sub.matched.text))
-                .unwrap_or((1, String::new()));
-
-            matches.push(TextMatchOutput {
-                file,
-                line: line_number,
-                column,
-                text,
-                matched,
-                context_before: None,
-                context_after: None,
-            });
+        2 => {
+            // Error - invalid regex or other ripgrep error
+            let stderr = stderr.trim();
+            if stderr.is_empty() {
+                ctx.print_error("Search failed: invalid pattern or configuration");
+            } else {
+                // Extract just the error message (strip "rg: " prefix if present)
+                let error_msg = stderr
+                    .lines()
+                    .find(|l| l.contains("error:") || l.starts_with("rg:"))
+                    .map(|l| l.trim_start_matches("rg: "))
+                    .unwrap_or(stderr);
+                ctx.print_error(&format!("Search failed: {}", error_msg));
+            }
+        }
+        _ => {
+            // Other exit codes - check stderr for context
+            if stderr.contains("not found") || stderr.contains("No such file") {
+                ctx.print_error(
+                    "ripgrep (rg) not found. Install: brew install ripgrep (macOS) or cargo install ripgrep",
+                );
+            } else {
+                ctx.print_error(&format!(
+                    "Search failed (exit code {}): {}",
+                    exit_code,
+                    stderr.trim()
+                ));
+            }
         }
     }
 
-    matches
+    Ok(())
+}
+
+/// Parse a single `rg --json` line, returning the match it reports (if any)
+fn parse_ripgrep_line(line: &str, root: &std::path::Path) -> Option<TextMatchOutput> {
+    if line.is_empty() {
+        return None;
+    }
+
+    let msg = serde_json::from_str::<RgMessage>(line).ok()?;
+    if msg.msg_type != "match" {
+        return None;
+    }
+    let data = msg.data?;
+
+    let file = data
+        .path
+        .map(|p| {
+            let path = std::path::Path::new(&p.text);
+            if let Ok(rel) = path.strip_prefix(root) {
+                rel.display().to_string()
+            } else {
+                p.text
+            }
+        })
+        .unwrap_or_default();
+
+    let line = data.lines.map(|l| l.text).unwrap_or_default();
+
+    let line_number = data.line_number.unwrap_or(0);
+
+    // ripgrep reports byte offsets; columns count characters
+    let (column, matched) = data
+        .submatches
+        .and_then(|subs| subs.into_iter().next())
+        .map(|sub| {
+            let column = PositionEncoding::Utf32.offset_of(&line, sub.start as usize);
+            (column + 1, sub.matched.text)
+        })
+        .unwrap_or((1, String::new()));
+    let text = line.trim_end().to_string();
+
+    Some(TextMatchOutput {
+        file,
+        line: line_number,
+        column,
+        text,
+        matched,
+        context_before: None,
+        context_after: None,
+    })
 }
 
 /// Supported file types for text search
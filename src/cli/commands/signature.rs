@@ -11,13 +11,13 @@ use crate::cli::response::{ParameterOutput, SignatureHelpResponse, SignatureOutp
 
 #[derive(Args, Debug)]
 pub struct SignatureArgs {
-    /// File path with position (file:line:column)
+    /// Location (file:line:column, file#Class/method or Class/method)
     pub location: String,
 }
 
 pub async fn execute(args: SignatureArgs, app: &App) -> Result<()> {
     let ctx = &app.output;
-    let loc = ParsedLocation::parse(&args.location)?.to_absolute()?;
+    let loc = ParsedLocation::resolve(&args.location, app).await?;
 
     match app
         .lsp
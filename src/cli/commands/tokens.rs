@@ -0,0 +1,137 @@
+//! Tokens command implementation
+//!
+//! Semantic tokens from the language server, decoded into type and modifier
+//! names (e.g. a `variable` that is `mutable`, a `function` that is `async`).
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+
+use crate::app::App;
+use crate::cli::commands::inspect::LineRange;
+use crate::models::lsp::{Position, Range};
+use crate::services::text_buffer::strip_bom;
+
+#[derive(Args, Debug)]
+pub struct TokensArgs {
+    /// File path
+    pub file: PathBuf,
+
+    /// Restrict to a line range (e.g., 10-40 or 25)
+    #[arg(long)]
+    pub range: Option<LineRange>,
+
+    /// Filter by token type(s), comma-separated (variable,parameter,function)
+    #[arg(short = 't', long = "type", value_delimiter = ',')]
+    pub types: Vec<String>,
+
+    /// Keep tokens having all of these modifiers, comma-separated (mutable,async)
+    #[arg(short, long = "modifier", value_delimiter = ',')]
+    pub modifiers: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct TokensResponse {
+    file: String,
+    count: usize,
+    tokens: Vec<TokenOutput>,
+}
+
+#[derive(Serialize)]
+struct TokenOutput {
+    line: u32,
+    column: u32,
+    length: u32,
+    #[serde(rename = "type")]
+    token_type: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    modifiers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+}
+
+pub async fn execute(args: TokensArgs, app: &App) -> Result<()> {
+    let ctx = &app.output;
+    let file = if args.file.is_absolute() {
+        args.file.clone()
+    } else {
+        app.root().join(&args.file)
+    };
+
+    let range = args
+        .range
+        .map(|l| Range::new(Position::new(l.start - 1, 0), Position::new(l.end, 0)));
+
+    match app.lsp.semantic_tokens(&file, range).await {
+        Ok(tokens) => {
+            let content = tokio::fs::read_to_string(&file).await.unwrap_or_default();
+            let lines: Vec<&str> = strip_bom(&content).lines().collect();
+
+            let tokens: Vec<_> = tokens
+                .into_iter()
+                .filter(|t| {
+                    args.types.is_empty()
+                        || args
+                            .types
+                            .iter()
+                            .any(|ty| t.token_type.eq_ignore_ascii_case(ty))
+                })
+                .filter(|t| {
+                    args.modifiers
+                        .iter()
+                        .all(|m| t.modifiers.iter().any(|tm| tm.eq_ignore_ascii_case(m)))
+                })
+                .map(|t| {
+                    let text = lines.get(t.position.line as usize).and_then(|line| {
+                        token_text(line, t.position.character, t.length).map(String::from)
+                    });
+                    let (line, column) = t.position.to_display();
+                    TokenOutput {
+                        line,
+                        column,
+                        length: t.length,
+                        token_type: t.token_type,
+                        modifiers: t.modifiers,
+                        text,
+                    }
+                })
+                .collect();
+
+            ctx.print_success_flat(TokensResponse {
+                file: ctx.relative_path(&file),
+                count: tokens.len(),
+                tokens,
+            });
+        }
+        Err(e) => ctx.print_error(&e.to_string()),
+    }
+
+    Ok(())
+}
+
+/// Slice of `line` covered by a token, with `start` and `length` counted in
+/// characters
+fn token_text(line: &str, start: u32, length: u32) -> Option<&str> {
+    let byte_offset = |offset: u32| {
+        line.char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(line.len()))
+            .nth(offset as usize)
+    };
+    line.get(byte_offset(start)?..byte_offset(start + length)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_text() {
+        assert_eq!(token_text("let mut x = 1;", 8, 1), Some("x"));
+        assert_eq!(token_text("let 값 = \"🦀\";", 4, 1), Some("값"));
+        assert_eq!(token_text("s = \"🦀\" + t", 10, 1), Some("t"));
+        assert_eq!(token_text("short", 3, 10), None);
+    }
+}
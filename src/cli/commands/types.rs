@@ -0,0 +1,280 @@
+//! Types command implementation
+//!
+//! Type hierarchy operations (supertypes/subtypes) using LSP.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+
+use crate::app::App;
+use crate::cli::ParsedLocation;
+use crate::cli::output::OutputContext;
+use crate::cli::response::{LocationOutput, TypeHierarchyOutput, TypesResponse};
+use crate::error::LspError;
+use crate::models::lsp::TypeHierarchyItem;
+use crate::models::symbol::Location;
+
+#[derive(Args, Debug)]
+pub struct TypesArgs {
+    #[command(subcommand)]
+    pub command: TypesCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TypesCommand {
+    /// Find supertypes (what does this type extend/implement?)
+    Super {
+        /// Location (file:line:column, file#Class/method or Class/method)
+        location: String,
+
+        /// Levels of the hierarchy to expand
+        #[arg(short, long, default_value = "1")]
+        depth: u32,
+    },
+
+    /// Find subtypes (what extends/implements this type?)
+    Sub {
+        /// Location (file:line:column, file#Class/method or Class/method)
+        location: String,
+
+        /// Levels of the hierarchy to expand
+        #[arg(short, long, default_value = "1")]
+        depth: u32,
+    },
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Super,
+    Sub,
+}
+
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Super => "supertypes",
+            Self::Sub => "subtypes",
+        }
+    }
+}
+
+/// Flattened hierarchy node; children refer to indices in the node list
+struct Node {
+    item: TypeHierarchyItem,
+    children: Vec<usize>,
+}
+
+pub async fn execute(args: TypesArgs, app: &App) -> Result<()> {
+    let (location, depth, direction) = match args.command {
+        TypesCommand::Super { location, depth } => (location, depth, Direction::Super),
+        TypesCommand::Sub { location, depth } => (location, depth, Direction::Sub),
+    };
+
+    let ctx = &app.output;
+    let loc = ParsedLocation::resolve(&location, app).await?;
+
+    match fetch(&loc.file, loc.line, loc.column, direction, app).await {
+        Ok(roots) => {
+            let (nodes, root_ids) = expand(roots, depth, |loc| async move {
+                fetch(&loc.file, loc.line, loc.column, direction, app).await
+            })
+            .await;
+            let types: Vec<_> = root_ids
+                .iter()
+                .map(|&i| build_tree(&nodes, i, ctx))
+                .collect();
+            let response = TypesResponse {
+                direction: direction.as_str().to_string(),
+                depth,
+                count: nodes.len(),
+                types,
+            };
+            ctx.print_success_flat(response);
+        }
+        Err(e) => ctx.print_error(&e.to_string()),
+    }
+
+    Ok(())
+}
+
+async fn fetch(
+    file: &Path,
+    line: u32,
+    column: u32,
+    direction: Direction,
+    app: &App,
+) -> Result<Vec<TypeHierarchyItem>, LspError> {
+    match direction {
+        Direction::Super => app.lsp.supertypes(file, line, column).await,
+        Direction::Sub => app.lsp.subtypes(file, line, column).await,
+    }
+}
+
+/// Expand the hierarchy breadth-first up to `depth` levels.
+///
+/// `related` looks up the next level for a type. Types already present in
+/// the tree are not expanded again, which keeps diamond inheritance and
+/// cyclic server responses finite.
+async fn expand<F, Fut>(
+    roots: Vec<TypeHierarchyItem>,
+    depth: u32,
+    mut related: F,
+) -> (Vec<Node>, Vec<usize>)
+where
+    F: FnMut(Location) -> Fut,
+    Fut: Future<Output = Result<Vec<TypeHierarchyItem>, LspError>>,
+{
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut seen: HashSet<(PathBuf, u32, u32)> = HashSet::new();
+
+    let mut push = |nodes: &mut Vec<Node>, item: TypeHierarchyItem| -> Option<usize> {
+        let key = (
+            item.location.file.clone(),
+            item.location.line,
+            item.location.column,
+        );
+        if !seen.insert(key) {
+            return None;
+        }
+        nodes.push(Node {
+            item,
+            children: Vec::new(),
+        });
+        Some(nodes.len() - 1)
+    };
+
+    let root_ids: Vec<usize> = roots
+        .into_iter()
+        .filter_map(|item| push(&mut nodes, item))
+        .collect();
+
+    let mut frontier = root_ids.clone();
+    for _ in 1..depth {
+        let mut next = Vec::new();
+
+        for parent in frontier {
+            let loc = nodes[parent].item.location.clone();
+            // Deeper levels are best-effort; a failure only prunes this branch
+            let Ok(items) = related(loc).await else {
+                continue;
+            };
+            for item in items {
+                if let Some(id) = push(&mut nodes, item) {
+                    nodes[parent].children.push(id);
+                    next.push(id);
+                }
+            }
+        }
+
+        if next.is_empty() {
+            break;
+        }
+        frontier = next;
+    }
+
+    (nodes, root_ids)
+}
+
+fn build_tree(nodes: &[Node], id: usize, ctx: &OutputContext) -> TypeHierarchyOutput {
+    let node = &nodes[id];
+    let loc = &node.item.location;
+    TypeHierarchyOutput {
+        name: node.item.name.clone(),
+        kind: node.item.kind.to_string(),
+        location: LocationOutput::from_path(&loc.file, loc.line, loc.column, ctx.root()),
+        detail: node.item.detail.clone(),
+        children: node
+            .children
+            .iter()
+            .map(|&child| build_tree(nodes, child, ctx))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::models::symbol::SymbolKind;
+
+    fn item(name: &str, line: u32) -> TypeHierarchyItem {
+        TypeHierarchyItem {
+            name: name.to_string(),
+            kind: SymbolKind::Class,
+            location: Location::point(PathBuf::from("/p/types.rs"), line, 1),
+            detail: None,
+        }
+    }
+
+    /// Expand `roots` over a fixed hierarchy of `type line -> related types`
+    /// and render the trees as `name(children...)`
+    async fn expand_tree(
+        roots: Vec<TypeHierarchyItem>,
+        graph: &[(u32, Vec<TypeHierarchyItem>)],
+        depth: u32,
+    ) -> Vec<String> {
+        let graph: HashMap<u32, Vec<TypeHierarchyItem>> = graph.iter().cloned().collect();
+        let (nodes, root_ids) = expand(roots, depth, |loc| {
+            let items = graph
+                .get(&loc.line)
+                .cloned()
+                .ok_or_else(|| LspError::Protocol("no hierarchy".to_string()));
+            async move { items }
+        })
+        .await;
+
+        let ctx = OutputContext::new(PathBuf::from("/p"));
+        fn render(node: &TypeHierarchyOutput) -> String {
+            let children: Vec<_> = node.children.iter().map(render).collect();
+            if children.is_empty() {
+                node.name.clone()
+            } else {
+                format!("{}({})", node.name, children.join(" "))
+            }
+        }
+        root_ids
+            .iter()
+            .map(|&id| render(&build_tree(&nodes, id, &ctx)))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_expand_depth_limit() {
+        let graph = [
+            (1, vec![item("B", 2), item("C", 3)]),
+            (2, vec![item("D", 4)]),
+            (4, vec![item("E", 5)]),
+        ];
+        let roots = || vec![item("A", 1)];
+
+        assert_eq!(expand_tree(roots(), &graph, 1).await, ["A"]);
+        assert_eq!(expand_tree(roots(), &graph, 2).await, ["A(B C)"]);
+        assert_eq!(expand_tree(roots(), &graph, 3).await, ["A(B(D) C)"]);
+        // Lookup failures below the roots only prune their branch
+        assert_eq!(expand_tree(roots(), &graph, 10).await, ["A(B(D(E)) C)"]);
+    }
+
+    #[tokio::test]
+    async fn test_expand_inheritance_cycle() {
+        // A -> B -> A, with B also reaching a diamond through C
+        let graph = [
+            (1, vec![item("B", 2), item("C", 3)]),
+            (2, vec![item("A", 1), item("D", 4)]),
+            (3, vec![item("D", 4)]),
+            (4, vec![item("B", 2)]),
+        ];
+        assert_eq!(
+            expand_tree(vec![item("A", 1)], &graph, 10).await,
+            ["A(B(D) C)"]
+        );
+
+        // Duplicate roots are listed once
+        assert_eq!(
+            expand_tree(vec![item("A", 1), item("A", 1)], &graph, 1).await,
+            ["A"]
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Undo command implementation
+//!
+//! Revert the most recent edits, renames and code actions recorded in the
+//! undo journal.
+
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+
+use crate::app::App;
+use crate::cli::response::JournalEntryOutput;
+
+#[derive(Args, Debug)]
+pub struct UndoArgs {
+    /// Number of recorded steps to undo
+    #[arg(short = 'n', long, default_value = "1")]
+    pub steps: usize,
+
+    /// Undo even if files changed after the recorded step
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Serialize)]
+struct UndoResponse {
+    count: usize,
+    undone: Vec<JournalEntryOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+pub async fn execute(args: UndoArgs, app: &App) -> Result<()> {
+    let ctx = &app.output;
+
+    match app.journal().undo(args.steps, args.force) {
+        Ok(undone) => {
+            let message = undone.is_empty().then(|| "Nothing to undo".to_string());
+            ctx.print_success_flat(UndoResponse {
+                count: undone.len(),
+                undone: undone.iter().map(JournalEntryOutput::from).collect(),
+                message,
+            });
+        }
+        Err(e) => ctx.print_error(&e.to_string()),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::models::lsp::{FileChangeWithEdits, Position, Range, ResourceOperation, TextEdit};
+
+    fn replace_all(file: std::path::PathBuf, text: &str) -> FileChangeWithEdits {
+        let edit = TextEdit {
+            range: Range::new(Position::new(0, 0), Position::new(u32::MAX, 0)),
+            new_text: text.to_string(),
+        };
+        FileChangeWithEdits::new(file, vec![edit])
+    }
+
+    async fn undo(app: &App, steps: usize) -> serde_json::Value {
+        let (app, capture) = app.with_captured_output();
+        execute(
+            UndoArgs {
+                steps,
+                force: false,
+            },
+            &app,
+        )
+        .await
+        .unwrap();
+        capture.take_last().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_undo_steps_past_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = App::with_root(dir.path().to_path_buf(), false)
+            .await
+            .unwrap();
+        let file = dir.path().join("a.rs");
+        fs::write(&file, "v1\n").unwrap();
+
+        app.apply_edit("edit 1", &[replace_all(file.clone(), "v2\n")])
+            .unwrap();
+        app.apply_edit("edit 2", &[replace_all(file.clone(), "v3\n")])
+            .unwrap();
+
+        let response = undo(&app, 5).await;
+        assert_eq!(response["success"], true);
+        assert_eq!(response["count"], 2);
+        assert_eq!(response["undone"][0]["command"], "edit 2");
+        assert_eq!(response["undone"][1]["command"], "edit 1");
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v1\n");
+
+        let response = undo(&app, 1).await;
+        assert_eq!(response["count"], 0);
+        assert_eq!(response["message"], "Nothing to undo");
+    }
+
+    #[tokio::test]
+    async fn test_undo_created_and_deleted_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = App::with_root(dir.path().to_path_buf(), false)
+            .await
+            .unwrap();
+        let created = dir.path().join("new.rs");
+        let deleted = dir.path().join("old.rs");
+        fs::write(&deleted, "old\r\n").unwrap();
+
+        let changes = [
+            FileChangeWithEdits {
+                operation: Some(ResourceOperation::Create {
+                    overwrite: false,
+                    ignore_if_exists: false,
+                }),
+                ..replace_all(created.clone(), "new\n")
+            },
+            FileChangeWithEdits::operation(
+                deleted.clone(),
+                ResourceOperation::Delete {
+                    recursive: false,
+                    ignore_if_not_exists: false,
+                },
+            ),
+        ];
+        app.apply_edit("move", &changes).unwrap();
+        assert_eq!(fs::read_to_string(&created).unwrap(), "new\n");
+        assert!(!deleted.exists());
+
+        let response = undo(&app, 1).await;
+        assert_eq!(response["count"], 1);
+        assert!(!created.exists());
+        assert_eq!(fs::read_to_string(&deleted).unwrap(), "old\r\n");
+    }
+}
@@ -0,0 +1,184 @@
+//! Expected-text guards for mutating commands
+//!
+//! Edits are often computed from positions looked up earlier; if the file
+//! changed in between, those positions point at different text. `--expect`
+//! states what the target range should contain, either literally or as the
+//! `hash:<hex>` of it, and the command refuses to write when it does not.
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::cli::OutputContext;
+use crate::models::lsp::{Position, Range};
+use crate::services::text_buffer::TextBuffer;
+
+const HASH_PREFIX: &str = "hash:";
+
+/// Expected content of an edit's target range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expectation {
+    Text(String),
+    /// Hash of the text as reported by [`text_hash`]
+    Hash(u64),
+}
+
+impl FromStr for Expectation {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.strip_prefix(HASH_PREFIX) {
+            Some(hex) => u64::from_str_radix(hex.trim(), 16)
+                .map(Self::Hash)
+                .map_err(|_| format!("Invalid hash '{hex}'. Expected up to 16 hex digits")),
+            None => Ok(Self::Text(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(text) => f.write_str(text),
+            Self::Hash(hash) => write!(f, "{HASH_PREFIX}{hash:016x}"),
+        }
+    }
+}
+
+impl Expectation {
+    /// Whether `current` satisfies this expectation. Text also matches when
+    /// it differs only in surrounding whitespace or line endings.
+    pub fn matches(&self, current: &str) -> bool {
+        match self {
+            Self::Text(text) => {
+                text == current || text.replace("\r\n", "\n").trim() == current.trim()
+            }
+            Self::Hash(hash) => *hash == fnv1a(current),
+        }
+    }
+}
+
+/// Stable `hash:<hex>` of `text` (64-bit FNV-1a) for use with `--expect`
+pub fn text_hash(text: &str) -> String {
+    Expectation::Hash(fnv1a(text)).to_string()
+}
+
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Target text that does not match `--expect`
+#[derive(Debug, Serialize)]
+pub struct Conflict {
+    pub file: String,
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    pub expected: String,
+    pub current_text: String,
+    pub current_hash: String,
+}
+
+/// Text of `range` (0-indexed, character columns) with LF line breaks.
+///
+/// An empty range is an insertion point and stands for its whole line.
+pub fn target_text(buffer: &TextBuffer, range: &Range) -> (Range, String) {
+    let range = if range.start == range.end {
+        Range::new(
+            Position::new(range.start.line, 0),
+            Position::new(range.start.line, u32::MAX),
+        )
+    } else {
+        range.clone()
+    };
+    let start = buffer.offset(range.start);
+    let end = buffer.offset(range.end).max(start);
+    let text = buffer.text()[start..end].replace("\r\n", "\n");
+    (range, text)
+}
+
+/// Check `expect` against the current text of `range` in `file`.
+///
+/// Prints a conflict error showing the current text and returns `false` when
+/// the edit must not proceed.
+pub fn guard(
+    ctx: &OutputContext,
+    file: &Path,
+    range: &Range,
+    expect: Option<&Expectation>,
+) -> Result<bool> {
+    let Some(expect) = expect else {
+        return Ok(true);
+    };
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let buffer = TextBuffer::new(&content);
+    let (range, current) = target_text(&buffer, range);
+    if expect.matches(&current) {
+        return Ok(true);
+    }
+
+    let column = |pos: Position| {
+        let line = buffer.lines().nth(pos.line as usize).unwrap_or_default();
+        pos.character.min(line.chars().count() as u32) + 1
+    };
+    let conflict = Conflict {
+        file: ctx.relative_path(file),
+        start_line: range.start.line + 1,
+        start_column: column(range.start),
+        end_line: range.end.line + 1,
+        end_column: column(range.end),
+        expected: expect.to_string(),
+        current_hash: text_hash(&current),
+        current_text: current,
+    };
+    ctx.print_error_with(
+        &format!(
+            "Text at {}:{}:{} does not match --expect. No files were modified.",
+            conflict.file, conflict.start_line, conflict.start_column
+        ),
+        "conflict",
+        conflict,
+    );
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expectation_matches() {
+        let text: Expectation = "fn main() {}".parse().unwrap();
+        assert!(text.matches("fn main() {}"));
+        assert!(text.matches("  fn main() {}\n"));
+        assert!(!text.matches("fn other() {}"));
+
+        let hash: Expectation = text_hash("let x = 1;").parse().unwrap();
+        assert_eq!(hash.to_string(), text_hash("let x = 1;"));
+        assert!(hash.matches("let x = 1;"));
+        assert!(!hash.matches("let x = 2;"));
+
+        assert!("hash:xyz".parse::<Expectation>().is_err());
+        // FNV-1a is stable across runs and platforms
+        assert_eq!(text_hash(""), "hash:cbf29ce484222325");
+    }
+
+    #[test]
+    fn test_target_text() {
+        let buffer = TextBuffer::new("fn a() {\r\n    1\r\n}\r\n");
+        let range = Range::new(Position::new(0, 3), Position::new(2, 1));
+        assert_eq!(target_text(&buffer, &range).1, "a() {\n    1\n}");
+
+        // An insertion point stands for its line, without the terminator
+        let (range, text) = target_text(&buffer, &Range::point(Position::new(1, 2)));
+        assert_eq!(text, "    1");
+        assert_eq!(range.start, Position::new(1, 0));
+    }
+}
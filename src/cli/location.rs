@@ -4,6 +4,11 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, bail};
 
+use crate::app::App;
+use crate::infra::lsp::servers;
+use crate::models::lsp::FindSymbolsOptions;
+use crate::models::symbol::Symbol;
+
 #[derive(Debug, Clone)]
 pub struct ParsedLocation {
     pub file: PathBuf,
@@ -167,12 +172,12 @@ impl ParsedLocation {
         while let Some(line) = lines.next_line().await? {
             line_num += 1;
             if line_num == self.line {
-                let col_max = line.len() + 1;
-                if self.column as usize > col_max {
+                let chars = line.chars().count();
+                if self.column as usize > chars + 1 {
                     bail!(
                         "Column {} exceeds line length ({} chars) at line {}",
                         self.column,
-                        line.len(),
+                        chars,
                         self.line
                     );
                 }
@@ -202,12 +207,12 @@ impl ParsedLocation {
         }
 
         if let Some(line_content) = lines.get((self.line - 1) as usize) {
-            let col_max = line_content.len() + 1;
-            if self.column as usize > col_max {
+            let chars = line_content.chars().count();
+            if self.column as usize > chars + 1 {
                 bail!(
                     "Column {} exceeds line length ({} chars) at line {}",
                     self.column,
-                    line_content.len(),
+                    chars,
                     self.line
                 );
             }
@@ -217,6 +222,165 @@ impl ParsedLocation {
     }
 }
 
+impl ParsedLocation {
+    /// Resolve a position given as `file:line[:column]`, `file#Class/method`
+    /// (document symbols) or a bare name path such as `Class/method`
+    /// (workspace symbols of the project's languages)
+    pub async fn resolve(input: &str, app: &App) -> Result<Self> {
+        let input = input.trim();
+        if let Some((file, path)) = input.split_once('#') {
+            return Self::resolve_in_file(Path::new(file), path, app).await;
+        }
+
+        match Self::parse(input) {
+            Ok(loc) => loc.to_absolute(),
+            Err(_) if is_name_path(input) => Self::resolve_in_workspace(input, app).await,
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn resolve_in_file(file: &Path, path: &str, app: &App) -> Result<Self> {
+        if path.is_empty() {
+            bail!("Missing symbol path after '#'. Example: src/main.rs#Class/method");
+        }
+        let file = Self {
+            file: file.to_path_buf(),
+            line: 1,
+            column: 1,
+        }
+        .to_absolute()?
+        .file;
+
+        let mut symbols = app
+            .lsp
+            .find_symbols(&file, FindSymbolsOptions::new().with_depth(10))
+            .await?;
+        Symbol::compute_paths_for_all(&mut symbols);
+
+        let candidates = Symbol::filter_by_path(&symbols, path);
+        if candidates.is_empty() {
+            bail!("Symbol not found: {} in {}", path, file.display());
+        }
+        Ok(select_symbol(path, candidates, app.root())?.into())
+    }
+
+    async fn resolve_in_workspace(path: &str, app: &App) -> Result<Self> {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let query = name.split('[').next().unwrap_or(name).replace('*', "");
+
+        let languages = match &app.config().project.languages {
+            configured if !configured.is_empty() => configured.clone(),
+            _ => app.project.detect_languages(),
+        };
+
+        let mut candidates: Vec<Symbol> = Vec::new();
+        let mut error = None;
+        for language in languages.into_iter().filter(|l| servers::is_installed(*l)) {
+            match app.lsp.workspace_symbols(&query, language).await {
+                Ok(symbols) => candidates.extend(
+                    symbols
+                        .into_iter()
+                        .map(with_container_path)
+                        .filter(|s| s.matches_path(path)),
+                ),
+                Err(e) => error = error.or(Some(e)),
+            }
+        }
+        let mut seen = Vec::new();
+        candidates.retain(|s| {
+            let new = !seen.contains(&s.location);
+            seen.push(s.location.clone());
+            new
+        });
+
+        if candidates.is_empty() {
+            match error {
+                Some(e) => bail!("Symbol not found: {} ({})", path, e),
+                None => bail!(
+                    "Symbol not found: {}. Use file#{} to search a single file",
+                    path,
+                    path
+                ),
+            }
+        }
+        Ok(select_symbol(path, candidates, app.root())?.into())
+    }
+}
+
+impl From<Symbol> for ParsedLocation {
+    fn from(symbol: Symbol) -> Self {
+        Self {
+            file: symbol.location.file,
+            line: symbol.location.line,
+            column: symbol.location.column,
+        }
+    }
+}
+
+/// Bare name paths contain no position separator and name no existing file
+fn is_name_path(input: &str) -> bool {
+    !input.is_empty() && !input.contains(':') && !Path::new(input).exists()
+}
+
+/// Name path of a workspace symbol from its container (`impl Foo`,
+/// `pkg.Class`, `mod::Type`) and name
+fn with_container_path(mut symbol: Symbol) -> Symbol {
+    let mut segments: Vec<&str> = symbol
+        .container
+        .as_deref()
+        .unwrap_or_default()
+        .split(|c: char| matches!(c, '.' | ':' | '/') || c.is_whitespace())
+        .map(|s| s.split('<').next().unwrap_or(s))
+        .filter(|s| !s.is_empty())
+        .collect();
+    segments.push(&symbol.name);
+    symbol.name_path = Some(segments.join("/"));
+    symbol
+}
+
+/// The only candidate, or the only one whose name path equals `pattern`
+fn select_symbol(pattern: &str, mut candidates: Vec<Symbol>, root: &Path) -> Result<Symbol> {
+    if candidates.len() == 1 {
+        return Ok(candidates.remove(0));
+    }
+
+    let exact = pattern.trim_start_matches('/');
+    let exact_matches: Vec<usize> = candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.path() == exact)
+        .map(|(i, _)| i)
+        .collect();
+    if let [i] = exact_matches[..] {
+        return Ok(candidates.remove(i));
+    }
+
+    let listed: Vec<String> = candidates
+        .iter()
+        .map(|s| {
+            let file = s
+                .location
+                .file
+                .strip_prefix(root)
+                .unwrap_or(&s.location.file);
+            format!(
+                "  {} ({}) {}:{}:{}",
+                s.path(),
+                s.kind,
+                file.display(),
+                s.location.line,
+                s.location.column
+            )
+        })
+        .collect();
+    bail!(
+        "Ambiguous symbol '{}' matches {} symbols:\n{}\nUse a longer name path (e.g. Class/method), an overload index (method[1]) or file:line:column",
+        pattern,
+        candidates.len(),
+        listed.join("\n")
+    )
+}
+
 impl std::fmt::Display for ParsedLocation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}:{}:{}", self.file.display(), self.line, self.column)
@@ -226,6 +390,7 @@ impl std::fmt::Display for ParsedLocation {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::symbol::{Location, SymbolKind};
 
     #[test]
     fn test_parse_full_location() {
@@ -311,4 +476,48 @@ mod tests {
         };
         assert!(loc_invalid.validate_position_with_content(content).is_err());
     }
+
+    fn symbol(name: &str, container: Option<&str>, line: u32) -> Symbol {
+        let symbol = Symbol::new(
+            name.to_string(),
+            SymbolKind::Method,
+            Location::new(PathBuf::from("/p/src/lib.rs"), line, 5, line, 9),
+        );
+        match container {
+            Some(c) => symbol.with_container(c),
+            None => symbol,
+        }
+    }
+
+    #[test]
+    fn test_with_container_path() {
+        let s = with_container_path(symbol("load", Some("impl Config<T>"), 1));
+        assert_eq!(s.name_path.as_deref(), Some("impl/Config/load"));
+
+        let s = with_container_path(symbol("submit", Some("com.shop.OrderService"), 1));
+        assert_eq!(s.name_path.as_deref(), Some("com/shop/OrderService/submit"));
+
+        let s = with_container_path(symbol("main", None, 1));
+        assert_eq!(s.name_path.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn test_select_symbol() {
+        let root = Path::new("/p");
+        let only = vec![with_container_path(symbol("load", Some("Config"), 3))];
+        assert_eq!(select_symbol("load", only, root).unwrap().location.line, 3);
+
+        let candidates = vec![
+            with_container_path(symbol("load", Some("Config"), 3)),
+            with_container_path(symbol("load", Some("Config/Inner"), 9)),
+        ];
+        let exact = select_symbol("Config/load", candidates.clone(), root).unwrap();
+        assert_eq!(exact.location.line, 3);
+
+        let err = select_symbol("load", candidates, root)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Ambiguous symbol 'load' matches 2 symbols"));
+        assert!(err.contains("Config/Inner/load (method) src/lib.rs:9:5"));
+    }
 }
@@ -3,20 +3,26 @@
 //! Provides command-line interface using clap derive macros.
 
 pub mod commands;
+pub mod expect;
 pub mod location;
 pub mod output;
 pub mod response;
+pub mod text;
 
 pub use location::ParsedLocation;
-pub use output::OutputContext;
+pub use output::{OutputCapture, OutputContext, OutputFormat, RecordStream};
 
 use clap::{Parser, Subcommand};
 
+use crate::app::App;
+
 use commands::{
-    actions::ActionsArgs, batch::BatchArgs, calls::CallsArgs, config::ConfigArgs,
-    daemon::DaemonArgs, diagnostics::DiagnosticsArgs, doctor::DoctorArgs, edit::EditArgs,
-    find::FindArgs, hover::HoverArgs, impact::ImpactArgs, init::InitArgs, rename::RenameArgs,
-    search::SearchArgs, signature::SignatureArgs, status::StatusArgs,
+    actions::ActionsArgs, batch::BatchArgs, calls::CallsArgs, check_edit::CheckEditArgs,
+    config::ConfigArgs, daemon::DaemonArgs, diagnostics::DiagnosticsArgs, doctor::DoctorArgs,
+    edit::EditArgs, find::FindArgs, fix::FixArgs, format::FormatArgs, history::HistoryArgs,
+    hover::HoverArgs, impact::ImpactArgs, init::InitArgs, inspect::InspectArgs, rename::RenameArgs,
+    search::SearchArgs, signature::SignatureArgs, status::StatusArgs, tokens::TokensArgs,
+    types::TypesArgs, undo::UndoArgs,
 };
 
 const LONG_ABOUT: &str = r#"
@@ -38,8 +44,26 @@ SEARCH EXAMPLES:
 
 LSP EXAMPLES:
   symora hover src/main.rs:10:5
+  symora hover src/main.rs#Config/load
+  symora find refs OrderService/submit
   symora find def src/api.rs:25:10
+  symora find def src/api.rs:25:10 --follow
+  symora find occurrences src/api.rs:25:10 --kind write
   symora calls incoming src/api.rs:25:10
+  symora types sub src/api.rs:12:8 --depth 3
+  symora tokens src/api.rs --range 10-40 --modifier mutable
+  symora inspect hints src/api.rs --lines 10-40 --kind type
+  symora inspect selection src/api.rs:25:10
+  symora edit symbol src/api.rs -s Config/load -t "$NEW" --expect hash:9e3c1f0a2b4d5e6f
+  symora edit replace-text src/api.rs --old "retries: 3" --new "retries: 5" -w
+  symora edit patch fix.diff --dry-run
+  symora format src/api.rs:10-40 --dry-run
+  symora fix src/ --kinds organizeImports,fixAll
+  symora check-edit rename src/lib.rs:10:5 new_name
+
+EDIT HISTORY:
+  symora history                                     # Recorded edits, newest first
+  symora undo --steps 2                              # Revert the last two steps
 
 For more information: https://github.com/junyeong-ai/symora
 "#;
@@ -54,9 +78,9 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
 
-    /// Output format (json, text)
-    #[arg(long, global = true, default_value = "json")]
-    pub format: String,
+    /// Output format (json, json-compact, ndjson, text) [default: output.format from config]
+    #[arg(long, global = true)]
+    pub format: Option<OutputFormat>,
 
     /// Verbose output (show debug info)
     #[arg(short, long, global = true)]
@@ -90,15 +114,27 @@ pub enum Commands {
     /// Get function/method signature help
     Signature(SignatureArgs),
 
-    /// Get LSP diagnostics for a file
+    /// Get LSP diagnostics for a file, a directory or the whole project
     Diagnostics(DiagnosticsArgs),
 
+    /// Run an editing command and report diagnostics it introduced or resolved
+    CheckEdit(CheckEditArgs),
+
     /// Rename a symbol across the codebase
     Rename(RenameArgs),
 
     /// Call hierarchy (incoming/outgoing)
     Calls(CallsArgs),
 
+    /// Type hierarchy (supertypes/subtypes)
+    Types(TypesArgs),
+
+    /// Inspect inlay hints, folding/selection ranges and code lenses
+    Inspect(InspectArgs),
+
+    /// Semantic tokens with type and modifier names
+    Tokens(TokensArgs),
+
     /// Code actions (quickfix, refactor, source)
     Actions(ActionsArgs),
 
@@ -108,9 +144,64 @@ pub enum Commands {
     /// Text editing operations
     Edit(EditArgs),
 
+    /// Format a file or range with the language server
+    Format(FormatArgs),
+
+    /// Organize imports and apply fix-all source actions to files or directories
+    Fix(FixArgs),
+
+    /// Undo recorded edits, renames and code actions
+    Undo(UndoArgs),
+
+    /// Show recorded edits, renames and code actions
+    History(HistoryArgs),
+
     /// Execute multiple commands in batch
     Batch(BatchArgs),
 
     /// Daemon server management (start, stop, status)
     Daemon(DaemonArgs),
 }
+
+/// Dispatch a parsed command to its implementation
+pub async fn execute_command(command: Commands, app: &App) -> anyhow::Result<()> {
+    match command {
+        // Project management
+        Commands::Init(args) => commands::init::execute(args, app).await,
+        Commands::Status(args) => commands::status::execute(args, app).await,
+        Commands::Config(args) => commands::config::execute(args, app).await,
+        Commands::Doctor(args) => commands::doctor::execute(args, app),
+
+        // Symbol operations (LSP-based)
+        Commands::Find(args) => commands::find::execute(args, app).await,
+        Commands::Hover(args) => commands::hover::execute(args, app).await,
+        Commands::Signature(args) => commands::signature::execute(args, app).await,
+        Commands::Diagnostics(args) => commands::diagnostics::execute(args, app).await,
+        Commands::CheckEdit(args) => Box::pin(commands::check_edit::execute(args, app)).await,
+        Commands::Rename(args) => commands::rename::execute(args, app).await,
+        Commands::Inspect(args) => commands::inspect::execute(args, app).await,
+        Commands::Tokens(args) => commands::tokens::execute(args, app).await,
+
+        // Call and type hierarchy
+        Commands::Calls(args) => commands::calls::execute(args, app).await,
+        Commands::Types(args) => commands::types::execute(args, app).await,
+
+        // Code transformation
+        Commands::Actions(args) => commands::actions::execute(args, app).await,
+        Commands::Impact(args) => commands::impact::execute(args, app).await,
+        Commands::Edit(args) => commands::edit::execute(args, app).await,
+        Commands::Format(args) => commands::format::execute(args, app).await,
+        Commands::Fix(args) => commands::fix::execute(args, app).await,
+        Commands::Undo(args) => commands::undo::execute(args, app).await,
+        Commands::History(args) => commands::history::execute(args, app).await,
+
+        // Search (AST pattern)
+        Commands::Search(args) => commands::search::execute(args, app).await,
+
+        // Batch mode
+        Commands::Batch(args) => Box::pin(commands::batch::execute(args, app)).await,
+
+        // Daemon management
+        Commands::Daemon(args) => commands::daemon::execute(args, app).await,
+    }
+}
@@ -1,9 +1,56 @@
 //! Output formatting for CLI commands
 
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
 use serde::Serialize;
 
+use super::text;
+
+/// Output format for command responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Pretty-printed JSON (default, for AI agents)
+    #[default]
+    Json,
+    /// Human-readable text
+    Text,
+    /// Newline-delimited JSON: one record per line, then a summary line
+    Ndjson,
+    /// Single-line JSON
+    JsonCompact,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "text" => Ok(Self::Text),
+            "ndjson" => Ok(Self::Ndjson),
+            "json-compact" | "json_compact" => Ok(Self::JsonCompact),
+            _ => Err(format!(
+                "Unknown output format '{}'. Valid: json, json-compact, ndjson, text",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json => write!(f, "json"),
+            Self::Text => write!(f, "text"),
+            Self::Ndjson => write!(f, "ndjson"),
+            Self::JsonCompact => write!(f, "json-compact"),
+        }
+    }
+}
+
 /// Output context for consistent formatting across commands
 ///
 /// This is the single source of truth for output formatting.
@@ -12,12 +59,65 @@ use serde::Serialize;
 pub struct OutputContext {
     /// Project root for relative path calculation
     root: PathBuf,
+    format: OutputFormat,
+    /// Use ANSI colors in text output
+    color: bool,
+    /// Collects responses instead of printing them (used by batch)
+    capture: Option<OutputCapture>,
+}
+
+/// Responses collected by a capturing [`OutputContext`]
+#[derive(Debug, Clone, Default)]
+pub struct OutputCapture(Arc<Mutex<Vec<serde_json::Value>>>);
+
+impl OutputCapture {
+    /// Take the last captured response, clearing the capture
+    pub fn take_last(&self) -> Option<serde_json::Value> {
+        let mut values = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let last = values.pop();
+        values.clear();
+        last
+    }
+
+    fn push(&self, value: serde_json::Value) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).push(value);
+    }
 }
 
 impl OutputContext {
     /// Create a new output context
     pub fn new(root: PathBuf) -> Self {
-        Self { root }
+        Self {
+            root,
+            format: OutputFormat::default(),
+            color: false,
+            capture: None,
+        }
+    }
+
+    /// Create a context that records responses as JSON values instead of
+    /// printing them
+    pub fn capturing(&self) -> (Self, OutputCapture) {
+        let capture = OutputCapture::default();
+        let ctx = Self {
+            root: self.root.clone(),
+            format: OutputFormat::Json,
+            color: false,
+            capture: Some(capture.clone()),
+        };
+        (ctx, capture)
+    }
+
+    /// Set the output format and whether text output may use colors
+    pub fn with_format(mut self, format: OutputFormat, color: bool) -> Self {
+        self.format = format;
+        self.color = color;
+        self
+    }
+
+    /// Get the output format
+    pub fn format(&self) -> OutputFormat {
+        self.format
     }
 
     /// Get the project root
@@ -32,6 +132,11 @@ impl OutputContext {
             .unwrap_or_else(|_| path.display().to_string())
     }
 
+    /// Resolve a path against the project root (absolute paths are kept)
+    pub fn absolute_path(&self, path: &Path) -> PathBuf {
+        self.root.join(path)
+    }
+
     /// Check if a path is within the project root
     pub fn is_project_path(&self, path: &Path) -> bool {
         path.starts_with(&self.root)
@@ -43,7 +148,7 @@ impl OutputContext {
             "success": true,
             "data": data
         });
-        print_json(&response);
+        self.print_value(&response);
     }
 
     /// Print a successful response with flat structure (data fields at top level)
@@ -52,7 +157,7 @@ impl OutputContext {
         if let Some(obj) = response.as_object_mut() {
             obj.insert("success".to_string(), serde_json::json!(true));
         }
-        print_json(&response);
+        self.print_value(&response);
     }
 
     /// Print an error response
@@ -61,17 +166,152 @@ impl OutputContext {
             "success": false,
             "error": message
         });
-        print_json(&response);
+        match self.format {
+            OutputFormat::Text if self.capture.is_none() => {
+                print!("{}", text::render_error(message, self.color))
+            }
+            _ => self.print_value(&response),
+        }
+    }
+
+    /// Print an error response carrying structured `details` under `key`
+    pub fn print_error_with<T: Serialize>(&self, message: &str, key: &str, details: T) {
+        let details = serde_json::to_value(details).unwrap_or_default();
+        match self.format {
+            OutputFormat::Text if self.capture.is_none() => {
+                print!("{}", text::render_error(message, self.color));
+                print!(
+                    "{}",
+                    text::render(&serde_json::json!({ key: details }), self.color)
+                );
+            }
+            _ => self.print_value(&serde_json::json!({
+                "success": false,
+                "error": message,
+                key: details
+            })),
+        }
+    }
+
+    /// Start a response whose `key` list is emitted record by record.
+    ///
+    /// With `ndjson`, each pushed record is printed immediately; other
+    /// formats buffer the records and print one response on `finish`.
+    pub fn stream(&self, key: &'static str) -> RecordStream<'_> {
+        RecordStream {
+            ctx: self,
+            key,
+            buffered: Vec::new(),
+            count: 0,
+        }
+    }
+
+    fn print_value(&self, value: &serde_json::Value) {
+        if let Some(capture) = &self.capture {
+            capture.push(value.clone());
+            return;
+        }
+        match self.format {
+            OutputFormat::Json => print_json(value, true),
+            OutputFormat::JsonCompact => print_json(value, false),
+            OutputFormat::Ndjson => {
+                for line in ndjson_lines(value) {
+                    println!("{line}");
+                }
+            }
+            OutputFormat::Text => print!("{}", text::render(value, self.color)),
+        }
     }
 }
 
-fn print_json(value: &serde_json::Value) {
-    match serde_json::to_string_pretty(value) {
+/// Incrementally emitted list of records, created by [`OutputContext::stream`]
+pub struct RecordStream<'a> {
+    ctx: &'a OutputContext,
+    key: &'static str,
+    buffered: Vec<serde_json::Value>,
+    count: usize,
+}
+
+impl RecordStream<'_> {
+    /// Emit (ndjson) or buffer (other formats) a single record
+    pub fn push<T: Serialize>(&mut self, record: &T) {
+        let value = serde_json::to_value(record).unwrap_or(serde_json::Value::Null);
+        self.count += 1;
+        if self.ctx.format == OutputFormat::Ndjson {
+            print_json(&value, false);
+        } else {
+            self.buffered.push(value);
+        }
+    }
+
+    /// Number of records pushed so far
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Complete the response with summary fields (e.g. `count`)
+    pub fn finish<T: Serialize>(self, summary: T) {
+        let mut response = serde_json::to_value(summary).unwrap_or(serde_json::json!({}));
+        let Some(obj) = response.as_object_mut() else {
+            return self.ctx.print_success_flat(response);
+        };
+
+        if self.ctx.format == OutputFormat::Ndjson {
+            obj.insert("success".to_string(), serde_json::json!(true));
+            print_json(&response, false);
+        } else {
+            obj.insert(
+                self.key.to_string(),
+                serde_json::Value::Array(self.buffered),
+            );
+            self.ctx.print_success_flat(response);
+        }
+    }
+}
+
+fn print_json(value: &serde_json::Value, pretty: bool) {
+    let json = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    match json {
         Ok(json) => println!("{json}"),
         Err(e) => eprintln!("Failed to serialize output: {e}"),
     }
 }
 
+/// Split a response into NDJSON lines: each element of the first non-empty
+/// list of objects becomes a line, followed by the remaining fields as a
+/// summary. Empty lists stay in the summary, since an empty `warnings` ahead
+/// of the actual results would otherwise be taken as the record list.
+fn ndjson_lines(value: &serde_json::Value) -> Vec<String> {
+    let mut summary = value.clone();
+    let records = summary.as_object_mut().and_then(|obj| {
+        let key = obj
+            .iter()
+            .find(|(_, v)| {
+                v.as_array()
+                    .is_some_and(|items| !items.is_empty() && items.iter().all(|i| i.is_object()))
+            })
+            .map(|(k, _)| k.clone())?;
+        obj.shift_remove(&key)
+    });
+
+    let mut lines: Vec<String> = records
+        .and_then(|r| r.as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .map(|r| r.to_string())
+        .collect();
+    lines.push(summary.to_string());
+    lines
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -91,6 +331,15 @@ mod tests {
             ctx.relative_path(Path::new("/other/file.rs")),
             "/other/file.rs"
         );
+
+        assert_eq!(
+            ctx.absolute_path(Path::new("src/main.rs")),
+            PathBuf::from("/project/src/main.rs")
+        );
+        assert_eq!(
+            ctx.absolute_path(Path::new("/other/file.rs")),
+            PathBuf::from("/other/file.rs")
+        );
     }
 
     #[test]
@@ -100,4 +349,97 @@ mod tests {
         assert!(ctx.is_project_path(Path::new("/project/src/main.rs")));
         assert!(!ctx.is_project_path(Path::new("/other/file.rs")));
     }
+
+    #[test]
+    fn test_output_format_parse() {
+        assert_eq!("json".parse::<OutputFormat>(), Ok(OutputFormat::Json));
+        assert_eq!("TEXT".parse::<OutputFormat>(), Ok(OutputFormat::Text));
+        assert_eq!("ndjson".parse::<OutputFormat>(), Ok(OutputFormat::Ndjson));
+        assert_eq!(
+            "json-compact".parse::<OutputFormat>(),
+            Ok(OutputFormat::JsonCompact)
+        );
+        assert!("yaml".parse::<OutputFormat>().is_err());
+        assert_eq!(OutputFormat::Text.to_string(), "text");
+    }
+
+    #[test]
+    fn test_capturing_context() {
+        let ctx =
+            OutputContext::new(PathBuf::from("/project")).with_format(OutputFormat::Ndjson, false);
+        let (captured, capture) = ctx.capturing();
+
+        captured.print_success_flat(serde_json::json!({"count": 1}));
+        assert_eq!(
+            capture.take_last(),
+            Some(serde_json::json!({"count": 1, "success": true}))
+        );
+
+        captured.print_error("boom");
+        assert_eq!(
+            capture.take_last(),
+            Some(serde_json::json!({"success": false, "error": "boom"}))
+        );
+
+        captured.print_error_with("stale", "conflict", serde_json::json!({"line": 3}));
+        assert_eq!(
+            capture.take_last(),
+            Some(serde_json::json!({"success": false, "error": "stale", "conflict": {"line": 3}}))
+        );
+        assert_eq!(capture.take_last(), None);
+    }
+
+    #[test]
+    fn test_ndjson_lines() {
+        let value = serde_json::json!({
+            "count": 2,
+            "references": [
+                {"file": "a.rs", "line": 1, "column": 1},
+                {"file": "b.rs", "line": 2, "column": 3}
+            ],
+            "success": true
+        });
+
+        let lines = ndjson_lines(&value);
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"file":"a.rs","line":1,"column":1}"#,
+                r#"{"file":"b.rs","line":2,"column":3}"#,
+                r#"{"count":2,"success":true}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ndjson_lines_without_records() {
+        let value = serde_json::json!({"content": "doc", "success": true});
+        assert_eq!(
+            ndjson_lines(&value),
+            vec![r#"{"content":"doc","success":true}"#]
+        );
+    }
+
+    #[test]
+    fn test_ndjson_lines_skip_empty_lists() {
+        let value = serde_json::json!({
+            "errors": [],
+            "symbols": [{"name": "a"}, {"name": "b"}],
+            "success": true
+        });
+        assert_eq!(
+            ndjson_lines(&value),
+            vec![
+                r#"{"name":"a"}"#,
+                r#"{"name":"b"}"#,
+                r#"{"errors":[],"success":true}"#,
+            ]
+        );
+
+        let empty = serde_json::json!({"count": 0, "references": [], "success": true});
+        assert_eq!(
+            ndjson_lines(&empty),
+            vec![r#"{"count":0,"references":[],"success":true}"#]
+        );
+    }
 }
@@ -5,9 +5,11 @@
 
 use std::path::Path;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use crate::models::lsp::{Range, ResourceOperation};
 use crate::models::symbol::Symbol;
+use crate::services::journal::JournalEntry;
 
 /// Location in a file (relative path by default)
 #[derive(Debug, Clone, Serialize)]
@@ -106,15 +108,58 @@ pub struct ReferencesResponse {
     pub references: Vec<LocationOutput>,
 }
 
+/// Response for find occurrences command
+#[derive(Debug, Serialize)]
+pub struct OccurrencesResponse {
+    pub file: String,
+    pub count: usize,
+    pub occurrences: Vec<OccurrenceOutput>,
+}
+
+/// Occurrence of a symbol within one file (1-indexed)
+#[derive(Debug, Serialize)]
+pub struct OccurrenceOutput {
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    pub kind: String,
+}
+
 /// Response for find def command
 #[derive(Debug, Serialize)]
 pub struct DefinitionResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub definition: Option<LocationOutput>,
+    /// Re-exports and aliases passed through with --follow, in order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chain: Vec<LocationOutput>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
 }
 
+/// Response for find def --all
+#[derive(Debug, Serialize)]
+pub struct DefinitionsResponse {
+    pub count: usize,
+    pub definitions: Vec<DefinitionCandidateOutput>,
+}
+
+/// Definition candidate, ranked best first
+#[derive(Debug, Serialize)]
+pub struct DefinitionCandidateOutput {
+    #[serde(flatten)]
+    pub location: LocationOutput,
+    pub reason: String,
+}
+
+/// Response for find decl command
+#[derive(Debug, Serialize)]
+pub struct DeclarationsResponse {
+    pub count: usize,
+    pub declarations: Vec<LocationOutput>,
+}
+
 /// Response for hover command
 #[derive(Debug, Serialize)]
 pub struct HoverResponse {
@@ -126,8 +171,8 @@ pub struct HoverResponse {
     pub message: Option<String>,
 }
 
-/// Diagnostic output
-#[derive(Debug, Serialize)]
+/// Diagnostic output (also read back from saved snapshots)
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiagnosticOutput {
     pub severity: String,
     pub message: String,
@@ -135,11 +180,11 @@ pub struct DiagnosticOutput {
     pub column: u32,
     pub end_line: u32,
     pub end_column: u32,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub code: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub source: Option<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<String>,
 }
 
@@ -148,9 +193,62 @@ pub struct DiagnosticOutput {
 pub struct DiagnosticsResponse {
     pub file: String,
     pub count: usize,
+    /// Document version the diagnostics belong to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// Whether the diagnostics may not reflect the file on disk
+    pub stale: bool,
     pub diagnostics: Vec<DiagnosticOutput>,
 }
 
+/// Diagnostics of one file in a multi-file response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiagnosticsOutput {
+    pub file: String,
+    pub count: usize,
+    pub diagnostics: Vec<DiagnosticOutput>,
+}
+
+/// Diagnostics that appeared or disappeared between two collections
+#[derive(Debug, Default, Serialize)]
+pub struct DiagnosticsDiffOutput {
+    pub introduced_count: usize,
+    pub resolved_count: usize,
+    pub unchanged_count: usize,
+    pub introduced: Vec<FileDiagnosticsOutput>,
+    pub resolved: Vec<FileDiagnosticsOutput>,
+}
+
+/// Diagnostic counts by severity
+#[derive(Debug, Default, Serialize)]
+pub struct SeverityCounts {
+    pub error: usize,
+    pub warning: usize,
+    pub info: usize,
+    pub hint: usize,
+}
+
+/// Response for project or directory diagnostics
+#[derive(Debug, Serialize)]
+pub struct WorkspaceDiagnosticsResponse {
+    pub checked: usize,
+    pub count: usize,
+    pub summary: SeverityCounts,
+    pub files: Vec<FileDiagnosticsOutput>,
+    /// Languages skipped because their server is not installed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<LanguageErrorOutput>,
+}
+
+/// Failure of one language server during a multi-language operation
+#[derive(Debug, Serialize)]
+pub struct LanguageErrorOutput {
+    pub language: String,
+    pub error: String,
+}
+
 /// Call hierarchy item output
 #[derive(Debug, Serialize)]
 pub struct CallHierarchyOutput {
@@ -168,6 +266,27 @@ pub struct CallsResponse {
     pub calls: Vec<CallHierarchyOutput>,
 }
 
+/// Node in a type hierarchy tree
+#[derive(Debug, Serialize)]
+pub struct TypeHierarchyOutput {
+    pub name: String,
+    pub kind: String,
+    pub location: LocationOutput,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TypeHierarchyOutput>,
+}
+
+/// Response for types command
+#[derive(Debug, Serialize)]
+pub struct TypesResponse {
+    pub direction: String,
+    pub depth: u32,
+    pub count: usize,
+    pub types: Vec<TypeHierarchyOutput>,
+}
+
 /// Impact file output
 #[derive(Debug, Serialize)]
 pub struct ImpactFileOutput {
@@ -182,6 +301,28 @@ pub struct ImpactReferenceOutput {
     pub column: u32,
 }
 
+/// Caller discovered while tracing impact through the call hierarchy
+#[derive(Debug, Serialize)]
+pub struct ImpactCallerOutput {
+    pub name: String,
+    pub kind: String,
+    pub location: LocationOutput,
+    /// Function this caller invokes; omitted for direct callers of the target
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callee: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub call_site: Option<LocationOutput>,
+}
+
+/// Callers found at a single depth level
+#[derive(Debug, Serialize)]
+pub struct ImpactLayerOutput {
+    pub depth: u32,
+    pub caller_count: usize,
+    pub callers: Vec<ImpactCallerOutput>,
+    pub files: Vec<String>,
+}
+
 /// Response for impact command
 #[derive(Debug, Serialize)]
 pub struct ImpactResponse {
@@ -190,6 +331,58 @@ pub struct ImpactResponse {
     pub total_references: usize,
     pub affected_files_count: usize,
     pub affected_files: Vec<ImpactFileOutput>,
+    pub total_callers: usize,
+    pub layers: Vec<ImpactLayerOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub call_hierarchy_error: Option<String>,
+}
+
+/// File operation of a workspace edit (create, rename, delete)
+#[derive(Debug, Serialize)]
+pub struct ResourceOperationOutput {
+    pub kind: String,
+    /// Source path of a rename
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_file: Option<String>,
+}
+
+impl ResourceOperationOutput {
+    pub fn new(operation: &ResourceOperation, root: &Path) -> Self {
+        let old_file = match operation {
+            ResourceOperation::Rename { old_file, .. } => Some(
+                old_file
+                    .strip_prefix(root)
+                    .unwrap_or(old_file)
+                    .display()
+                    .to_string(),
+            ),
+            _ => None,
+        };
+        Self {
+            kind: operation.name().to_string(),
+            old_file,
+        }
+    }
+}
+
+/// Undo journal entry output
+#[derive(Debug, Serialize)]
+pub struct JournalEntryOutput {
+    pub id: u64,
+    pub timestamp: String,
+    pub command: String,
+    pub files: Vec<String>,
+}
+
+impl From<&JournalEntry> for JournalEntryOutput {
+    fn from(entry: &JournalEntry) -> Self {
+        Self {
+            id: entry.id,
+            timestamp: entry.timestamp.clone(),
+            command: entry.command.clone(),
+            files: entry.files.iter().map(|f| f.path.clone()).collect(),
+        }
+    }
 }
 
 /// Project status output
@@ -259,3 +452,92 @@ pub struct SignatureHelpResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub active_parameter: Option<u32>,
 }
+
+/// Range with 1-indexed lines and columns
+#[derive(Debug, Serialize)]
+pub struct RangeOutput {
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+impl From<&Range> for RangeOutput {
+    fn from(r: &Range) -> Self {
+        Self {
+            start_line: r.start.line + 1,
+            start_column: r.start.character + 1,
+            end_line: r.end.line + 1,
+            end_column: r.end.character + 1,
+        }
+    }
+}
+
+/// Inlay hint output
+#[derive(Debug, Serialize)]
+pub struct HintOutput {
+    pub line: u32,
+    pub column: u32,
+    pub kind: String,
+    pub label: String,
+}
+
+/// Response for inspect hints command
+#[derive(Debug, Serialize)]
+pub struct HintsResponse {
+    pub file: String,
+    pub count: usize,
+    pub hints: Vec<HintOutput>,
+}
+
+/// Folding range output
+#[derive(Debug, Serialize)]
+pub struct FoldOutput {
+    pub start_line: u32,
+    pub end_line: u32,
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collapsed_text: Option<String>,
+}
+
+/// Response for inspect folds command
+#[derive(Debug, Serialize)]
+pub struct FoldsResponse {
+    pub file: String,
+    pub count: usize,
+    pub ranges: Vec<FoldOutput>,
+}
+
+/// Selection ranges at one position, innermost first
+#[derive(Debug, Serialize)]
+pub struct SelectionOutput {
+    pub line: u32,
+    pub column: u32,
+    pub ranges: Vec<RangeOutput>,
+}
+
+/// Response for inspect selection command
+#[derive(Debug, Serialize)]
+pub struct SelectionResponse {
+    pub file: String,
+    pub count: usize,
+    pub selections: Vec<SelectionOutput>,
+}
+
+/// Code lens output
+#[derive(Debug, Serialize)]
+pub struct LensOutput {
+    pub range: RangeOutput,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+/// Response for inspect lens command
+#[derive(Debug, Serialize)]
+pub struct LensResponse {
+    pub file: String,
+    pub count: usize,
+    pub lenses: Vec<LensOutput>,
+}
@@ -0,0 +1,517 @@
+//! Human-readable text rendering for CLI responses
+//!
+//! Renders the same serialized responses used for JSON output, so every
+//! command gets text output without a per-command formatter. Recognized
+//! shapes get compact layouts:
+//! - locations as `file:line:col`
+//! - list items as `file:line:col  kind  name` rows
+//! - plain location lists grouped by file
+//! - edits (`old_text`/`new_text`) as `-`/`+` diffs
+//! - unified `diff` fields with colored hunks
+
+use std::fmt::Write;
+
+use serde_json::{Map, Value};
+
+const INDENT: &str = "  ";
+
+/// Keys used as the row kind column, in priority order
+const KIND_KEYS: &[&str] = &["kind", "severity", "direction"];
+
+/// Keys used as the row name column, in priority order
+const NAME_KEYS: &[&str] = &["name", "title", "label", "message", "text", "node_type"];
+
+/// Keys that only refine a location and are not rendered separately
+const LOCATION_KEYS: &[&str] = &[
+    "file",
+    "line",
+    "column",
+    "end_line",
+    "end_column",
+    "start_line",
+    "start_column",
+    "end_location",
+];
+
+#[derive(Debug, Clone, Copy)]
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn paint(self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{code}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn bold(self, text: &str) -> String {
+        self.paint("1", text)
+    }
+
+    fn red(self, text: &str) -> String {
+        self.paint("31", text)
+    }
+
+    fn green(self, text: &str) -> String {
+        self.paint("32", text)
+    }
+
+    fn cyan(self, text: &str) -> String {
+        self.paint("36", text)
+    }
+
+    fn dim(self, text: &str) -> String {
+        self.paint("2", text)
+    }
+}
+
+/// Render a successful response
+pub fn render(value: &Value, color: bool) -> String {
+    let style = Style { color };
+    let mut out = String::new();
+    match value {
+        Value::Object(obj) => render_fields(obj, 0, style, &mut out),
+        other => render_scalar_line(None, other, 0, &mut out),
+    }
+    out
+}
+
+/// Render an error message
+pub fn render_error(message: &str, color: bool) -> String {
+    let style = Style { color };
+    format!("{} {}\n", style.red("error:"), message)
+}
+
+fn render_fields(obj: &Map<String, Value>, indent: usize, style: Style, out: &mut String) {
+    if is_diff(obj) {
+        render_diff(obj, indent, style, out);
+        return;
+    }
+
+    for (key, value) in obj {
+        if key == "success" {
+            continue;
+        }
+        render_field(key, value, indent, style, out);
+    }
+}
+
+fn render_field(key: &str, value: &Value, indent: usize, style: Style, out: &mut String) {
+    let pad = INDENT.repeat(indent);
+    match value {
+        Value::Null => {}
+        Value::Array(items) if items.is_empty() => {
+            let _ = writeln!(out, "{pad}{key}: {}", style.dim("(none)"));
+        }
+        Value::Array(items) if items.iter().all(|v| !v.is_object() && !v.is_array()) => {
+            let joined: Vec<_> = items.iter().map(scalar_to_string).collect();
+            let _ = writeln!(out, "{pad}{key}: {}", joined.join(", "));
+        }
+        Value::Array(items) => {
+            let _ = writeln!(out, "{pad}{} ({}):", style.bold(key), items.len());
+            render_list(items, indent + 1, style, out);
+        }
+        Value::Object(obj) => {
+            if let Some(loc) = location_string(obj) {
+                let _ = writeln!(out, "{pad}{key}: {}", style.cyan(&loc));
+            } else {
+                let _ = writeln!(out, "{pad}{}:", style.bold(key));
+                render_fields(obj, indent + 1, style, out);
+            }
+        }
+        Value::String(diff) if is_unified_diff(key, diff) => {
+            render_unified_diff(key, diff, indent, style, out);
+        }
+        scalar => render_scalar_line(Some(key), scalar, indent, out),
+    }
+}
+
+fn render_list(items: &[Value], indent: usize, style: Style, out: &mut String) {
+    if items.iter().all(is_plain_location) {
+        render_grouped_locations(items, indent, style, out);
+        return;
+    }
+
+    for item in items {
+        match item {
+            Value::Object(obj) => render_row(obj, indent, style, out),
+            Value::Array(inner) => {
+                let parts: Vec<_> = inner.iter().map(scalar_to_string).collect();
+                let _ = writeln!(out, "{}{}", INDENT.repeat(indent), parts.join("  "));
+            }
+            scalar => render_scalar_line(None, scalar, indent, out),
+        }
+    }
+}
+
+/// Render a list item as `location  kind  name  extra=value`, followed by
+/// its nested lists indented below
+fn render_row(obj: &Map<String, Value>, indent: usize, style: Style, out: &mut String) {
+    if is_diff(obj) {
+        render_diff(obj, indent, style, out);
+        return;
+    }
+
+    let pad = INDENT.repeat(indent);
+    let mut consumed: Vec<&str> = LOCATION_KEYS.to_vec();
+    let mut columns = Vec::new();
+
+    if let Some(Value::Object(loc)) = obj.get("location")
+        && let Some(s) = location_string(loc)
+    {
+        columns.push(style.cyan(&s));
+        consumed.push("location");
+    } else if let Some(s) = location_string(obj).or_else(|| position_string(obj)) {
+        columns.push(style.cyan(&s));
+    } else {
+        // No location: every field is rendered as-is
+        consumed.clear();
+    }
+
+    if let Some((key, kind)) = first_string(obj, KIND_KEYS) {
+        columns.push(style.dim(kind));
+        consumed.push(key);
+    }
+
+    let name = first_string(obj, NAME_KEYS);
+    if let Some((key, name)) = name
+        && !name.contains('\n')
+    {
+        columns.push(name.to_string());
+        consumed.push(key);
+    }
+
+    for (key, value) in obj {
+        if consumed.contains(&key.as_str()) {
+            continue;
+        }
+        match value {
+            Value::Null | Value::Array(_) | Value::Object(_) => {}
+            Value::String(s) if s.contains('\n') => {}
+            scalar => columns.push(format!("{key}={}", scalar_to_string(scalar))),
+        }
+    }
+    if columns.is_empty() {
+        columns.push("-".to_string());
+    }
+    let _ = writeln!(out, "{pad}{}", columns.join("  "));
+
+    for (key, value) in obj {
+        if consumed.contains(&key.as_str()) {
+            continue;
+        }
+        match value {
+            Value::Array(_) | Value::Object(_) => {
+                render_field(key, value, indent + 1, style, out);
+            }
+            Value::String(s) if s.contains('\n') => {
+                render_field(key, value, indent + 1, style, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Group bare locations by file: a file header, then `line:col` rows
+fn render_grouped_locations(items: &[Value], indent: usize, style: Style, out: &mut String) {
+    let pad = INDENT.repeat(indent);
+    let mut groups: Vec<(&str, Vec<String>)> = Vec::new();
+
+    for item in items {
+        let Some(obj) = item.as_object() else {
+            continue;
+        };
+        let file = obj.get("file").and_then(Value::as_str).unwrap_or("");
+        let pos = position_string(obj).unwrap_or_default();
+        match groups.iter_mut().find(|(f, _)| *f == file) {
+            Some((_, positions)) => positions.push(pos),
+            None => groups.push((file, vec![pos])),
+        }
+    }
+
+    for (file, positions) in groups {
+        let _ = writeln!(out, "{pad}{} ({})", style.bold(file), positions.len());
+        for pos in positions {
+            let _ = writeln!(out, "{pad}{INDENT}{}", style.cyan(&pos));
+        }
+    }
+}
+
+/// Render an edit as a diff: metadata first, then removed and added lines
+fn render_diff(obj: &Map<String, Value>, indent: usize, style: Style, out: &mut String) {
+    let pad = INDENT.repeat(indent);
+
+    let header = [
+        obj.get("file").and_then(Value::as_str).map(String::from),
+        obj.get("range")
+            .or_else(|| obj.get("position"))
+            .and_then(Value::as_object)
+            .and_then(range_string),
+    ];
+    let header: Vec<_> = header.into_iter().flatten().collect();
+    if !header.is_empty() {
+        let _ = writeln!(
+            out,
+            "{pad}{}",
+            style.bold(&format!("@@ {} @@", header.join(" ")))
+        );
+    }
+
+    for (key, value) in obj {
+        if matches!(
+            key.as_str(),
+            "success" | "file" | "range" | "position" | "old_text" | "new_text" | "text"
+        ) {
+            continue;
+        }
+        render_field(key, value, indent, style, out);
+    }
+
+    if let Some(old) = obj.get("old_text").and_then(Value::as_str) {
+        for line in old.lines() {
+            let _ = writeln!(out, "{pad}{}", style.red(&format!("-{line}")));
+        }
+    }
+    let new = obj
+        .get("new_text")
+        .or_else(|| obj.get("text"))
+        .and_then(Value::as_str);
+    if let Some(new) = new {
+        for line in new.lines() {
+            let _ = writeln!(out, "{pad}{}", style.green(&format!("+{line}")));
+        }
+    }
+}
+
+/// Render a unified diff with file headers bold, hunk headers cyan and
+/// removed/added lines red/green
+fn render_unified_diff(key: &str, diff: &str, indent: usize, style: Style, out: &mut String) {
+    let pad = INDENT.repeat(indent);
+    let _ = writeln!(out, "{pad}{key}:");
+    for line in diff.lines() {
+        let line = if line.starts_with("---") || line.starts_with("+++") {
+            style.bold(line)
+        } else if line.starts_with("@@") {
+            style.cyan(line)
+        } else if line.starts_with('-') {
+            style.red(line)
+        } else if line.starts_with('+') {
+            style.green(line)
+        } else {
+            line.to_string()
+        };
+        let _ = writeln!(out, "{pad}{INDENT}{line}");
+    }
+}
+
+fn render_scalar_line(key: Option<&str>, value: &Value, indent: usize, out: &mut String) {
+    let pad = INDENT.repeat(indent);
+    let text = scalar_to_string(value);
+    let prefix = key.map(|k| format!("{k}: ")).unwrap_or_default();
+
+    if text.contains('\n') {
+        if let Some(k) = key {
+            let _ = writeln!(out, "{pad}{k}:");
+        }
+        for line in text.lines() {
+            let _ = writeln!(out, "{pad}{INDENT}{line}");
+        }
+    } else {
+        let _ = writeln!(out, "{pad}{prefix}{text}");
+    }
+}
+
+/// An edit carries replacement text (`new_text`), or inserted text with a position
+fn is_diff(obj: &Map<String, Value>) -> bool {
+    obj.get("new_text").is_some_and(Value::is_string)
+        || (obj.get("text").is_some_and(Value::is_string) && obj.contains_key("position"))
+}
+
+/// A `diff` field holding unified diff text
+fn is_unified_diff(key: &str, value: &str) -> bool {
+    key == "diff" && value.lines().any(|l| l.starts_with("@@"))
+}
+
+/// A location with no payload beyond its coordinates
+fn is_plain_location(value: &Value) -> bool {
+    value.as_object().is_some_and(|obj| {
+        location_string(obj).is_some() && obj.keys().all(|k| LOCATION_KEYS.contains(&k.as_str()))
+    })
+}
+
+/// `file:line:col` for objects with file and line
+fn location_string(obj: &Map<String, Value>) -> Option<String> {
+    let file = obj.get("file")?.as_str()?;
+    let pos = position_string(obj)?;
+    Some(format!("{file}:{pos}"))
+}
+
+/// `line:col` from `line`/`column` or `start_line`/`start_column`
+fn position_string(obj: &Map<String, Value>) -> Option<String> {
+    let (line, column) = match obj.get("line") {
+        Some(line) => (line.as_u64()?, obj.get("column").and_then(Value::as_u64)),
+        None => (
+            obj.get("start_line")?.as_u64()?,
+            obj.get("start_column").and_then(Value::as_u64),
+        ),
+    };
+    Some(match column {
+        Some(column) => format!("{line}:{column}"),
+        None => line.to_string(),
+    })
+}
+
+/// `line:col-line:col` for LSP-style ranges or a single position
+fn range_string(obj: &Map<String, Value>) -> Option<String> {
+    if let (Some(Value::Object(start)), Some(Value::Object(end))) =
+        (obj.get("start"), obj.get("end"))
+    {
+        return Some(format!(
+            "{}-{}",
+            position_string(start)?,
+            position_string(end)?
+        ));
+    }
+    if obj.contains_key("start_line") {
+        let start = position_string(obj)?;
+        let end_line = obj.get("end_line")?.as_u64()?;
+        let end_column = obj.get("end_column").and_then(Value::as_u64).unwrap_or(1);
+        return Some(format!("{start}-{end_line}:{end_column}"));
+    }
+    position_string(obj)
+}
+
+fn first_string<'a>(obj: &'a Map<String, Value>, keys: &[&'a str]) -> Option<(&'a str, &'a str)> {
+    keys.iter()
+        .find_map(|&k| obj.get(k).and_then(Value::as_str).map(|v| (k, v)))
+}
+
+fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_symbol_rows() {
+        let value = json!({
+            "count": 1,
+            "symbols": [{
+                "name": "main",
+                "kind": "function",
+                "location": {"file": "src/main.rs", "line": 10, "column": 4}
+            }],
+            "success": true
+        });
+
+        let text = render(&value, false);
+        assert_eq!(
+            text,
+            "count: 1\nsymbols (1):\n  src/main.rs:10:4  function  main\n"
+        );
+    }
+
+    #[test]
+    fn test_render_grouped_references() {
+        let value = json!({
+            "count": 3,
+            "references": [
+                {"file": "a.rs", "line": 1, "column": 2},
+                {"file": "b.rs", "line": 5, "column": 1},
+                {"file": "a.rs", "line": 9, "column": 3}
+            ]
+        });
+
+        let text = render(&value, false);
+        assert!(text.contains("  a.rs (2)\n    1:2\n    9:3\n  b.rs (1)\n    5:1\n"));
+    }
+
+    #[test]
+    fn test_render_diff() {
+        let value = json!({
+            "dry_run": true,
+            "file": "src/lib.rs",
+            "old_text": "fn old() {}",
+            "new_text": "fn new() {}\nfn extra() {}",
+            "range": {"start": {"line": 3, "column": 1}, "end": {"line": 3, "column": 12}}
+        });
+
+        let text = render(&value, false);
+        assert!(text.starts_with("@@ src/lib.rs 3:1-3:12 @@\n"));
+        assert!(text.contains("dry_run: true\n"));
+        assert!(text.contains("-fn old() {}\n+fn new() {}\n+fn extra() {}\n"));
+    }
+
+    #[test]
+    fn test_render_diff_colored() {
+        let value = json!({"old_text": "a", "new_text": "b"});
+        let text = render(&value, true);
+        assert!(text.contains("\x1b[31m-a\x1b[0m"));
+        assert!(text.contains("\x1b[32m+b\x1b[0m"));
+    }
+
+    #[test]
+    fn test_render_unified_diff() {
+        let diff = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n fn a() {}\n-fn b() {}\n+fn c() {}\n";
+        let value = json!({"dry_run": true, "diff": diff});
+        assert_eq!(
+            render(&value, false),
+            format!(
+                "dry_run: true\ndiff:\n{}",
+                diff.lines().map(|l| format!("  {l}\n")).collect::<String>()
+            )
+        );
+
+        let text = render(&value, true);
+        assert!(text.contains("\x1b[1m--- a/src/lib.rs\x1b[0m"));
+        assert!(text.contains("\x1b[36m@@ -1,2 +1,2 @@\x1b[0m"));
+        assert!(text.contains("\x1b[31m-fn b() {}\x1b[0m"));
+        assert!(text.contains("\x1b[32m+fn c() {}\x1b[0m"));
+        assert!(text.contains("\n   fn a() {}\n"));
+
+        // Diffs nested in list rows are colored too
+        let value = json!({"changes": [{"file": "src/lib.rs", "edit_count": 1, "diff": diff}]});
+        let text = render(&value, true);
+        assert!(text.contains("\x1b[31m-fn b() {}\x1b[0m"));
+    }
+
+    #[test]
+    fn test_render_diagnostic_rows_and_extras() {
+        let value = json!({
+            "file": "src/main.rs",
+            "diagnostics": [{
+                "severity": "error",
+                "message": "mismatched types",
+                "line": 4,
+                "column": 9,
+                "end_line": 4,
+                "end_column": 12,
+                "source": "rustc"
+            }]
+        });
+
+        let text = render(&value, false);
+        assert!(text.contains("  4:9  error  mismatched types  source=rustc\n"));
+    }
+
+    #[test]
+    fn test_render_multiline_field() {
+        let value = json!({"content": "line one\nline two"});
+        assert_eq!(render(&value, false), "content:\n  line one\n  line two\n");
+    }
+
+    #[test]
+    fn test_render_error() {
+        assert_eq!(render_error("boom", false), "error: boom\n");
+    }
+}
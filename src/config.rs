@@ -73,7 +73,9 @@ impl OperationType {
             | "textDocument/implementation"
             | "textDocument/prepareCallHierarchy"
             | "callHierarchy/incomingCalls"
-            | "callHierarchy/outgoingCalls" => Self::WorkspaceOperation,
+            | "callHierarchy/outgoingCalls"
+            | "workspace/executeCommand"
+            | "workspace/diagnostic" => Self::WorkspaceOperation,
             "initialize" => Self::Initialization,
             "shutdown" => Self::Shutdown,
             _ => Self::Request,
@@ -237,6 +239,14 @@ mod tests {
             OperationType::from_method("workspace/symbol"),
             OperationType::WorkspaceOperation
         );
+        assert_eq!(
+            OperationType::from_method("workspace/executeCommand"),
+            OperationType::WorkspaceOperation
+        );
+        assert_eq!(
+            OperationType::from_method("workspace/diagnostic"),
+            OperationType::WorkspaceOperation
+        );
         assert_eq!(
             OperationType::from_method("textDocument/rename"),
             OperationType::Rename
@@ -5,23 +5,83 @@
 
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::Duration;
 
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::process::Command;
+use tokio::sync::Mutex;
 use tokio::time::timeout;
 
 use crate::daemon::protocol::{Request, Response, methods};
 use crate::daemon::server::DaemonConfig;
 use crate::error::LspError;
+use crate::models::lsp::{CodeActionKind, FormattingOptions, Range};
+
+/// How long to wait for the daemon to answer; workspace scans open many files
+fn response_timeout(method: &str) -> Duration {
+    match method {
+        methods::WORKSPACE_DIAGNOSTICS | methods::CONTENT_DIAGNOSTICS => Duration::from_secs(300),
+        _ => Duration::from_secs(30),
+    }
+}
 
 /// Daemon client for CLI commands
+///
+/// Connections are kept open and reused across requests, so commands that
+/// issue many requests (e.g. batch) share a small pool instead of
+/// reconnecting each time.
 pub struct DaemonClient {
     config: DaemonConfig,
     project_root: PathBuf,
     next_request_id: AtomicU64,
+    idle: Mutex<Vec<Connection>>,
+    /// Set once the daemon answered a ping; cleared when a connection fails
+    running: AtomicBool,
+}
+
+/// Persistent connection to the daemon (one request in flight at a time)
+struct Connection {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+}
+
+impl Connection {
+    async fn open(socket_path: &Path) -> Result<Self, LspError> {
+        let stream = UnixStream::connect(socket_path)
+            .await
+            .map_err(|_| LspError::NotConnected)?;
+        let (reader, writer) = stream.into_split();
+        Ok(Self {
+            reader: BufReader::new(reader),
+            writer,
+        })
+    }
+
+    async fn roundtrip(&mut self, request_json: &str, method: &str) -> Result<String, LspError> {
+        self.writer.write_all(request_json.as_bytes()).await?;
+        self.writer.write_all(b"\n").await?;
+        self.writer.flush().await?;
+
+        let limit = response_timeout(method);
+        let mut line = String::new();
+        let read = timeout(limit, self.reader.read_line(&mut line))
+            .await
+            .map_err(|_| {
+                LspError::Timeout(format!(
+                    "Operation '{}' timed out after {}s. Try 'symora daemon restart'",
+                    method,
+                    limit.as_secs()
+                ))
+            })??;
+
+        if read == 0 {
+            return Err(LspError::NotConnected);
+        }
+        Ok(line)
+    }
 }
 
 // ============================================================================
@@ -72,6 +132,8 @@ impl DaemonClient {
             config: DaemonConfig::default(),
             project_root: project_root.to_path_buf(),
             next_request_id: AtomicU64::new(1),
+            idle: Mutex::new(Vec::new()),
+            running: AtomicBool::new(false),
         }
     }
 
@@ -81,7 +143,7 @@ impl DaemonClient {
 
     /// Ensure daemon is running, starting it if necessary
     pub async fn ensure_running(&self) -> Result<(), LspError> {
-        if self.ping().await.is_ok() {
+        if self.running.load(Ordering::Relaxed) || self.ping().await.is_ok() {
             return Ok(());
         }
         self.start_daemon_with_lock().await
@@ -193,32 +255,48 @@ impl DaemonClient {
         method: &str,
         params: Option<serde_json::Value>,
     ) -> Result<Response, LspError> {
-        let stream = UnixStream::connect(&self.config.socket_path)
-            .await
-            .map_err(|_| LspError::NotConnected)?;
-
-        let (reader, mut writer) = stream.into_split();
-        let mut reader = BufReader::new(reader);
-
         let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
         let request = Request::new(id, method, params);
         let request_json = serde_json::to_string(&request)?;
 
-        writer.write_all(request_json.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
-        writer.flush().await?;
+        // A pooled connection may have been closed by a daemon restart;
+        // retry once on a fresh connection in that case
+        let pooled = self.idle.lock().await.pop();
+        let reused = pooled.is_some();
+        let mut conn = match pooled {
+            Some(conn) => conn,
+            None => self.connect().await?,
+        };
+
+        let line = match conn.roundtrip(&request_json, method).await {
+            Ok(line) => line,
+            Err(LspError::NotConnected | LspError::Io(_)) if reused => {
+                conn = self.connect().await?;
+                conn.roundtrip(&request_json, method)
+                    .await
+                    .map_err(|e| self.connection_failed(e))?
+            }
+            Err(e) => return Err(self.connection_failed(e)),
+        };
 
-        let mut line = String::new();
-        timeout(Duration::from_secs(30), reader.read_line(&mut line))
+        self.idle.lock().await.push(conn);
+        self.running.store(true, Ordering::Relaxed);
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    async fn connect(&self) -> Result<Connection, LspError> {
+        Connection::open(&self.config.socket_path)
             .await
-            .map_err(|_| {
-                LspError::Timeout(format!(
-                    "Operation '{}' timed out after 30s. Try 'symora daemon restart'",
-                    method
-                ))
-            })??;
+            .map_err(|e| self.connection_failed(e))
+    }
 
-        Ok(serde_json::from_str(&line)?)
+    /// Connections that failed mid-request are dropped (a late response
+    /// would otherwise be read by the next request)
+    fn connection_failed(&self, e: LspError) -> LspError {
+        if matches!(e, LspError::NotConnected | LspError::Io(_)) {
+            self.running.store(false, Ordering::Relaxed);
+        }
+        e
     }
 
     async fn request_with_project(
@@ -251,10 +329,14 @@ impl DaemonClient {
     rpc_position! {
         find_references => methods::FIND_REFS,
         goto_definition => methods::FIND_DEF,
+        definitions => methods::FIND_DEF_ALL,
+        goto_declaration => methods::FIND_DECL,
         goto_type_definition => methods::FIND_TYPEDEF,
         find_implementations => methods::FIND_IMPL,
+        document_highlights => methods::DOCUMENT_HIGHLIGHTS,
         hover => methods::HOVER,
         signature_help => methods::SIGNATURE_HELP,
+        prepare_call_hierarchy => methods::PREPARE_CALL_HIERARCHY,
         incoming_calls => methods::CALLS_INCOMING,
         outgoing_calls => methods::CALLS_OUTGOING,
         supertypes => methods::SUPERTYPES,
@@ -370,17 +452,94 @@ impl DaemonClient {
         &self,
         file: &Path,
         action: &serde_json::Value,
+        run_command: bool,
     ) -> Result<serde_json::Value, LspError> {
         self.ensure_running().await?;
         let params = serde_json::json!({
             "file": file.display().to_string(),
-            "action": action
+            "action": action,
+            "run_command": run_command
         });
         self.request_with_project(methods::APPLY_CODE_ACTION, params)
             .await
             .and_then(Self::extract_result)
     }
 
+    pub async fn workspace_diagnostics(
+        &self,
+        files: &[PathBuf],
+    ) -> Result<serde_json::Value, LspError> {
+        self.ensure_running().await?;
+        let params = serde_json::json!({ "files": files });
+        self.request_with_project(methods::WORKSPACE_DIAGNOSTICS, params)
+            .await
+            .and_then(Self::extract_result)
+    }
+
+    pub async fn content_diagnostics(
+        &self,
+        documents: &[(PathBuf, String)],
+    ) -> Result<serde_json::Value, LspError> {
+        self.ensure_running().await?;
+        let documents: Vec<_> = documents
+            .iter()
+            .map(|(file, content)| {
+                serde_json::json!({ "file": file.display().to_string(), "content": content })
+            })
+            .collect();
+        let params = serde_json::json!({ "documents": documents });
+        self.request_with_project(methods::CONTENT_DIAGNOSTICS, params)
+            .await
+            .and_then(Self::extract_result)
+    }
+
+    pub async fn source_actions(
+        &self,
+        file: &Path,
+        kinds: &[CodeActionKind],
+    ) -> Result<serde_json::Value, LspError> {
+        self.ensure_running().await?;
+        let params = serde_json::json!({
+            "file": file.display().to_string(),
+            "kinds": kinds
+        });
+        self.request_with_project(methods::SOURCE_ACTIONS, params)
+            .await
+            .and_then(Self::extract_result)
+    }
+
+    pub async fn format(
+        &self,
+        file: &Path,
+        range: Option<&Range>,
+        options: &FormattingOptions,
+    ) -> Result<serde_json::Value, LspError> {
+        self.ensure_running().await?;
+        let params = serde_json::json!({
+            "file": file.display().to_string(),
+            "range": range,
+            "options": options
+        });
+        self.request_with_project(methods::FORMAT, params)
+            .await
+            .and_then(Self::extract_result)
+    }
+
+    pub async fn semantic_tokens(
+        &self,
+        file: &Path,
+        range: Option<&Range>,
+    ) -> Result<serde_json::Value, LspError> {
+        self.ensure_running().await?;
+        let params = serde_json::json!({
+            "file": file.display().to_string(),
+            "range": range
+        });
+        self.request_with_project(methods::SEMANTIC_TOKENS, params)
+            .await
+            .and_then(Self::extract_result)
+    }
+
     // ========================================================================
     // Daemon Control Operations
     // ========================================================================
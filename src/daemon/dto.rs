@@ -235,6 +235,10 @@ pub struct CallsResponse {
 pub struct DiagnosticsResponse {
     pub count: usize,
     pub diagnostics: Vec<DiagnosticDto>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    #[serde(default)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
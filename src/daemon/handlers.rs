@@ -4,6 +4,9 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::daemon::dto::LocationDto;
+use crate::models::lsp::{CodeActionKind, FormattingOptions, Range};
+
 // ============================================================================
 // Request Parameter Types
 // ============================================================================
@@ -26,6 +29,24 @@ pub struct FileParams {
     pub depth: u32,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct FilesParams {
+    pub files: Vec<String>,
+    pub project: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DocumentContent {
+    pub file: String,
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContentDiagnosticsParams {
+    pub documents: Vec<DocumentContent>,
+    pub project: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct RenameParams {
     pub file: String,
@@ -47,6 +68,32 @@ pub struct ApplyActionParams {
     pub file: String,
     pub project: String,
     pub action: serde_json::Value,
+    #[serde(default)]
+    pub run_command: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SourceActionsParams {
+    pub file: String,
+    pub kinds: Vec<CodeActionKind>,
+    pub project: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FormatParams {
+    pub file: String,
+    #[serde(default)]
+    pub range: Option<Range>,
+    pub options: FormattingOptions,
+    pub project: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SemanticTokensParams {
+    pub file: String,
+    #[serde(default)]
+    pub range: Option<Range>,
+    pub project: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -82,12 +129,9 @@ pub struct CodeActionJson {
     pub kind: String,
     pub is_preferred: bool,
     pub diagnostics: Vec<String>,
-}
-
-#[derive(Serialize)]
-pub struct FileChangeJson {
-    pub file: String,
-    pub edit_count: usize,
+    /// Raw server action, sent back on apply for resolve and command execution
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
 }
 
 #[derive(Serialize)]
@@ -111,6 +155,22 @@ pub struct InlayHintJson {
     pub padding_right: bool,
 }
 
+#[derive(Serialize)]
+pub struct DefinitionCandidateJson {
+    #[serde(flatten)]
+    pub location: LocationDto,
+    pub reason: String,
+}
+
+#[derive(Serialize)]
+pub struct DocumentHighlightJson {
+    pub start_line: u32,
+    pub start_character: u32,
+    pub end_line: u32,
+    pub end_character: u32,
+    pub kind: String,
+}
+
 #[derive(Serialize)]
 pub struct FoldingRangeJson {
     pub start_line: u32,
@@ -135,6 +195,16 @@ pub struct SelectionRangeJson {
     pub parent: Option<Box<SelectionRangeJson>>,
 }
 
+#[derive(Serialize)]
+pub struct SemanticTokenJson {
+    pub line: u32,
+    pub character: u32,
+    pub length: u32,
+    pub token_type: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<String>,
+}
+
 #[derive(Serialize)]
 pub struct CodeLensJson {
     pub start_line: u32,
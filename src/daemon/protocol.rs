@@ -144,12 +144,18 @@ pub mod methods {
     pub const FIND_SYMBOL: &str = "find_symbol";
     pub const FIND_REFS: &str = "find_refs";
     pub const FIND_DEF: &str = "find_def";
+    pub const FIND_DEF_ALL: &str = "find_def_all";
+    pub const FIND_DECL: &str = "find_decl";
     pub const FIND_TYPEDEF: &str = "find_typedef";
     pub const FIND_IMPL: &str = "find_impl";
+    pub const DOCUMENT_HIGHLIGHTS: &str = "document_highlights";
     pub const WORKSPACE_SYMBOL: &str = "workspace_symbol";
     pub const HOVER: &str = "hover";
     pub const SIGNATURE_HELP: &str = "signature_help";
     pub const DIAGNOSTICS: &str = "diagnostics";
+    pub const WORKSPACE_DIAGNOSTICS: &str = "workspace_diagnostics";
+    pub const CONTENT_DIAGNOSTICS: &str = "content_diagnostics";
+    pub const PREPARE_CALL_HIERARCHY: &str = "prepare_call_hierarchy";
     pub const CALLS_INCOMING: &str = "calls_incoming";
     pub const CALLS_OUTGOING: &str = "calls_outgoing";
     pub const SUPERTYPES: &str = "supertypes";
@@ -158,8 +164,11 @@ pub mod methods {
     pub const FOLDING_RANGES: &str = "folding_ranges";
     pub const SELECTION_RANGES: &str = "selection_ranges";
     pub const CODE_LENS: &str = "code_lens";
+    pub const SEMANTIC_TOKENS: &str = "semantic_tokens";
     pub const CODE_ACTIONS: &str = "code_actions";
+    pub const SOURCE_ACTIONS: &str = "source_actions";
     pub const APPLY_CODE_ACTION: &str = "apply_code_action";
+    pub const FORMAT: &str = "format";
     pub const PREPARE_RENAME: &str = "prepare_rename";
     pub const RENAME: &str = "rename";
     pub const PING: &str = "ping";
@@ -172,6 +181,8 @@ pub mod dto {
     use serde::Deserialize;
 
     use crate::daemon::dto::{CallItemDto, DiagnosticDto, LocationDto, SignatureDto, SymbolDto};
+    use crate::models::diagnostic::{Diagnostic, DiagnosticsReport};
+    use crate::models::lsp::ResourceOperation;
 
     // Re-export shared DTOs from daemon::dto
     pub use crate::daemon::dto::{HoverResponse, RangeDto, ReferencesResponse};
@@ -182,6 +193,11 @@ pub mod dto {
         pub symbols: Vec<SymbolDto>,
     }
 
+    #[derive(Debug, Deserialize)]
+    pub struct PrepareCallHierarchyResponse {
+        pub item: Option<CallItemDto>,
+    }
+
     #[derive(Debug, Deserialize)]
     pub struct CallsResponse {
         pub count: usize,
@@ -201,6 +217,40 @@ pub mod dto {
         pub implementations: Vec<LocationDto>,
     }
 
+    #[derive(Debug, Deserialize)]
+    pub struct DefinitionsResponse {
+        pub count: usize,
+        pub definitions: Vec<DefinitionCandidateDto>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct DefinitionCandidateDto {
+        #[serde(flatten)]
+        pub location: LocationDto,
+        pub reason: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct DeclarationsResponse {
+        pub count: usize,
+        pub declarations: Vec<LocationDto>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct DocumentHighlightsResponse {
+        pub count: usize,
+        pub highlights: Vec<DocumentHighlightDto>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct DocumentHighlightDto {
+        pub start_line: u32,
+        pub start_character: u32,
+        pub end_line: u32,
+        pub end_character: u32,
+        pub kind: String,
+    }
+
     #[derive(Debug, Deserialize)]
     pub struct SignatureResponse {
         pub signatures: Vec<SignatureDto>,
@@ -214,6 +264,21 @@ pub mod dto {
     pub struct DiagnosticsResponse {
         pub count: usize,
         pub diagnostics: Vec<DiagnosticDto>,
+        #[serde(default)]
+        pub version: Option<u32>,
+        #[serde(default)]
+        pub stale: bool,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct WorkspaceDiagnosticsResponse {
+        pub count: usize,
+        pub diagnostics: Vec<Diagnostic>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct ContentDiagnosticsResponse {
+        pub reports: Vec<DiagnosticsReport>,
     }
 
     #[derive(Debug, Deserialize)]
@@ -224,13 +289,7 @@ pub mod dto {
 
     #[derive(Debug, Deserialize)]
     pub struct RenameResponse {
-        pub changes: Vec<FileChangeSummary>,
-    }
-
-    #[derive(Debug, Deserialize)]
-    pub struct FileChangeSummary {
-        pub file: String,
-        pub edit_count: usize,
+        pub changes: Vec<FileEditDto>,
     }
 
     #[derive(Debug, Deserialize)]
@@ -245,17 +304,35 @@ pub mod dto {
         pub kind: Option<String>,
         pub is_preferred: bool,
         pub diagnostics: Vec<String>,
+        #[serde(default)]
+        pub data: Option<serde_json::Value>,
     }
 
     #[derive(Debug, Deserialize)]
     pub struct ApplyActionResponse {
         pub changes: Vec<FileEditDto>,
+        #[serde(default)]
+        pub command: Option<String>,
+        #[serde(default)]
+        pub command_executed: bool,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct FormatResponse {
+        pub count: usize,
+        pub edits: Vec<TextEditDto>,
     }
 
     #[derive(Debug, Deserialize)]
     pub struct FileEditDto {
         pub file: String,
         pub edits: Vec<TextEditDto>,
+        #[serde(default)]
+        pub operation: Option<ResourceOperation>,
+        #[serde(default)]
+        pub version: Option<u32>,
+        #[serde(default)]
+        pub synced_version: Option<u32>,
     }
 
     #[derive(Debug, Deserialize)]
@@ -329,6 +406,22 @@ pub mod dto {
         pub parent: Option<Box<SelectionRangeDto>>,
     }
 
+    #[derive(Debug, Deserialize)]
+    pub struct SemanticTokensResponse {
+        pub count: usize,
+        pub tokens: Vec<SemanticTokenDto>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct SemanticTokenDto {
+        pub line: u32,
+        pub character: u32,
+        pub length: u32,
+        pub token_type: String,
+        #[serde(default)]
+        pub modifiers: Vec<String>,
+    }
+
     #[derive(Debug, Deserialize)]
     pub struct CodeLensResponse {
         pub count: usize,
@@ -18,7 +18,7 @@ use crate::daemon::dto::{LocationDto, SymbolDto};
 use crate::daemon::handlers::*;
 use crate::daemon::protocol::{Request, RequestId, Response, RpcError, methods};
 use crate::models::config::SymoraConfig;
-use crate::models::lsp::FindSymbolsOptions;
+use crate::models::lsp::{CodeAction, FileChangeWithEdits, FindSymbolsOptions, TextEdit};
 use crate::services::lsp::{DefaultLspService, LspService};
 
 type ProjectsMap = Arc<RwLock<HashMap<PathBuf, Arc<ProjectContext>>>>;
@@ -380,6 +380,25 @@ async fn dispatch(
             })
         }).await,
 
+        methods::FIND_DEF_ALL => handle_position(&params, projects, |ctx, f, l, c| async move {
+            let defs = ctx.lsp.definitions(&f, l, c).await?;
+            Ok(serde_json::json!({
+                "count": defs.len(),
+                "definitions": defs.iter().map(|d| DefinitionCandidateJson {
+                    location: LocationDto::from(&d.location),
+                    reason: d.reason.to_string(),
+                }).collect::<Vec<_>>()
+            }))
+        }).await,
+
+        methods::FIND_DECL => handle_position(&params, projects, |ctx, f, l, c| async move {
+            let decls = ctx.lsp.goto_declaration(&f, l, c).await?;
+            Ok(serde_json::json!({
+                "count": decls.len(),
+                "declarations": decls.iter().map(LocationDto::from).collect::<Vec<_>>()
+            }))
+        }).await,
+
         methods::FIND_TYPEDEF => handle_position(&params, projects, |ctx, f, l, c| async move {
             let def = ctx.lsp.goto_type_definition(&f, l, c).await?;
             Ok(match def {
@@ -396,6 +415,20 @@ async fn dispatch(
             }))
         }).await,
 
+        methods::DOCUMENT_HIGHLIGHTS => handle_position(&params, projects, |ctx, f, l, c| async move {
+            let highlights = ctx.lsp.document_highlights(&f, l, c).await?;
+            Ok(serde_json::json!({
+                "count": highlights.len(),
+                "highlights": highlights.iter().map(|h| DocumentHighlightJson {
+                    start_line: h.range.start.line,
+                    start_character: h.range.start.character,
+                    end_line: h.range.end.line,
+                    end_character: h.range.end.character,
+                    kind: h.kind.to_string(),
+                }).collect::<Vec<_>>()
+            }))
+        }).await,
+
         methods::HOVER => handle_position(&params, projects, |ctx, f, l, c| async move {
             let hover = ctx.lsp.hover(&f, l, c).await?;
             Ok(match hover {
@@ -424,6 +457,19 @@ async fn dispatch(
             })
         }).await,
 
+        methods::PREPARE_CALL_HIERARCHY => handle_position(&params, projects, |ctx, f, l, c| async move {
+            let item = ctx.lsp.prepare_call_hierarchy(&f, l, c).await?;
+            Ok(serde_json::json!({
+                "item": item.map(|i| serde_json::json!({
+                    "name": i.name,
+                    "kind": i.kind.to_string(),
+                    "file": i.location.file.display().to_string(),
+                    "line": i.location.line,
+                    "column": i.location.column,
+                })),
+            }))
+        }).await,
+
         methods::CALLS_INCOMING => handle_position(&params, projects, |ctx, f, l, c| async move {
             let calls = ctx.lsp.incoming_calls(&f, l, c).await?;
             Ok(serde_json::json!({
@@ -508,22 +554,17 @@ async fn dispatch(
 
         methods::CODE_ACTIONS => handle_position(&params, projects, |ctx, f, l, c| async move {
             let actions = ctx.lsp.code_actions(&f, l, c).await?;
-            Ok(serde_json::json!({
-                "count": actions.len(),
-                "actions": actions.iter().map(|a| CodeActionJson {
-                    title: a.title.clone(),
-                    kind: a.kind.to_string(),
-                    is_preferred: a.is_preferred,
-                    diagnostics: a.diagnostics.clone(),
-                }).collect::<Vec<_>>()
-            }))
+            Ok(code_actions_json(&actions))
         }).await,
 
         // File-based operations
         methods::DIAGNOSTICS => handle_file(&params, projects, |ctx, f| async move {
-            let diags = ctx.lsp.diagnostics(&f).await?;
+            let report = ctx.lsp.diagnostics(&f).await?;
+            let diags = &report.diagnostics;
             Ok(serde_json::json!({
                 "count": diags.len(),
+                "version": report.version,
+                "stale": report.stale,
                 "diagnostics": diags.iter().map(|d| serde_json::json!({
                     "message": d.message,
                     "severity": format!("{:?}", d.severity),
@@ -533,6 +574,9 @@ async fn dispatch(
             }))
         }).await,
 
+        methods::WORKSPACE_DIAGNOSTICS => handle_workspace_diagnostics(&params, projects).await,
+        methods::CONTENT_DIAGNOSTICS => handle_content_diagnostics(&params, projects).await,
+
         methods::FOLDING_RANGES => handle_file(&params, projects, |ctx, f| async move {
             let ranges = ctx.lsp.folding_ranges(&f).await?;
             Ok(serde_json::json!({
@@ -572,6 +616,9 @@ async fn dispatch(
         methods::INLAY_HINTS => handle_inlay_hints(&params, projects).await,
         methods::SELECTION_RANGES => handle_selection_ranges(&params, projects).await,
         methods::APPLY_CODE_ACTION => handle_apply_action(&params, projects).await,
+        methods::FORMAT => handle_format(&params, projects).await,
+        methods::SEMANTIC_TOKENS => handle_semantic_tokens(&params, projects).await,
+        methods::SOURCE_ACTIONS => handle_source_actions(&params, projects).await,
 
         _ => Err(RpcError::method_not_found(&request.method)),
     }
@@ -742,12 +789,7 @@ async fn handle_rename(
         .await
         .map_err(RpcError::from)?;
 
-    Ok(serde_json::json!({
-        "changes": result.changes.iter().map(|c| FileChangeJson {
-            file: c.file.display().to_string(),
-            edit_count: c.edit_count,
-        }).collect::<Vec<_>>()
-    }))
+    Ok(serde_json::json!({ "changes": file_edits_json(&result.changes) }))
 }
 
 async fn handle_inlay_hints(
@@ -830,20 +872,162 @@ async fn handle_apply_action(
 
     let result = ctx
         .lsp
-        .apply_code_action(Path::new(&p.file), &action)
+        .apply_code_action(Path::new(&p.file), &action, p.run_command)
         .await
         .map_err(RpcError::from)?;
 
     Ok(serde_json::json!({
-        "changes": result.changes.iter().map(|c| serde_json::json!({
-            "file": c.file.display().to_string(),
-            "edits": c.edits.iter().map(|e| serde_json::json!({
+        "changes": file_edits_json(&result.changes),
+        "command": result.command,
+        "command_executed": result.command_executed,
+    }))
+}
+
+async fn handle_workspace_diagnostics(
+    params: &serde_json::Value,
+    projects: &ProjectsMap,
+) -> Result<serde_json::Value, RpcError> {
+    let p: FilesParams = parse_params(params)?;
+    let ctx = get_context(projects, &p.project).await?;
+    ctx.touch().await;
+
+    let files: Vec<PathBuf> = p.files.into_iter().map(PathBuf::from).collect();
+    let diagnostics = ctx
+        .lsp
+        .workspace_diagnostics(&files)
+        .await
+        .map_err(RpcError::from)?;
+
+    Ok(serde_json::json!({
+        "count": diagnostics.len(),
+        "diagnostics": diagnostics,
+    }))
+}
+
+async fn handle_content_diagnostics(
+    params: &serde_json::Value,
+    projects: &ProjectsMap,
+) -> Result<serde_json::Value, RpcError> {
+    let p: ContentDiagnosticsParams = parse_params(params)?;
+    let ctx = get_context(projects, &p.project).await?;
+    ctx.touch().await;
+
+    let documents: Vec<(PathBuf, String)> = p
+        .documents
+        .into_iter()
+        .map(|d| (PathBuf::from(d.file), d.content))
+        .collect();
+    let reports = ctx
+        .lsp
+        .content_diagnostics(&documents)
+        .await
+        .map_err(RpcError::from)?;
+
+    Ok(serde_json::json!({ "reports": reports }))
+}
+
+async fn handle_source_actions(
+    params: &serde_json::Value,
+    projects: &ProjectsMap,
+) -> Result<serde_json::Value, RpcError> {
+    let p: SourceActionsParams = parse_params(params)?;
+    let ctx = get_context(projects, &p.project).await?;
+    ctx.touch().await;
+
+    let actions = ctx
+        .lsp
+        .source_actions(Path::new(&p.file), &p.kinds)
+        .await
+        .map_err(RpcError::from)?;
+
+    Ok(code_actions_json(&actions))
+}
+
+fn code_actions_json(actions: &[CodeAction]) -> serde_json::Value {
+    serde_json::json!({
+        "count": actions.len(),
+        "actions": actions.iter().map(|a| CodeActionJson {
+            title: a.title.clone(),
+            kind: a.kind.to_string(),
+            is_preferred: a.is_preferred,
+            diagnostics: a.diagnostics.clone(),
+            data: a.data.clone(),
+        }).collect::<Vec<_>>()
+    })
+}
+
+async fn handle_format(
+    params: &serde_json::Value,
+    projects: &ProjectsMap,
+) -> Result<serde_json::Value, RpcError> {
+    let p: FormatParams = parse_params(params)?;
+    let ctx = get_context(projects, &p.project).await?;
+    ctx.touch().await;
+
+    let edits = ctx
+        .lsp
+        .format(Path::new(&p.file), p.range, &p.options)
+        .await
+        .map_err(RpcError::from)?;
+
+    Ok(serde_json::json!({
+        "count": edits.len(),
+        "edits": text_edits_json(&edits),
+    }))
+}
+
+async fn handle_semantic_tokens(
+    params: &serde_json::Value,
+    projects: &ProjectsMap,
+) -> Result<serde_json::Value, RpcError> {
+    let p: SemanticTokensParams = parse_params(params)?;
+    let ctx = get_context(projects, &p.project).await?;
+    ctx.touch().await;
+
+    let tokens = ctx
+        .lsp
+        .semantic_tokens(Path::new(&p.file), p.range)
+        .await
+        .map_err(RpcError::from)?;
+
+    Ok(serde_json::json!({
+        "count": tokens.len(),
+        "tokens": tokens.into_iter().map(|t| SemanticTokenJson {
+            line: t.position.line,
+            character: t.position.character,
+            length: t.length,
+            token_type: t.token_type,
+            modifiers: t.modifiers,
+        }).collect::<Vec<_>>()
+    }))
+}
+
+fn file_edits_json(changes: &[FileChangeWithEdits]) -> serde_json::Value {
+    serde_json::json!(
+        changes
+            .iter()
+            .map(|c| serde_json::json!({
+                "file": c.file.display().to_string(),
+                "operation": c.operation,
+                "version": c.version,
+                "synced_version": c.synced_version,
+                "edits": text_edits_json(&c.edits),
+            }))
+            .collect::<Vec<_>>()
+    )
+}
+
+fn text_edits_json(edits: &[TextEdit]) -> serde_json::Value {
+    serde_json::json!(
+        edits
+            .iter()
+            .map(|e| serde_json::json!({
                 "range": {
                     "start": { "line": e.range.start.line, "character": e.range.start.character },
                     "end": { "line": e.range.end.line, "character": e.range.end.character }
                 },
                 "new_text": e.new_text,
-            })).collect::<Vec<_>>()
-        })).collect::<Vec<_>>()
-    }))
+            }))
+            .collect::<Vec<_>>()
+    )
 }
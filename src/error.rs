@@ -20,6 +20,9 @@ pub enum SymoraError {
     #[error("{0}")]
     Project(#[from] ProjectError),
 
+    #[error("{0}")]
+    Edit(#[from] EditError),
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 }
@@ -248,6 +251,44 @@ pub enum ProjectError {
     Io(#[from] std::io::Error),
 }
 
+/// Workspace edit failures. Files already replaced are restored before any
+/// of these is returned, except for `RollbackFailed`.
+#[derive(Debug, Error)]
+pub enum EditError {
+    #[error("Failed to read {path}: {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid edit for {path}: {message}")]
+    InvalidEdit { path: String, message: String },
+
+    #[error("File changed since the edits were computed: {path}. No files were modified.")]
+    Modified { path: String },
+
+    #[error(
+        "Edits for {path} were computed for document version {version}, but version {synced} is open. No files were modified."
+    )]
+    VersionMismatch {
+        path: String,
+        version: u32,
+        synced: u32,
+    },
+
+    #[error("Failed to write {path}: {source}. No files were modified.")]
+    Write {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Files changed since the last undone step: {files}. Use --force to undo anyway.")]
+    UndoConflict { files: String },
+
+    #[error("{cause}. Failed to restore: {files}")]
+    RollbackFailed { cause: String, files: String },
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -14,13 +14,13 @@ use super::init_options::get_initialization_options;
 use super::protocol::{
     ClientCapabilities, ClientInfo, GeneralClientCapabilities, InitializeParams, InitializeResult,
     LspDiagnostic, Message, Notification, Position, RegularExpressionsCapability, Request,
-    RequestId, Response, ResponseError, StaleRequestSupport, TextDocumentClientCapabilities,
-    TextDocumentIdentifier, TextDocumentPositionParams, WindowClientCapabilities,
-    WorkspaceClientCapabilities, error_codes,
+    RequestId, Response, ResponseError, SemanticTokensLegend, StaleRequestSupport,
+    TextDocumentClientCapabilities, TextDocumentIdentifier, TextDocumentPositionParams,
+    WindowClientCapabilities, WorkspaceClientCapabilities, error_codes,
 };
 use super::transport::{Transport, write_notification, write_request, write_response};
 use crate::error::LspError;
-use crate::models::lsp::path_to_uri;
+use crate::models::lsp::{PositionEncoding, path_to_uri};
 use crate::models::symbol::Language;
 
 type PendingRequest = oneshot::Sender<Response>;
@@ -43,14 +43,17 @@ struct DocumentState {
     version: u32,
     content_hash: u64,
     ref_count: u32,
+    /// Diagnostics generation when this version was sent to the server
+    synced_generation: u64,
 }
 
 impl DocumentState {
-    fn new(content: &str) -> Self {
+    fn new(content: &str, generation: u64) -> Self {
         Self {
             version: 1,
             content_hash: crate::infra::hash_content(content),
             ref_count: 1,
+            synced_generation: generation,
         }
     }
 
@@ -58,9 +61,10 @@ impl DocumentState {
         crate::infra::hash_content(new_content) != self.content_hash
     }
 
-    fn update(&mut self, new_content: &str) {
+    fn update(&mut self, new_content: &str, generation: u64) {
         self.version += 1;
         self.content_hash = crate::infra::hash_content(new_content);
+        self.synced_generation = generation;
     }
 
     fn acquire(&mut self) {
@@ -194,13 +198,25 @@ impl IndexingState {
     }
 }
 
+/// Diagnostics from the last `textDocument/publishDiagnostics` for a document
+#[derive(Debug, Clone)]
+struct PublishedDiagnostics {
+    /// Document version reported by the server, if any
+    version: Option<u32>,
+    /// Value of the generation counter when these arrived
+    generation: u64,
+    diagnostics: Vec<LspDiagnostic>,
+}
+
 pub struct LspClient {
     language: Language,
     process: Mutex<Option<Child>>,
     stdin: Mutex<Option<ChildStdin>>,
     next_id: AtomicU64,
     pending: RwLock<HashMap<RequestId, PendingRequest>>,
-    diagnostics: RwLock<HashMap<String, Vec<LspDiagnostic>>>,
+    diagnostics: RwLock<HashMap<String, PublishedDiagnostics>>,
+    /// Incremented on every `textDocument/publishDiagnostics`
+    diagnostics_generation: AtomicU64,
     document_cache: RwLock<DocumentCache>,
     notification_handlers: RwLock<HashMap<String, NotificationHandler>>,
     root: PathBuf,
@@ -210,6 +226,10 @@ pub struct LspClient {
     indexing_notify: Notify,
     terminated: AtomicBool,
     cross_file_waited: AtomicBool,
+    /// Serializes `workspace/executeCommand` so captured edits belong to one command
+    command_lock: Mutex<()>,
+    /// Edits received through `workspace/applyEdit` while a command runs
+    applied_edits: std::sync::Mutex<Option<Vec<Value>>>,
 }
 
 impl LspClient {
@@ -221,6 +241,7 @@ impl LspClient {
             next_id: AtomicU64::new(1),
             pending: RwLock::new(HashMap::new()),
             diagnostics: RwLock::new(HashMap::new()),
+            diagnostics_generation: AtomicU64::new(0),
             document_cache: RwLock::new(DocumentCache::new()),
             notification_handlers: RwLock::new(HashMap::new()),
             root,
@@ -230,6 +251,8 @@ impl LspClient {
             indexing_notify: Notify::new(),
             terminated: AtomicBool::new(false),
             cross_file_waited: AtomicBool::new(false),
+            command_lock: Mutex::new(()),
+            applied_edits: std::sync::Mutex::new(None),
         })
     }
 
@@ -375,7 +398,11 @@ impl LspClient {
     /// Build client capabilities optimized for the target language server (LSP 3.17 complete)
     fn client_capabilities(language: Language) -> ClientCapabilities {
         let general = GeneralClientCapabilities {
-            position_encodings: Some(vec!["utf-16".to_string(), "utf-8".to_string()]),
+            position_encodings: Some(vec![
+                "utf-16".to_string(),
+                "utf-8".to_string(),
+                "utf-32".to_string(),
+            ]),
             stale_request_support: Some(StaleRequestSupport {
                 cancel: true,
                 retry_on_content_modified: Some(vec![
@@ -466,7 +493,8 @@ impl LspClient {
                     "codeActionKind": {
                         "valueSet": [
                             "", "quickfix", "refactor", "refactor.extract", "refactor.inline",
-                            "refactor.rewrite", "source", "source.organizeImports", "source.fixAll"
+                            "refactor.rewrite", "source", "source.organizeImports", "source.fixAll",
+                            "source.addMissingImports"
                         ]
                     }
                 },
@@ -570,7 +598,7 @@ impl LspClient {
             workspace_edit: Some(serde_json::json!({
                 "documentChanges": true,
                 "resourceOperations": ["create", "rename", "delete"],
-                "failureHandling": "textOnlyTransactional",
+                "failureHandling": "transactional",
                 "normalizesLineEndings": true,
                 "changeAnnotationSupport": { "groupsOnLabel": true }
             })),
@@ -877,6 +905,10 @@ impl LspClient {
                 match method {
                     "textDocument/publishDiagnostics" => {
                         let uri = params.get("uri").and_then(|u| u.as_str());
+                        let version = params
+                            .get("version")
+                            .and_then(|v| v.as_u64())
+                            .map(|v| v as u32);
                         let diags = params.get("diagnostics").cloned();
                         if let (Some(uri), Some(diags)) = (uri, diags)
                             && let Ok(diagnostics) =
@@ -885,16 +917,28 @@ impl LspClient {
                             let mut cache = self.diagnostics.write().await;
                             let count = diagnostics.len();
 
-                            // Evict oldest entry if at capacity (simple FIFO)
+                            // Evict the oldest published entry if at capacity
                             if cache.len() >= MAX_DIAGNOSTICS_CACHE
                                 && !cache.contains_key(uri)
-                                && let Some(oldest_key) = cache.keys().next().cloned()
+                                && let Some(oldest_key) = cache
+                                    .iter()
+                                    .min_by_key(|(_, p)| p.generation)
+                                    .map(|(k, _)| k.clone())
                             {
                                 cache.remove(&oldest_key);
                                 tracing::trace!("Evicted diagnostics for {}", oldest_key);
                             }
 
-                            cache.insert(uri.to_string(), diagnostics);
+                            let generation =
+                                self.diagnostics_generation.fetch_add(1, Ordering::AcqRel) + 1;
+                            cache.insert(
+                                uri.to_string(),
+                                PublishedDiagnostics {
+                                    version,
+                                    generation,
+                                    diagnostics,
+                                },
+                            );
                             tracing::debug!("Cached {} diagnostics for {}", count, uri);
                         }
                     }
@@ -972,7 +1016,7 @@ impl LspClient {
 
             if let Some(state) = cache.get_mut(uri) {
                 if state.needs_update(content) {
-                    state.update(content);
+                    state.update(content, self.diagnostics_generation());
                     self.invalidate_index();
                     self.notify(
                         "textDocument/didChange",
@@ -985,7 +1029,7 @@ impl LspClient {
                 }
                 None
             } else {
-                let state = DocumentState::new(content);
+                let state = DocumentState::new(content, self.diagnostics_generation());
                 self.invalidate_index();
                 self.notify(
                     "textDocument/didOpen",
@@ -1026,7 +1070,7 @@ impl LspClient {
             if let Some(state) = cache.get_mut(uri) {
                 state.acquire();
                 if state.needs_update(content) {
-                    state.update(content);
+                    state.update(content, self.diagnostics_generation());
                     self.invalidate_index();
                     self.notify(
                         "textDocument/didChange",
@@ -1039,7 +1083,7 @@ impl LspClient {
                 }
                 None
             } else {
-                let state = DocumentState::new(content);
+                let state = DocumentState::new(content, self.diagnostics_generation());
                 self.invalidate_index();
                 self.notify(
                     "textDocument/didOpen",
@@ -1302,6 +1346,7 @@ impl LspClient {
             "client/registerCapability" => Ok(serde_json::Value::Null),
             "client/unregisterCapability" => Ok(serde_json::Value::Null),
             "window/workDoneProgress/create" => Ok(serde_json::Value::Null),
+            "workspace/applyEdit" => Ok(self.handle_apply_edit(&request.params)),
             _ => {
                 tracing::debug!("Unhandled server request: {}", request.method);
                 Err(ResponseError {
@@ -1334,6 +1379,52 @@ impl LspClient {
         }
     }
 
+    /// Capture a server-initiated edit instead of writing it.
+    ///
+    /// Edits are only accepted while [`Self::execute_command`] runs; the caller
+    /// decides whether to apply them, so dry runs see them too.
+    fn handle_apply_edit(&self, params: &Option<Value>) -> Value {
+        let edit = params.as_ref().and_then(|p| p.get("edit")).cloned();
+        let mut captured = self.applied_edits.lock().unwrap_or_else(|e| e.into_inner());
+        match (captured.as_mut(), edit) {
+            (Some(edits), Some(edit)) => {
+                edits.push(edit);
+                serde_json::json!({ "applied": true })
+            }
+            (None, _) => serde_json::json!({
+                "applied": false,
+                "failureReason": "symora only applies edits requested by a command it executes"
+            }),
+            (_, None) => serde_json::json!({
+                "applied": false,
+                "failureReason": "Missing workspace edit"
+            }),
+        }
+    }
+
+    /// Run `workspace/executeCommand`, returning the workspace edits the server
+    /// requested through `workspace/applyEdit` while the command ran
+    pub async fn execute_command(
+        &self,
+        command: &str,
+        arguments: &[Value],
+    ) -> Result<Vec<Value>, LspError> {
+        let _guard = self.command_lock.lock().await;
+        *self.applied_edits.lock().unwrap_or_else(|e| e.into_inner()) = Some(Vec::new());
+
+        let params = serde_json::json!({ "command": command, "arguments": arguments });
+        let result: Result<Option<Value>, LspError> =
+            self.request("workspace/executeCommand", Some(params)).await;
+
+        let edits = self
+            .applied_edits
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .unwrap_or_default();
+        result.map(|_| edits)
+    }
+
     fn handle_workspace_configuration(
         &self,
         params: &Option<Value>,
@@ -1371,9 +1462,94 @@ impl LspClient {
             .read()
             .await
             .get(uri)
-            .cloned()
+            .map(|p| p.diagnostics.clone())
             .unwrap_or_default()
     }
+
+    /// Diagnostics published for the version of `uri` last sent to the server.
+    ///
+    /// Unversioned diagnostics count when they arrived after that version was
+    /// sent; `None` until such diagnostics arrive.
+    pub async fn fresh_diagnostics(&self, uri: &str) -> Option<Vec<LspDiagnostic>> {
+        let (version, synced_generation) = {
+            let cache = self.document_cache.read().await;
+            let state = cache.docs.get(uri)?;
+            (state.version, state.synced_generation)
+        };
+        let published = self.diagnostics.read().await;
+        published
+            .get(uri)
+            .filter(|p| p.generation > synced_generation)
+            .filter(|p| p.version.is_none_or(|v| v == version))
+            .map(|p| p.diagnostics.clone())
+    }
+
+    /// Version of `uri` last sent to the server, if the document is open
+    pub async fn document_version(&self, uri: &str) -> Option<u32> {
+        self.document_cache
+            .read()
+            .await
+            .docs
+            .get(uri)
+            .map(|s| s.version)
+    }
+
+    /// Whether the server answers `textDocument/diagnostic` (LSP 3.17 pull)
+    pub async fn supports_pull_diagnostics(&self) -> bool {
+        self.capabilities
+            .read()
+            .await
+            .as_ref()
+            .is_some_and(|c| c.capabilities.diagnostic_provider.is_some())
+    }
+
+    /// Changes whenever new diagnostics are published
+    pub fn diagnostics_generation(&self) -> u64 {
+        self.diagnostics_generation.load(Ordering::Acquire)
+    }
+
+    /// Whether the server answers `workspace/diagnostic` (LSP 3.17 pull)
+    pub async fn supports_workspace_diagnostics(&self) -> bool {
+        self.capabilities
+            .read()
+            .await
+            .as_ref()
+            .and_then(|c| c.capabilities.diagnostic_provider.as_ref())
+            .and_then(|p| p.get("workspaceDiagnostics"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Encoding of `Position.character` chosen by the server at initialization
+    pub async fn position_encoding(&self) -> PositionEncoding {
+        let capabilities = self.capabilities.read().await;
+        PositionEncoding::from_lsp(
+            capabilities
+                .as_ref()
+                .and_then(|c| c.capabilities.position_encoding.as_deref()),
+        )
+    }
+
+    /// Legend of `semanticTokensProvider`; `None` when semantic tokens are unsupported
+    pub async fn semantic_tokens_legend(&self) -> Option<SemanticTokensLegend> {
+        let capabilities = self.capabilities.read().await;
+        let provider = capabilities
+            .as_ref()?
+            .capabilities
+            .semantic_tokens_provider
+            .as_ref()?;
+        serde_json::from_value(provider.get("legend")?.clone()).ok()
+    }
+
+    pub async fn supports_semantic_tokens_range(&self) -> bool {
+        self.capabilities
+            .read()
+            .await
+            .as_ref()
+            .and_then(|c| c.capabilities.semantic_tokens_provider.as_ref())
+            .and_then(|p| p.get("range"))
+            .is_some_and(|v| v.as_bool() != Some(false))
+    }
 }
 
 impl Drop for LspClient {
@@ -1411,4 +1587,53 @@ mod tests {
         assert_eq!(params.position.line, 10);
         assert_eq!(params.position.character, 5);
     }
+
+    #[test]
+    fn test_apply_edit_captured_only_during_command() {
+        let client = LspClient::new(Language::Rust, PathBuf::from("/tmp"));
+        let params = Some(serde_json::json!({ "edit": { "changes": {} } }));
+
+        let result = client.handle_apply_edit(&params);
+        assert_eq!(result["applied"], false);
+
+        *client.applied_edits.lock().unwrap() = Some(Vec::new());
+        assert_eq!(client.handle_apply_edit(&params)["applied"], true);
+        assert_eq!(client.handle_apply_edit(&None)["applied"], false);
+        assert_eq!(
+            client.applied_edits.lock().unwrap().as_ref().unwrap().len(),
+            1
+        );
+    }
+    #[tokio::test]
+    async fn test_fresh_diagnostics_match_synced_version() {
+        let client = LspClient::new(Language::Rust, PathBuf::from("/tmp"));
+        let uri = "file:///tmp/a.rs";
+        let publish = |version: Option<u32>| {
+            Message::Notification(Notification::new(
+                "textDocument/publishDiagnostics",
+                Some(serde_json::json!({ "uri": uri, "version": version, "diagnostics": [] })),
+            ))
+        };
+
+        client.handle_message(publish(None)).await;
+        client.document_cache.write().await.insert(
+            uri.to_string(),
+            DocumentState::new("fn a() {}", client.diagnostics_generation()),
+        );
+        assert!(client.fresh_diagnostics(uri).await.is_none());
+
+        client.handle_message(publish(Some(1))).await;
+        assert!(client.fresh_diagnostics(uri).await.is_some());
+
+        let generation = client.diagnostics_generation();
+        if let Some(state) = client.document_cache.write().await.get_mut(uri) {
+            state.update("fn b() {}", generation);
+        }
+        client.handle_message(publish(Some(1))).await;
+        assert!(client.fresh_diagnostics(uri).await.is_none());
+
+        client.handle_message(publish(None)).await;
+        assert!(client.fresh_diagnostics(uri).await.is_some());
+        assert_eq!(client.document_version(uri).await, Some(2));
+    }
 }
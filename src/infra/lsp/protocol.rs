@@ -7,6 +7,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
+use crate::models::lsp::SemanticToken;
+
 // Re-export commonly used types from models
 pub use crate::models::lsp::{Position, Range, TextEdit, WorkspaceEdit};
 
@@ -357,6 +359,8 @@ pub struct WorkspaceClientCapabilities {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct ServerCapabilities {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position_encoding: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text_document_sync: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -375,6 +379,10 @@ pub struct ServerCapabilities {
     pub call_hierarchy_provider: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub type_hierarchy_provider: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diagnostic_provider: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub semantic_tokens_provider: Option<Value>,
 }
 
 /// Initialize result
@@ -605,6 +613,75 @@ pub struct CallHierarchyOutgoingCall {
     pub from_ranges: Vec<Range>,
 }
 
+// ============================================================================
+// LSP Document Highlight Types
+// ============================================================================
+
+/// Document highlight; `kind` defaults to Text when omitted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LspDocumentHighlight {
+    pub range: Range,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<u32>,
+}
+
+// ============================================================================
+// LSP Semantic Token Types
+// ============================================================================
+
+/// Token type and modifier names, indexed by the encoded token stream
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensLegend {
+    pub token_types: Vec<String>,
+    pub token_modifiers: Vec<String>,
+}
+
+impl SemanticTokensLegend {
+    /// Decode the relative 5-integer groups of a `SemanticTokens.data` stream
+    ///
+    /// Each group is (deltaLine, deltaStart, length, tokenType, modifierBits);
+    /// deltaStart is relative to the previous token only on the same line.
+    pub fn decode(&self, data: &[u32]) -> Vec<SemanticToken> {
+        let (mut line, mut start) = (0, 0);
+        data.chunks_exact(5)
+            .map(|group| {
+                if group[0] > 0 {
+                    line += group[0];
+                    start = group[1];
+                } else {
+                    start += group[1];
+                }
+                let token_type = self
+                    .token_types
+                    .get(group[3] as usize)
+                    .cloned()
+                    .unwrap_or_else(|| group[3].to_string());
+                let modifiers = self
+                    .token_modifiers
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| *bit < 32 && group[4] & (1 << bit) != 0)
+                    .map(|(_, name)| name.clone())
+                    .collect();
+
+                SemanticToken {
+                    position: Position::new(line, start),
+                    length: group[2],
+                    token_type,
+                    modifiers,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Semantic tokens result (`resultId` is unused: delta requests are not sent)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticTokens {
+    pub data: Vec<u32>,
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -638,4 +715,22 @@ mod tests {
         assert!(!resp.is_success());
         assert!(resp.error.is_some());
     }
+
+    #[test]
+    fn test_semantic_tokens_decode() {
+        let legend = SemanticTokensLegend {
+            token_types: vec!["function".into(), "variable".into()],
+            token_modifiers: vec!["declaration".into(), "mutable".into()],
+        };
+        // fn main() { let mut x = 1; x += 1; }
+        let tokens = legend.decode(&[0, 3, 4, 0, 1, 1, 8, 1, 1, 3, 0, 7, 1, 1, 2, 7]);
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].token_type, "function");
+        assert_eq!(tokens[0].modifiers, vec!["declaration"]);
+        assert_eq!(tokens[1].position, Position::new(1, 8));
+        assert_eq!(tokens[1].modifiers, vec!["declaration", "mutable"]);
+        assert_eq!(tokens[2].position, Position::new(1, 15));
+        assert_eq!(tokens[2].modifiers, vec!["mutable"]);
+    }
 }
@@ -172,6 +172,13 @@ impl ServerConfig {
     }
 }
 
+/// Whether the default server for `language` is installed
+pub fn is_installed(language: Language) -> bool {
+    defaults()
+        .get(&language)
+        .is_some_and(ServerConfig::is_installed)
+}
+
 /// Default server configurations for all supported languages
 pub fn defaults() -> HashMap<Language, ServerConfig> {
     let mut configs = HashMap::new();
@@ -10,7 +10,7 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use symora::app::App;
 use symora::cli::commands::daemon::{DaemonArgs, DaemonCommand};
-use symora::cli::{Cli, Commands};
+use symora::cli::{Cli, Commands, execute_command};
 
 fn main() {
     // Initialize tracing with quiet defaults for AI agent consumption
@@ -71,44 +71,13 @@ async fn async_main() -> anyhow::Result<()> {
     // Initialize application
     let app = App::with_daemon(use_daemon)
         .await
-        .map_err(|e| anyhow::anyhow!("Failed to initialize: {}", e))?;
+        .map_err(|e| anyhow::anyhow!("Failed to initialize: {}", e))?
+        .with_output_format(cli.format);
 
-    // Execute command
-    execute_command(cli.command, &app).await
-}
-
-async fn execute_command(command: Commands, app: &App) -> anyhow::Result<()> {
-    use symora::cli::commands;
-
-    match command {
-        // Project management
-        Commands::Init(args) => commands::init::execute(args, app).await,
-        Commands::Status(args) => commands::status::execute(args, app).await,
-        Commands::Config(args) => commands::config::execute(args, app).await,
-        Commands::Doctor(args) => commands::doctor::execute(args, app),
-
-        // Symbol operations (LSP-based)
-        Commands::Find(args) => commands::find::execute(args, app).await,
-        Commands::Hover(args) => commands::hover::execute(args, app).await,
-        Commands::Signature(args) => commands::signature::execute(args, app).await,
-        Commands::Diagnostics(args) => commands::diagnostics::execute(args, app).await,
-        Commands::Rename(args) => commands::rename::execute(args, app).await,
-
-        // Call hierarchy
-        Commands::Calls(args) => commands::calls::execute(args, app).await,
-
-        // Code transformation
-        Commands::Actions(args) => commands::actions::execute(args, app).await,
-        Commands::Impact(args) => commands::impact::execute(args, app).await,
-        Commands::Edit(args) => commands::edit::execute(args, app).await,
-
-        // Search (AST pattern)
-        Commands::Search(args) => commands::search::execute(args, app).await,
-
-        // Batch mode
-        Commands::Batch(args) => commands::batch::execute(args, app).await,
-
-        // Daemon management
-        Commands::Daemon(args) => commands::daemon::execute(args, app).await,
+    // Execute command, reporting errors in the selected output format
+    if let Err(e) = execute_command(cli.command, &app).await {
+        app.output().print_error(&e.to_string());
+        std::process::exit(2);
     }
+    Ok(())
 }
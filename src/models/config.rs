@@ -21,6 +21,9 @@ pub struct SymoraConfig {
     #[serde(default)]
     pub output: OutputConfig,
 
+    #[serde(default)]
+    pub format: FormatConfig,
+
     #[serde(default)]
     pub daemon: DaemonSettings,
 }
@@ -140,6 +143,14 @@ mod defaults {
         true
     }
 
+    // Format
+    pub fn tab_size() -> u32 {
+        4
+    }
+    pub fn insert_spaces() -> bool {
+        true
+    }
+
     // Daemon
     pub fn max_concurrent() -> usize {
         100
@@ -223,6 +234,25 @@ impl Default for OutputConfig {
     }
 }
 
+/// Formatting defaults, overridden per file by `.editorconfig`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatConfig {
+    #[serde(default = "defaults::tab_size")]
+    pub tab_size: u32,
+
+    #[serde(default = "defaults::insert_spaces")]
+    pub insert_spaces: bool,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self {
+            tab_size: defaults::tab_size(),
+            insert_spaces: defaults::insert_spaces(),
+        }
+    }
+}
+
 /// Daemon settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DaemonSettings {
@@ -254,6 +284,8 @@ mod tests {
         assert_eq!(config.lsp.calls_limit, 100);
         assert_eq!(config.search.limit, 100);
         assert_eq!(config.output.format, "json");
+        assert_eq!(config.format.tab_size, 4);
+        assert!(config.format.insert_spaces);
         assert_eq!(config.daemon.idle_timeout_mins, 30);
     }
 
@@ -60,6 +60,19 @@ impl Diagnostic {
     }
 }
 
+/// Diagnostics for one document, with whether they reflect its current content
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiagnosticsReport {
+    pub diagnostics: Vec<Diagnostic>,
+    /// Document version the diagnostics were requested for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// No diagnostics for the current version arrived in time, so these are
+    /// the last ones published and may be outdated
+    #[serde(default)]
+    pub stale: bool,
+}
+
 /// Severity levels (matches LSP spec)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -15,6 +15,9 @@ use super::symbol::{Location, SymbolKind};
 // ============================================================================
 
 /// Position within a document (0-indexed, LSP standard)
+///
+/// `character` counts characters; the LSP service converts to and from the
+/// server's [`PositionEncoding`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub struct Position {
     pub line: u32,
@@ -26,7 +29,7 @@ impl Position {
         Self { line, character }
     }
 
-    /// Convert 1-indexed CLI input to 0-indexed LSP position
+    /// Convert 1-indexed CLI input to a 0-indexed position counting characters
     pub fn from_cli(line: u32, column: u32) -> Self {
         Self {
             line: line.saturating_sub(1),
@@ -61,6 +64,83 @@ impl Range {
     }
 }
 
+/// Unit counted by `Position.character` (LSP 3.17 `PositionEncodingKind`)
+///
+/// Symora's own columns count characters, which is `Utf32`; servers that do
+/// not negotiate an encoding use `Utf16`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum PositionEncoding {
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[default]
+    #[serde(rename = "utf-16")]
+    Utf16,
+    #[serde(rename = "utf-32")]
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Encoding chosen by the server, defaulting to UTF-16 when absent or unknown
+    pub fn from_lsp(kind: Option<&str>) -> Self {
+        match kind {
+            Some("utf-8") => Self::Utf8,
+            Some("utf-32") => Self::Utf32,
+            _ => Self::Utf16,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf16 => "utf-16",
+            Self::Utf32 => "utf-32",
+        }
+    }
+
+    fn units(self, c: char) -> u32 {
+        match self {
+            Self::Utf8 => c.len_utf8() as u32,
+            Self::Utf16 => c.len_utf16() as u32,
+            Self::Utf32 => 1,
+        }
+    }
+
+    /// Byte index in `line` of an offset in this encoding, clamped to the line
+    /// end and rounded down to a character boundary
+    pub fn byte_index(self, line: &str, offset: u32) -> usize {
+        let mut units = 0;
+        for (i, c) in line.char_indices() {
+            units += self.units(c);
+            if units > offset {
+                return i;
+            }
+        }
+        line.len()
+    }
+
+    /// Offset in this encoding of a byte index in `line`
+    pub fn offset_of(self, line: &str, byte: usize) -> u32 {
+        line.char_indices()
+            .take_while(|&(i, _)| i < byte)
+            .map(|(_, c)| self.units(c))
+            .sum()
+    }
+
+    /// Re-express an offset in `line` in another encoding
+    pub fn convert(self, line: &str, offset: u32, to: Self) -> u32 {
+        if self == to || line.is_ascii() {
+            return offset;
+        }
+        to.offset_of(line, self.byte_index(line, offset))
+    }
+}
+
+impl std::fmt::Display for PositionEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 // ============================================================================
 // Text Edit Types
 // ============================================================================
@@ -87,37 +167,117 @@ pub struct WorkspaceEdit {
 }
 
 impl WorkspaceEdit {
-    /// Extract file changes from changes or document_changes
-    pub fn to_file_changes(&self) -> Vec<FileChange> {
-        if let Some(ref changes) = self.changes {
-            changes
-                .iter()
-                .map(|(uri, edits)| FileChange {
-                    file: uri_to_path(uri),
-                    edit_count: edits.len(),
-                })
-                .collect()
-        } else if let Some(ref doc_changes) = self.document_changes {
-            doc_changes
+    /// Extract file changes in document order, including resource operations
+    ///
+    /// `documentChanges` is preferred over `changes` when both are present.
+    /// Fails if any document change cannot be parsed, so that no part of the
+    /// edit is applied without the rest.
+    pub fn to_file_edits(&self) -> Result<Vec<FileChangeWithEdits>, String> {
+        if let Some(ref doc_changes) = self.document_changes {
+            let items = doc_changes
                 .as_array()
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|item| {
-                            let text_doc = item.get("textDocument")?;
-                            let uri = text_doc.get("uri")?.as_str()?;
-                            let edits = item.get("edits")?.as_array()?;
-                            Some(FileChange {
-                                file: uri_to_path(uri),
-                                edit_count: edits.len(),
-                            })
-                        })
-                        .collect()
-                })
-                .unwrap_or_default()
+                .ok_or("documentChanges is not an array")?;
+            let mut changes = Vec::with_capacity(items.len());
+            for (i, item) in items.iter().enumerate() {
+                let change = parse_document_change(item)
+                    .map_err(|e| format!("documentChanges[{}]: {}", i, e))?;
+                changes.extend(change);
+            }
+            Ok(changes)
+        } else if let Some(ref changes) = self.changes {
+            Ok(changes
+                .iter()
+                .filter(|(_, edits)| !edits.is_empty())
+                .map(|(uri, edits)| FileChangeWithEdits::new(uri_to_path(uri), edits.clone()))
+                .collect())
         } else {
-            Vec::new()
+            Ok(Vec::new())
         }
     }
+
+    /// Extract per-file change summaries
+    pub fn to_file_changes(&self) -> Result<Vec<FileChange>, String> {
+        Ok(self
+            .to_file_edits()?
+            .into_iter()
+            .map(|c| FileChange {
+                file: c.file,
+                edit_count: c.edits.len(),
+                operation: c.operation,
+            })
+            .collect())
+    }
+}
+
+/// Parse one `documentChanges` item: a TextDocumentEdit or a resource
+/// operation. A TextDocumentEdit without edits yields `None`.
+fn parse_document_change(item: &serde_json::Value) -> Result<Option<FileChangeWithEdits>, String> {
+    let flag = |key: &str| {
+        item.get("options")
+            .and_then(|o| o.get(key))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    };
+    let uri = |key: &str| {
+        item.get(key)
+            .and_then(|u| u.as_str())
+            .map(uri_to_path)
+            .ok_or_else(|| format!("missing '{}'", key))
+    };
+
+    let (file, operation) = match item.get("kind") {
+        None => {
+            let document = item.get("textDocument").ok_or("missing 'textDocument'")?;
+            let uri = document
+                .get("uri")
+                .and_then(|u| u.as_str())
+                .ok_or("missing 'textDocument.uri'")?;
+            // Annotated and snippet edits share the TextEdit shape
+            let edits: Vec<TextEdit> = item
+                .get("edits")
+                .cloned()
+                .map(serde_json::from_value)
+                .ok_or("missing 'edits'")?
+                .map_err(|e| format!("invalid edits: {}", e))?;
+            if edits.is_empty() {
+                return Ok(None);
+            }
+            return Ok(Some(FileChangeWithEdits {
+                version: document
+                    .get("version")
+                    .and_then(|v| v.as_u64())
+                    .and_then(|v| u32::try_from(v).ok()),
+                ..FileChangeWithEdits::new(uri_to_path(uri), edits)
+            }));
+        }
+        Some(kind) => match kind.as_str() {
+            Some("create") => (
+                uri("uri")?,
+                ResourceOperation::Create {
+                    overwrite: flag("overwrite"),
+                    ignore_if_exists: flag("ignoreIfExists"),
+                },
+            ),
+            Some("rename") => (
+                uri("newUri")?,
+                ResourceOperation::Rename {
+                    old_file: uri("oldUri")?,
+                    overwrite: flag("overwrite"),
+                    ignore_if_exists: flag("ignoreIfExists"),
+                },
+            ),
+            Some("delete") => (
+                uri("uri")?,
+                ResourceOperation::Delete {
+                    recursive: flag("recursive"),
+                    ignore_if_not_exists: flag("ignoreIfNotExists"),
+                },
+            ),
+            _ => return Err(format!("unsupported resource operation kind {}", kind)),
+        },
+    };
+
+    Ok(Some(FileChangeWithEdits::operation(file, operation)))
 }
 
 /// Per-file change summary
@@ -125,6 +285,40 @@ impl WorkspaceEdit {
 pub struct FileChange {
     pub file: PathBuf,
     pub edit_count: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation: Option<ResourceOperation>,
+}
+
+/// File-level operation of a workspace edit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ResourceOperation {
+    /// Create the file (empty)
+    Create {
+        overwrite: bool,
+        ignore_if_exists: bool,
+    },
+    /// Move `old_file` to the file
+    Rename {
+        old_file: PathBuf,
+        overwrite: bool,
+        ignore_if_exists: bool,
+    },
+    /// Delete the file
+    Delete {
+        recursive: bool,
+        ignore_if_not_exists: bool,
+    },
+}
+
+impl ResourceOperation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Create { .. } => "create",
+            Self::Rename { .. } => "rename",
+            Self::Delete { .. } => "delete",
+        }
+    }
 }
 
 // ============================================================================
@@ -331,7 +525,7 @@ pub struct PrepareRenameResult {
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RenameResult {
-    pub changes: Vec<FileChange>,
+    pub changes: Vec<FileChangeWithEdits>,
 }
 
 // ============================================================================
@@ -349,6 +543,80 @@ pub struct CallHierarchyItem {
     pub call_site: Option<Location>,
 }
 
+// ============================================================================
+// Definition Types
+// ============================================================================
+
+/// Definition location returned by the server, with why it ranks where it does
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefinitionCandidate {
+    pub location: Location,
+    pub reason: DefinitionReason,
+}
+
+/// Where a definition candidate lives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefinitionReason {
+    /// Source file inside the project
+    Source,
+    /// Declaration-only file inside the project (.d.ts, header, stub)
+    DeclarationFile,
+    /// Source file of a dependency (node_modules or outside the project)
+    Dependency,
+    /// Declaration-only file of a dependency
+    DependencyDeclarationFile,
+}
+
+impl DefinitionReason {
+    /// Classify a definition file relative to the project root
+    pub fn classify(file: &Path, root: &Path) -> Self {
+        let dependency =
+            !file.starts_with(root) || file.components().any(|c| c.as_os_str() == "node_modules");
+        let name = file
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        let declaration = [
+            ".d.ts", ".d.mts", ".d.cts", ".pyi", ".h", ".hh", ".hpp", ".hxx",
+        ]
+        .iter()
+        .any(|ext| name.ends_with(ext));
+
+        match (dependency, declaration) {
+            (false, false) => Self::Source,
+            (false, true) => Self::DeclarationFile,
+            (true, false) => Self::Dependency,
+            (true, true) => Self::DependencyDeclarationFile,
+        }
+    }
+}
+
+impl std::fmt::Display for DefinitionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Source => write!(f, "source"),
+            Self::DeclarationFile => write!(f, "declaration_file"),
+            Self::Dependency => write!(f, "dependency"),
+            Self::DependencyDeclarationFile => write!(f, "dependency_declaration_file"),
+        }
+    }
+}
+
+impl std::str::FromStr for DefinitionReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "source" => Ok(Self::Source),
+            "declaration_file" => Ok(Self::DeclarationFile),
+            "dependency" => Ok(Self::Dependency),
+            "dependency_declaration_file" => Ok(Self::DependencyDeclarationFile),
+            _ => Err(format!("Unknown definition reason '{}'", s)),
+        }
+    }
+}
+
 // ============================================================================
 // Type Hierarchy Types
 // ============================================================================
@@ -518,6 +786,86 @@ impl SelectionRange {
     }
 }
 
+// ============================================================================
+// Document Highlight Types
+// ============================================================================
+
+/// Occurrence of a symbol within its document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentHighlight {
+    /// Range of the occurrence
+    pub range: Range,
+    /// How the symbol is used at this occurrence
+    pub kind: DocumentHighlightKind,
+}
+
+/// Document highlight kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentHighlightKind {
+    /// Textual occurrence (default)
+    #[default]
+    Text,
+    /// Read access, e.g. a variable being used
+    Read,
+    /// Write access, e.g. a variable being assigned
+    Write,
+}
+
+impl DocumentHighlightKind {
+    pub fn from_lsp(kind: Option<u32>) -> Self {
+        match kind {
+            Some(2) => Self::Read,
+            Some(3) => Self::Write,
+            _ => Self::Text,
+        }
+    }
+}
+
+impl std::fmt::Display for DocumentHighlightKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Read => write!(f, "read"),
+            Self::Write => write!(f, "write"),
+        }
+    }
+}
+
+impl std::str::FromStr for DocumentHighlightKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "read" => Ok(Self::Read),
+            "write" => Ok(Self::Write),
+            _ => Err(format!(
+                "Unknown occurrence kind '{}'. Expected read, write or text",
+                s
+            )),
+        }
+    }
+}
+
+// ============================================================================
+// Semantic Token Types
+// ============================================================================
+
+/// Semantic token decoded with the server's legend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticToken {
+    /// Start position (0-indexed)
+    pub position: Position,
+    /// Length in characters
+    pub length: u32,
+    /// Token type name (e.g., variable, function, macro)
+    pub token_type: String,
+    /// Token modifier names (e.g., mutable, deprecated, async)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<String>,
+}
+
 // ============================================================================
 // Code Lens Types
 // ============================================================================
@@ -563,7 +911,7 @@ pub struct CodeAction {
     pub diagnostics: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub edit: Option<WorkspaceEdit>,
-    /// Original data returned by LSP server (needed for apply)
+    /// Original action returned by LSP server (needed for resolve and apply)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<serde_json::Value>,
 }
@@ -587,6 +935,8 @@ pub enum CodeActionKind {
     OrganizeImports,
     #[serde(rename = "source.fixAll")]
     FixAll,
+    #[serde(rename = "source.addMissingImports")]
+    AddMissingImports,
     #[serde(other)]
     #[default]
     Other,
@@ -603,24 +953,66 @@ impl std::fmt::Display for CodeActionKind {
             Self::Source => write!(f, "source"),
             Self::OrganizeImports => write!(f, "source.organizeImports"),
             Self::FixAll => write!(f, "source.fixAll"),
+            Self::AddMissingImports => write!(f, "source.addMissingImports"),
             Self::Other => write!(f, "other"),
         }
     }
 }
 
+impl CodeActionKind {
+    /// Known kinds, most specific first
+    const KNOWN: [Self; 10] = [
+        Self::RefactorExtract,
+        Self::RefactorInline,
+        Self::RefactorRewrite,
+        Self::OrganizeImports,
+        Self::FixAll,
+        Self::AddMissingImports,
+        Self::QuickFix,
+        Self::Refactor,
+        Self::Source,
+        Self::Other,
+    ];
+}
+
 impl From<Option<&str>> for CodeActionKind {
+    /// Map an LSP kind to the closest known kind; server-specific sub-kinds
+    /// such as `source.fixAll.eslint` map to their parent
     fn from(s: Option<&str>) -> Self {
-        match s {
-            Some(s) if s.starts_with("quickfix") => Self::QuickFix,
-            Some("refactor.extract") => Self::RefactorExtract,
-            Some("refactor.inline") => Self::RefactorInline,
-            Some("refactor.rewrite") => Self::RefactorRewrite,
-            Some(s) if s.starts_with("refactor") => Self::Refactor,
-            Some("source.organizeImports") => Self::OrganizeImports,
-            Some("source.fixAll") => Self::FixAll,
-            Some(s) if s.starts_with("source") => Self::Source,
-            _ => Self::Other,
-        }
+        let Some(s) = s else {
+            return Self::Other;
+        };
+        Self::KNOWN
+            .into_iter()
+            .find(|kind| {
+                let name = kind.to_string();
+                s.strip_prefix(name.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+            })
+            .unwrap_or(Self::Other)
+    }
+}
+
+impl std::str::FromStr for CodeActionKind {
+    type Err = String;
+
+    /// Parse an LSP kind (`source.organizeImports`) or its last segment
+    /// (`organizeImports`), ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::KNOWN
+            .into_iter()
+            .filter(|kind| *kind != Self::Other)
+            .find(|kind| {
+                let name = kind.to_string();
+                let short = name.rsplit('.').next().unwrap_or(&name);
+                name.eq_ignore_ascii_case(s) || short.eq_ignore_ascii_case(s)
+            })
+            .ok_or_else(|| {
+                format!(
+                    "Unknown code action kind '{}'. Expected e.g. organizeImports, fixAll, addMissingImports, quickfix",
+                    s
+                )
+            })
     }
 }
 
@@ -628,6 +1020,12 @@ impl From<Option<&str>> for CodeActionKind {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ApplyActionResult {
     pub changes: Vec<FileChangeWithEdits>,
+    /// Server command of the action, run through `workspace/executeCommand`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Whether `command` ran; it is skipped when only previewing the action
+    #[serde(default)]
+    pub command_executed: bool,
 }
 
 /// File change with detailed edits
@@ -635,6 +1033,57 @@ pub struct ApplyActionResult {
 pub struct FileChangeWithEdits {
     pub file: PathBuf,
     pub edits: Vec<TextEdit>,
+    /// Resource operation performed on `file` before `edits` are applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation: Option<ResourceOperation>,
+    /// Document version the server computed `edits` for
+    /// (`OptionalVersionedTextDocumentIdentifier.version`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// Version of the document the client had synced when the edit arrived
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synced_version: Option<u32>,
+    /// Whether `file` ends with a newline after `edits`; by default a final
+    /// newline is kept as it was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_newline: Option<bool>,
+}
+
+impl FileChangeWithEdits {
+    pub fn new(file: PathBuf, edits: Vec<TextEdit>) -> Self {
+        Self {
+            file,
+            edits,
+            operation: None,
+            version: None,
+            synced_version: None,
+            final_newline: None,
+        }
+    }
+
+    /// Resource operation on `file` without text edits
+    pub fn operation(file: PathBuf, operation: ResourceOperation) -> Self {
+        Self {
+            operation: Some(operation),
+            ..Self::new(file, Vec::new())
+        }
+    }
+}
+
+// ============================================================================
+// Formatting Types
+// ============================================================================
+
+/// LSP `FormattingOptions`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormattingOptions {
+    pub tab_size: u32,
+    pub insert_spaces: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trim_trailing_whitespace: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insert_final_newline: Option<bool>,
 }
 
 // ============================================================================
@@ -835,6 +1284,30 @@ mod tests {
         assert_eq!(pos.to_display(), (10, 5));
     }
 
+    #[test]
+    fn test_position_encoding_convert() {
+        use PositionEncoding::*;
+
+        // '값' is 3 bytes / 1 UTF-16 unit, '🦀' is 4 bytes / 2 UTF-16 units
+        let line = "let 값 = \"🦀\"; x";
+        assert_eq!(Utf32.convert(line, 4, Utf16), 4);
+        assert_eq!(Utf32.convert(line, 13, Utf16), 14);
+        assert_eq!(Utf32.convert(line, 13, Utf8), 18);
+        assert_eq!(Utf16.convert(line, 14, Utf32), 13);
+        assert_eq!(Utf8.convert(line, 18, Utf32), 13);
+        assert_eq!(Utf8.convert(line, 18, Utf16), 14);
+
+        assert_eq!(Utf32.byte_index(line, 13), 18);
+        assert_eq!(Utf32.byte_index(line, 99), line.len());
+        // Offsets inside a character round down to its start
+        assert_eq!(Utf16.byte_index(line, 10), 11);
+        assert_eq!(Utf32.convert("abc", 7, Utf16), 7);
+
+        assert_eq!(PositionEncoding::from_lsp(Some("utf-8")), Utf8);
+        assert_eq!(PositionEncoding::from_lsp(None), Utf16);
+        assert_eq!(Utf32.to_string(), "utf-32");
+    }
+
     #[test]
     fn test_workspace_edit_to_file_changes() {
         let mut changes = HashMap::new();
@@ -851,11 +1324,115 @@ mod tests {
             document_changes: None,
         };
 
-        let file_changes = edit.to_file_changes();
+        let file_changes = edit.to_file_changes().unwrap();
         assert_eq!(file_changes.len(), 1);
         assert_eq!(file_changes[0].edit_count, 1);
     }
 
+    #[test]
+    fn test_workspace_edit_resource_operations() {
+        let edit: WorkspaceEdit = serde_json::from_value(serde_json::json!({
+            "documentChanges": [
+                {"kind": "create", "uri": "file:///new.rs", "options": {"ignoreIfExists": true}},
+                {
+                    "textDocument": {"uri": "file:///new.rs", "version": 4},
+                    "edits": [{"range": {"start": {"line": 0, "character": 0},
+                        "end": {"line": 0, "character": 0}}, "newText": "fn a() {}"}]
+                },
+                {"kind": "rename", "oldUri": "file:///a.rs", "newUri": "file:///b.rs"},
+                {"kind": "delete", "uri": "file:///old.rs"}
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(edit.to_file_edits().unwrap()[1].version, Some(4));
+
+        let changes = edit.to_file_changes().unwrap();
+        assert_eq!(changes.len(), 4);
+        assert_eq!(
+            changes[0].operation,
+            Some(ResourceOperation::Create {
+                overwrite: false,
+                ignore_if_exists: true,
+            })
+        );
+        assert_eq!(changes[1].edit_count, 1);
+        assert!(changes[1].operation.is_none());
+        assert_eq!(changes[2].file, PathBuf::from("/b.rs"));
+        assert!(matches!(
+            &changes[2].operation,
+            Some(ResourceOperation::Rename { old_file, .. }) if old_file == Path::new("/a.rs")
+        ));
+        assert_eq!(
+            changes[3].operation.as_ref().map(|o| o.name()),
+            Some("delete")
+        );
+    }
+
+    #[test]
+    fn test_workspace_edit_rejects_unknown_operations() {
+        let text_edit = serde_json::json!({
+            "textDocument": {"uri": "file:///a.rs", "version": null},
+            "edits": [{"range": {"start": {"line": 0, "character": 0},
+                "end": {"line": 0, "character": 0}}, "newText": "x"}]
+        });
+        let parse = |change: serde_json::Value| {
+            serde_json::from_value::<WorkspaceEdit>(serde_json::json!({
+                "documentChanges": [text_edit.clone(), change]
+            }))
+            .unwrap()
+            .to_file_edits()
+        };
+
+        let err = parse(serde_json::json!({"kind": "copy", "uri": "file:///b.rs"})).unwrap_err();
+        assert!(err.starts_with("documentChanges[1]"), "{err}");
+        assert!(parse(serde_json::json!({"kind": "rename", "newUri": "file:///b.rs"})).is_err());
+        assert!(
+            parse(serde_json::json!({"textDocument": {"uri": "file:///b.rs"}, "edits": [{}]}))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_definition_reason_classify() {
+        let root = Path::new("/repo");
+        let classify = |p: &str| DefinitionReason::classify(Path::new(p), root);
+        assert_eq!(classify("/repo/src/api.ts"), DefinitionReason::Source);
+        assert_eq!(
+            classify("/repo/types/api.d.ts"),
+            DefinitionReason::DeclarationFile
+        );
+        assert_eq!(
+            classify("/repo/node_modules/lib/index.js"),
+            DefinitionReason::Dependency
+        );
+        assert_eq!(
+            classify("/repo/node_modules/@types/node/fs.d.ts"),
+            DefinitionReason::DependencyDeclarationFile
+        );
+        assert_eq!(
+            classify("/usr/include/stdio.h"),
+            DefinitionReason::DependencyDeclarationFile
+        );
+    }
+
+    #[test]
+    fn test_document_highlight_kind() {
+        assert_eq!(
+            DocumentHighlightKind::from_lsp(None),
+            DocumentHighlightKind::Text
+        );
+        assert_eq!(
+            DocumentHighlightKind::from_lsp(Some(3)),
+            DocumentHighlightKind::Write
+        );
+        assert_eq!(
+            "READ".parse::<DocumentHighlightKind>(),
+            Ok(DocumentHighlightKind::Read)
+        );
+        assert!("mutate".parse::<DocumentHighlightKind>().is_err());
+    }
+
     #[test]
     fn test_uri_roundtrip_simple() {
         let path = PathBuf::from("/test/file.rs");
@@ -897,6 +1474,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_code_action_kind_parse() {
+        assert_eq!(
+            CodeActionKind::from(Some("source.fixAll.eslint")),
+            CodeActionKind::FixAll
+        );
+        assert_eq!(
+            CodeActionKind::from(Some("quickfix")),
+            CodeActionKind::QuickFix
+        );
+        assert_eq!(
+            CodeActionKind::from(Some("source.sortImports")),
+            CodeActionKind::Source
+        );
+        assert_eq!(
+            CodeActionKind::from(Some("sourcery")),
+            CodeActionKind::Other
+        );
+        assert_eq!(
+            "organizeImports".parse::<CodeActionKind>().unwrap(),
+            CodeActionKind::OrganizeImports
+        );
+        assert_eq!(
+            "source.addMissingImports"
+                .parse::<CodeActionKind>()
+                .unwrap(),
+            CodeActionKind::AddMissingImports
+        );
+        assert!("other".parse::<CodeActionKind>().is_err());
+    }
+
     #[test]
     fn test_hover_extract_symbol_name_kotlin() {
         let hover = HoverInfo {
@@ -9,6 +9,7 @@ use tree_sitter::{Language, Parser, Query, QueryCursor};
 
 use crate::error::SearchError;
 use crate::infra::file_filter::{FileFilter, FileFilterConfig};
+use crate::models::lsp::PositionEncoding;
 use crate::models::symbol::Language as SymbolLanguage;
 
 #[derive(Debug, Clone)]
@@ -162,8 +163,8 @@ impl DefaultAstQueryService {
                 file: file_path.to_path_buf(),
                 start_line: start.row as u32 + 1,
                 end_line: end.row as u32 + 1,
-                start_column: start.column as u32,
-                end_column: end.column as u32,
+                start_column: char_column(content, node.start_byte(), start.column),
+                end_column: char_column(content, node.end_byte(), end.column),
                 text,
                 captures,
             });
@@ -269,6 +270,11 @@ impl AstQueryService for DefaultAstQueryService {
     }
 }
 
+/// Character column of a byte offset whose tree-sitter column is `byte_column`
+fn char_column(content: &str, byte: usize, byte_column: usize) -> u32 {
+    PositionEncoding::Utf32.offset_of(&content[byte - byte_column..], byte_column)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -163,6 +163,7 @@ fn merge_config(base: SymoraConfig, overlay: SymoraConfig) -> SymoraConfig {
         lsp: overlay.lsp,
         search: overlay.search,
         output: overlay.output,
+        format: overlay.format,
         daemon: base.daemon, // daemon settings from global only
     }
 }
@@ -8,18 +8,22 @@ use serde_json::Value;
 
 use crate::daemon::DaemonClient;
 use crate::daemon::protocol::dto::{
-    ApplyActionResponse, CallsResponse, CodeActionsResponse, CodeLensResponse, DefinitionResponse,
-    DiagnosticsResponse, FoldingRangesResponse, HoverResponse, ImplementationsResponse,
-    InlayHintsResponse, PrepareRenameResponse, ReferencesResponse, RenameResponse,
-    SelectionRangesResponse, SignatureResponse, SymbolsResponse, TypeHierarchyResponse,
+    ApplyActionResponse, CallsResponse, CodeActionsResponse, CodeLensResponse,
+    ContentDiagnosticsResponse, DeclarationsResponse, DefinitionResponse, DefinitionsResponse,
+    DiagnosticsResponse, DocumentHighlightsResponse, FileEditDto, FoldingRangesResponse,
+    FormatResponse, HoverResponse, ImplementationsResponse, InlayHintsResponse,
+    PrepareCallHierarchyResponse, PrepareRenameResponse, ReferencesResponse, RenameResponse,
+    SelectionRangesResponse, SemanticTokensResponse, SignatureResponse, SymbolsResponse,
+    TextEditDto, TypeHierarchyResponse, WorkspaceDiagnosticsResponse,
 };
 use crate::error::LspError;
-use crate::models::diagnostic::{Diagnostic, DiagnosticSeverity};
+use crate::models::diagnostic::{Diagnostic, DiagnosticSeverity, DiagnosticsReport};
 use crate::models::lsp::{
     ApplyActionResult, CallHierarchyItem, CodeAction, CodeActionKind, CodeLens, CodeLensCommand,
-    FileChange, FileChangeWithEdits, FindSymbolsOptions, FoldingRange, FoldingRangeKind, HoverInfo,
-    InlayHint, InlayHintKind, ParameterInfo, Position, PrepareRenameResult, Range, RenameResult,
-    SelectionRange, ServerStatus, SignatureHelp, SignatureInfo, TextEdit, TypeHierarchyItem,
+    DefinitionCandidate, DocumentHighlight, FileChangeWithEdits, FindSymbolsOptions, FoldingRange,
+    FoldingRangeKind, FormattingOptions, HoverInfo, InlayHint, InlayHintKind, ParameterInfo,
+    Position, PrepareRenameResult, Range, RenameResult, SelectionRange, SemanticToken,
+    ServerStatus, SignatureHelp, SignatureInfo, TextEdit, TypeHierarchyItem,
 };
 use crate::models::symbol::{Language, Location, Symbol, SymbolKind};
 use crate::services::lsp::LspService;
@@ -28,6 +32,45 @@ fn parse<T: DeserializeOwned>(value: Value) -> Result<T, LspError> {
     serde_json::from_value(value).map_err(|e| LspError::Protocol(e.to_string()))
 }
 
+fn file_edits(changes: Vec<FileEditDto>) -> Vec<FileChangeWithEdits> {
+    changes
+        .into_iter()
+        .map(|c| FileChangeWithEdits {
+            file: PathBuf::from(c.file),
+            edits: text_edits(c.edits),
+            operation: c.operation,
+            version: c.version,
+            synced_version: c.synced_version,
+            final_newline: None,
+        })
+        .collect()
+}
+
+fn text_edits(edits: Vec<TextEditDto>) -> Vec<TextEdit> {
+    edits
+        .into_iter()
+        .map(|e| TextEdit {
+            range: e.range.into(),
+            new_text: e.new_text,
+        })
+        .collect()
+}
+
+fn code_actions(response: CodeActionsResponse) -> Vec<CodeAction> {
+    response
+        .actions
+        .into_iter()
+        .map(|a| CodeAction {
+            title: a.title,
+            kind: CodeActionKind::from(a.kind.as_deref()),
+            is_preferred: a.is_preferred,
+            diagnostics: a.diagnostics,
+            edit: None,
+            data: a.data,
+        })
+        .collect()
+}
+
 pub struct DaemonLspService {
     client: DaemonClient,
 }
@@ -120,6 +163,37 @@ impl LspService for DaemonLspService {
         Ok(response.definition.map(Into::into))
     }
 
+    async fn definitions(
+        &self,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<DefinitionCandidate>, LspError> {
+        let result = self.client.definitions(file, line, column).await?;
+        let response: DefinitionsResponse = parse(result)?;
+        response
+            .definitions
+            .into_iter()
+            .map(|d| {
+                Ok(DefinitionCandidate {
+                    location: d.location.into(),
+                    reason: d.reason.parse().map_err(LspError::Protocol)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn goto_declaration(
+        &self,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<Location>, LspError> {
+        let result = self.client.goto_declaration(file, line, column).await?;
+        let response: DeclarationsResponse = parse(result)?;
+        Ok(response.declarations.into_iter().map(Into::into).collect())
+    }
+
     async fn goto_type_definition(
         &self,
         file: &Path,
@@ -150,6 +224,27 @@ impl LspService for DaemonLspService {
             .collect())
     }
 
+    async fn document_highlights(
+        &self,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<DocumentHighlight>, LspError> {
+        let result = self.client.document_highlights(file, line, column).await?;
+        let response: DocumentHighlightsResponse = parse(result)?;
+        Ok(response
+            .highlights
+            .into_iter()
+            .map(|h| DocumentHighlight {
+                range: Range::new(
+                    Position::new(h.start_line, h.start_character),
+                    Position::new(h.end_line, h.end_character),
+                ),
+                kind: h.kind.parse().unwrap_or_default(),
+            })
+            .collect())
+    }
+
     async fn hover(
         &self,
         file: &Path,
@@ -206,12 +301,12 @@ impl LspService for DaemonLspService {
         }))
     }
 
-    async fn diagnostics(&self, file: &Path) -> Result<Vec<Diagnostic>, LspError> {
+    async fn diagnostics(&self, file: &Path) -> Result<DiagnosticsReport, LspError> {
         let result = self.client.diagnostics(file).await?;
 
         let response: DiagnosticsResponse = parse(result)?;
 
-        Ok(response
+        let diagnostics = response
             .diagnostics
             .into_iter()
             .map(|d| {
@@ -236,7 +331,28 @@ impl LspService for DaemonLspService {
                     related_information: vec![],
                 }
             })
-            .collect())
+            .collect();
+
+        Ok(DiagnosticsReport {
+            diagnostics,
+            version: response.version,
+            stale: response.stale,
+        })
+    }
+
+    async fn content_diagnostics(
+        &self,
+        documents: &[(PathBuf, String)],
+    ) -> Result<Vec<DiagnosticsReport>, LspError> {
+        let result = self.client.content_diagnostics(documents).await?;
+        let response: ContentDiagnosticsResponse = parse(result)?;
+        Ok(response.reports)
+    }
+
+    async fn workspace_diagnostics(&self, files: &[PathBuf]) -> Result<Vec<Diagnostic>, LspError> {
+        let result = self.client.workspace_diagnostics(files).await?;
+        let response: WorkspaceDiagnosticsResponse = parse(result)?;
+        Ok(response.diagnostics)
     }
 
     async fn prepare_rename(
@@ -270,17 +386,31 @@ impl LspService for DaemonLspService {
         let response: RenameResponse = parse(result)?;
 
         Ok(RenameResult {
-            changes: response
-                .changes
-                .into_iter()
-                .map(|c| FileChange {
-                    file: PathBuf::from(c.file),
-                    edit_count: c.edit_count,
-                })
-                .collect(),
+            changes: file_edits(response.changes),
         })
     }
 
+    async fn prepare_call_hierarchy(
+        &self,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Option<CallHierarchyItem>, LspError> {
+        let result = self
+            .client
+            .prepare_call_hierarchy(file, line, column)
+            .await?;
+
+        let response: PrepareCallHierarchyResponse = parse(result)?;
+
+        Ok(response.item.map(|c| CallHierarchyItem {
+            name: c.name,
+            kind: SymbolKind::from_str_loose(&c.kind),
+            location: Location::point(PathBuf::from(&c.file), c.line, c.column),
+            call_site: None,
+        }))
+    }
+
     async fn incoming_calls(
         &self,
         file: &Path,
@@ -462,6 +592,25 @@ impl LspService for DaemonLspService {
             .collect())
     }
 
+    async fn semantic_tokens(
+        &self,
+        file: &Path,
+        range: Option<Range>,
+    ) -> Result<Vec<SemanticToken>, LspError> {
+        let result = self.client.semantic_tokens(file, range.as_ref()).await?;
+        let response: SemanticTokensResponse = parse(result)?;
+        Ok(response
+            .tokens
+            .into_iter()
+            .map(|t| SemanticToken {
+                position: Position::new(t.line, t.character),
+                length: t.length,
+                token_type: t.token_type,
+                modifiers: t.modifiers,
+            })
+            .collect())
+    }
+
     async fn code_actions(
         &self,
         file: &Path,
@@ -471,52 +620,53 @@ impl LspService for DaemonLspService {
         let result = self.client.code_actions(file, line, column).await?;
 
         let response: CodeActionsResponse = parse(result)?;
+        Ok(code_actions(response))
+    }
 
-        Ok(response
-            .actions
-            .into_iter()
-            .map(|a| CodeAction {
-                title: a.title,
-                kind: CodeActionKind::from(a.kind.as_deref()),
-                is_preferred: a.is_preferred,
-                diagnostics: a.diagnostics,
-                edit: None,
-                data: None,
-            })
-            .collect())
+    async fn source_actions(
+        &self,
+        file: &Path,
+        kinds: &[CodeActionKind],
+    ) -> Result<Vec<CodeAction>, LspError> {
+        let result = self.client.source_actions(file, kinds).await?;
+        let response: CodeActionsResponse = parse(result)?;
+        Ok(code_actions(response))
     }
 
     async fn apply_code_action(
         &self,
         file: &Path,
         action: &CodeAction,
+        run_command: bool,
     ) -> Result<ApplyActionResult, LspError> {
         let action_json = serde_json::to_value(action)
             .map_err(|e| LspError::Protocol(format!("Failed to serialize action: {}", e)))?;
 
-        let result = self.client.apply_code_action(file, &action_json).await?;
+        let result = self
+            .client
+            .apply_code_action(file, &action_json, run_command)
+            .await?;
 
         let response: ApplyActionResponse = parse(result)?;
 
         Ok(ApplyActionResult {
-            changes: response
-                .changes
-                .into_iter()
-                .map(|c| FileChangeWithEdits {
-                    file: PathBuf::from(c.file),
-                    edits: c
-                        .edits
-                        .into_iter()
-                        .map(|e| TextEdit {
-                            range: e.range.into(),
-                            new_text: e.new_text,
-                        })
-                        .collect(),
-                })
-                .collect(),
+            changes: file_edits(response.changes),
+            command: response.command,
+            command_executed: response.command_executed,
         })
     }
 
+    async fn format(
+        &self,
+        file: &Path,
+        range: Option<Range>,
+        options: &FormattingOptions,
+    ) -> Result<Vec<TextEdit>, LspError> {
+        let result = self.client.format(file, range.as_ref(), options).await?;
+        let response: FormatResponse = parse(result)?;
+        Ok(text_edits(response.edits))
+    }
+
     async fn is_available(&self, _language: Language) -> bool {
         self.client.status().await.is_ok()
     }
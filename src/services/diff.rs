@@ -0,0 +1,265 @@
+//! Line diff
+//!
+//! Myers' O(ND) difference algorithm over lines, rendered as unified diff
+//! hunks for dry-run output.
+
+use std::fmt::Write;
+
+/// Edit distance above which the texts are treated as fully rewritten
+const MAX_DISTANCE: usize = 2_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Unified diff of `old` and `new` with `context` lines around each change,
+/// or an empty string when the texts are equal
+pub fn unified_diff(path: &str, old: &str, new: &str, context: usize) -> String {
+    if old == new {
+        return String::new();
+    }
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = diff_lines(&a, &b);
+
+    let mut out = format!("--- a/{path}\n+++ b/{path}\n");
+    for hunk in hunks(&ops, context) {
+        let (old_start, new_start) = positions(&ops[..hunk.start]);
+        let (old_len, new_len) = positions(&ops[hunk.clone()]);
+        let _ = writeln!(
+            out,
+            "@@ -{} +{} @@",
+            range_header(old_start, old_len),
+            range_header(new_start, new_len)
+        );
+
+        let (mut i, mut j) = (old_start, new_start);
+        for op in &ops[hunk] {
+            let (prefix, line) = match op {
+                Op::Equal => {
+                    i += 1;
+                    j += 1;
+                    (' ', a[i - 1])
+                }
+                Op::Delete => {
+                    i += 1;
+                    ('-', a[i - 1])
+                }
+                Op::Insert => {
+                    j += 1;
+                    ('+', b[j - 1])
+                }
+            };
+            out.push(prefix);
+            match line.strip_suffix('\n') {
+                Some(line) => {
+                    out.push_str(line);
+                    out.push('\n');
+                }
+                None => {
+                    out.push_str(line);
+                    out.push_str("\n\\ No newline at end of file\n");
+                }
+            }
+        }
+    }
+    out
+}
+
+/// New line index for each old line index of `old`, plus one entry for the
+/// end of the text. Deleted lines map to where they would have been.
+pub fn line_map(old: &str, new: &str) -> Vec<usize> {
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+
+    let mut map = Vec::with_capacity(a.len() + 1);
+    let mut j = 0;
+    for op in diff_lines(&a, &b) {
+        match op {
+            Op::Equal => {
+                map.push(j);
+                j += 1;
+            }
+            Op::Delete => map.push(j),
+            Op::Insert => j += 1,
+        }
+    }
+    map.push(j);
+    map
+}
+
+/// `start,len` in unified diff notation (1-indexed; an empty range names the
+/// line before it)
+fn range_header(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+/// Lines of the old and new text covered by `ops`
+fn positions(ops: &[Op]) -> (usize, usize) {
+    ops.iter().fold((0, 0), |(i, j), op| match op {
+        Op::Equal => (i + 1, j + 1),
+        Op::Delete => (i + 1, j),
+        Op::Insert => (i, j + 1),
+    })
+}
+
+/// Op index ranges of hunks: changes plus `context` equal lines on each side,
+/// merging changes separated by at most twice the context
+fn hunks(ops: &[Op], context: usize) -> Vec<std::ops::Range<usize>> {
+    let mut hunks: Vec<std::ops::Range<usize>> = Vec::new();
+    for (idx, _) in ops.iter().enumerate().filter(|(_, op)| **op != Op::Equal) {
+        let start = idx.saturating_sub(context);
+        let end = (idx + 1 + context).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.end => last.end = end,
+            _ => hunks.push(start..end),
+        }
+    }
+    hunks
+}
+
+/// Shortest edit script from `a` to `b`
+fn diff_lines(a: &[&str], b: &[&str]) -> Vec<Op> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    let mut ops = vec![Op::Equal; prefix];
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    match myers(a_mid, b_mid) {
+        Some(mid) => ops.extend(mid),
+        None => {
+            ops.extend(std::iter::repeat_n(Op::Delete, a_mid.len()));
+            ops.extend(std::iter::repeat_n(Op::Insert, b_mid.len()));
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Equal, suffix));
+    ops
+}
+
+/// Myers' greedy algorithm; `None` if the edit distance exceeds [`MAX_DISTANCE`]
+fn myers(a: &[&str], b: &[&str]) -> Option<Vec<Op>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (a.len() + b.len()).min(MAX_DISTANCE);
+    let offset = max as isize + 1;
+    let index = |k: isize| (k + offset) as usize;
+
+    let mut v = vec![0isize; 2 * max + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    for d in 0..=max as isize {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+
+            if x >= n && y >= m {
+                return Some(backtrack(&trace, n, m, index));
+            }
+        }
+    }
+    None
+}
+
+fn backtrack(trace: &[Vec<isize>], n: isize, m: isize, index: impl Fn(isize) -> usize) -> Vec<Op> {
+    let (mut x, mut y) = (n, m);
+    let mut ops = Vec::new();
+
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[index(prev_k)];
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            ops.push(Op::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            if x == prev_x {
+                ops.push(Op::Insert);
+                y -= 1;
+            } else {
+                ops.push(Op::Delete);
+                x -= 1;
+            }
+        }
+    }
+
+    ops.reverse();
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff_equal() {
+        assert_eq!(unified_diff("a.rs", "x\n", "x\n", 3), "");
+    }
+
+    #[test]
+    fn test_unified_diff_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        assert_eq!(
+            unified_diff("x.rs", old, new, 1),
+            "--- a/x.rs\n+++ b/x.rs\n\
+             @@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n\
+             @@ -9 +9,2 @@\n i\n+j\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_missing_newline() {
+        assert_eq!(
+            unified_diff("x.rs", "a\nb", "a\nb\n", 3),
+            "--- a/x.rs\n+++ b/x.rs\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n"
+        );
+    }
+
+    #[test]
+    fn test_line_map() {
+        let old = "a\nb\nc\nd\n";
+        let new = "x\na\nc\ny\nd\n";
+        assert_eq!(line_map(old, new), vec![1, 2, 2, 4, 5]);
+        assert_eq!(line_map("", "a\n"), vec![1]);
+    }
+
+    #[test]
+    fn test_diff_lines_minimal() {
+        let a = ["a\n", "b\n", "c\n", "a\n", "b\n", "b\n", "a\n"];
+        let b = ["c\n", "b\n", "a\n", "b\n", "a\n", "c\n"];
+        let ops = diff_lines(&a, &b);
+        let edits = ops.iter().filter(|op| **op != Op::Equal).count();
+        assert_eq!(edits, 5);
+        assert_eq!(positions(&ops), (a.len(), b.len()));
+    }
+}
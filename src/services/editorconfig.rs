@@ -0,0 +1,288 @@
+//! `.editorconfig` lookup
+//!
+//! Resolves the indentation and whitespace properties that apply to a file,
+//! reading `.editorconfig` files from the file's directory up to the project
+//! root (or the first file declaring `root = true`).
+
+use std::fs;
+use std::path::Path;
+
+/// Properties relevant to formatting; `None` when unset
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditorConfig {
+    pub insert_spaces: Option<bool>,
+    pub indent_size: Option<u32>,
+    pub tab_width: Option<u32>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub insert_final_newline: Option<bool>,
+}
+
+impl EditorConfig {
+    /// Properties for `file`, with closer `.editorconfig` files taking precedence
+    pub fn resolve(file: &Path, root: &Path) -> Self {
+        let mut configs = Vec::new();
+        for dir in file.ancestors().skip(1) {
+            if let Ok(content) = fs::read_to_string(dir.join(".editorconfig")) {
+                let is_root = is_root(&content);
+                configs.push((dir, content));
+                if is_root {
+                    break;
+                }
+            }
+            if dir == root {
+                break;
+            }
+        }
+
+        let mut config = Self::default();
+        for (dir, content) in configs.iter().rev() {
+            let Ok(relative) = file.strip_prefix(dir) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            config.apply(content, &relative);
+        }
+        config
+    }
+
+    /// Indentation width, falling back to `tab_width` for `indent_size = tab`
+    pub fn tab_size(&self) -> Option<u32> {
+        self.indent_size.or(self.tab_width)
+    }
+
+    /// Apply the sections of one `.editorconfig` file matching `path`
+    fn apply(&mut self, content: &str, path: &str) {
+        let mut matched = false;
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                matched = section_matches(section, path);
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            if !matched {
+                continue;
+            }
+
+            // Unrecognized values, including `unset`, clear the property
+            let value = value.trim().to_lowercase();
+            let flag = |v: &str| match v {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            };
+            match key.trim().to_lowercase().as_str() {
+                "indent_style" => {
+                    self.insert_spaces = match value.as_str() {
+                        "space" => Some(true),
+                        "tab" => Some(false),
+                        _ => None,
+                    }
+                }
+                // `tab` defers to tab_width
+                "indent_size" => self.indent_size = value.parse().ok(),
+                "tab_width" => self.tab_width = value.parse().ok(),
+                "trim_trailing_whitespace" => self.trim_trailing_whitespace = flag(&value),
+                "insert_final_newline" => self.insert_final_newline = flag(&value),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// `root = true` in the preamble (before the first section)
+fn is_root(content: &str) -> bool {
+    content
+        .lines()
+        .map(str::trim)
+        .take_while(|l| !l.starts_with('['))
+        .filter_map(|l| l.split_once('='))
+        .any(|(k, v)| {
+            k.trim().eq_ignore_ascii_case("root") && v.trim().eq_ignore_ascii_case("true")
+        })
+}
+
+/// Section globs without `/` match the file name in any directory; others are
+/// relative to the `.editorconfig` directory
+fn section_matches(section: &str, path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    expand_braces(section).iter().any(|pattern| {
+        let (pattern, target) = match pattern.strip_prefix('/') {
+            Some(anchored) => (anchored, path),
+            None if pattern.contains('/') => (pattern.as_str(), path),
+            None => (pattern.as_str(), file_name),
+        };
+        let pattern: Vec<char> = pattern.chars().collect();
+        let target: Vec<char> = target.chars().collect();
+        glob_match(&pattern, &target)
+    })
+}
+
+/// Expand `{a,b}` alternatives into separate patterns
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_string()];
+    };
+    let mut depth = 0;
+    let close = pattern[open..].char_indices().find_map(|(i, c)| {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+        None
+    });
+    let Some(close) = close else {
+        return vec![pattern.to_string()];
+    };
+
+    let inner = &pattern[open + 1..close];
+    if !inner.contains(',') {
+        return vec![pattern.to_string()];
+    }
+
+    let mut alternatives = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in inner.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                alternatives.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    alternatives.push(&inner[start..]);
+
+    alternatives
+        .into_iter()
+        .flat_map(|alt| {
+            expand_braces(&format!(
+                "{}{}{}",
+                &pattern[..open],
+                alt,
+                &pattern[close + 1..]
+            ))
+        })
+        .collect()
+}
+
+/// Match `*`, `**`, `?` and `[...]` classes; `*` and `?` do not cross `/`
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            (0..=text.len()).any(|i| glob_match(&pattern[2..], &text[i..]))
+        }
+        Some('*') => {
+            for i in 0..=text.len() {
+                if glob_match(&pattern[1..], &text[i..]) {
+                    return true;
+                }
+                if text.get(i) == Some(&'/') {
+                    return false;
+                }
+            }
+            false
+        }
+        Some('?') => {
+            text.first().is_some_and(|&c| c != '/') && glob_match(&pattern[1..], &text[1..])
+        }
+        Some('[') => match pattern.iter().skip(1).position(|&c| c == ']') {
+            Some(len) if len > 0 => {
+                let class = &pattern[1..=len];
+                let (negate, class) = match class.first() {
+                    Some('!') => (true, &class[1..]),
+                    _ => (false, class),
+                };
+                text.first()
+                    .is_some_and(|&c| c != '/' && class_contains(class, c) != negate)
+                    && glob_match(&pattern[len + 2..], &text[1..])
+            }
+            _ => text.first() == Some(&'[') && glob_match(&pattern[1..], &text[1..]),
+        },
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &text[1..])
+        }
+        Some(&c) => text.first() == Some(&c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+fn class_contains(class: &[char], c: char) -> bool {
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            if (class[i]..=class[i + 2]).contains(&c) {
+                return true;
+            }
+            i += 3;
+        } else {
+            if class[i] == c {
+                return true;
+            }
+            i += 1;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_section_matches() {
+        assert!(section_matches("*", "src/main.rs"));
+        assert!(section_matches("*.rs", "src/main.rs"));
+        assert!(section_matches("*.{js,ts}", "web/app.ts"));
+        assert!(!section_matches("*.{js,ts}", "web/app.rs"));
+        assert!(section_matches("src/*.rs", "src/main.rs"));
+        assert!(!section_matches("src/*.rs", "src/cli/mod.rs"));
+        assert!(section_matches("src/**.rs", "src/cli/mod.rs"));
+        assert!(section_matches("/Makefile", "Makefile"));
+        assert!(!section_matches("/Makefile", "sub/Makefile"));
+        assert!(section_matches("[Mm]akefile", "sub/makefile"));
+        assert!(section_matches("file?.py", "file1.py"));
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let dir = TempDir::new().unwrap();
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        fs::write(
+            dir.path().join(".editorconfig"),
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 4\n\n\
+             [*.go]\nindent_style = tab\nindent_size = tab\ntab_width = 8\n",
+        )
+        .unwrap();
+        fs::write(
+            sub.join(".editorconfig"),
+            "[*.rs]\nindent_size = 2\ntrim_trailing_whitespace = true\n",
+        )
+        .unwrap();
+
+        let rs = EditorConfig::resolve(&sub.join("lib.rs"), dir.path());
+        assert_eq!(rs.insert_spaces, Some(true));
+        assert_eq!(rs.tab_size(), Some(2));
+        assert_eq!(rs.trim_trailing_whitespace, Some(true));
+
+        let go = EditorConfig::resolve(&sub.join("main.go"), dir.path());
+        assert_eq!(go.insert_spaces, Some(false));
+        assert_eq!(go.tab_size(), Some(8));
+        assert_eq!(go.insert_final_newline, None);
+    }
+}
@@ -0,0 +1,366 @@
+//! Edit journal
+//!
+//! Every file mutation made by symora is recorded under `.symora/history/`
+//! with the content before and after it, so a step can be undone later
+//! without touching unrelated changes.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::EditError;
+use crate::models::lsp::{FileChangeWithEdits, Position, Range, ResourceOperation, TextEdit};
+use crate::services::workspace_edit::{AppliedFile, apply_workspace_edit};
+
+/// Entries kept before the oldest ones are dropped
+const MAX_ENTRIES: usize = 100;
+
+/// One recorded mutation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: u64,
+    /// UTC time in RFC 3339 format
+    pub timestamp: String,
+    pub command: String,
+    pub files: Vec<JournalFile>,
+}
+
+/// File content before and after a mutation; `None` when the file did not
+/// exist (created) or no longer exists (deleted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalFile {
+    /// Path relative to the project root
+    pub path: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+pub struct Journal {
+    root: PathBuf,
+    dir: PathBuf,
+}
+
+impl Journal {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            dir: root.join(".symora").join("history"),
+        }
+    }
+
+    /// Record applied files; returns the entry id, or `None` if nothing changed
+    pub fn record(&self, command: &str, files: &[AppliedFile]) -> io::Result<Option<u64>> {
+        if files.is_empty() {
+            return Ok(None);
+        }
+        fs::create_dir_all(&self.dir)?;
+
+        let entries = self.entry_paths()?;
+        let id = entries.last().map_or(1, |(id, _)| id + 1);
+        let entry = JournalEntry {
+            id,
+            timestamp: format_utc(now_secs()),
+            command: command.to_string(),
+            files: files
+                .iter()
+                .map(|f| JournalFile {
+                    path: self.relative(&f.path),
+                    before: f.original.clone(),
+                    after: f.updated.clone(),
+                })
+                .collect(),
+        };
+        let json = serde_json::to_string(&entry).map_err(io::Error::other)?;
+        fs::write(self.entry_path(id), json)?;
+
+        let excess = (entries.len() + 1).saturating_sub(MAX_ENTRIES);
+        for (_, path) in entries.iter().take(excess) {
+            let _ = fs::remove_file(path);
+        }
+
+        Ok(Some(id))
+    }
+
+    /// All entries, oldest first
+    pub fn entries(&self) -> io::Result<Vec<JournalEntry>> {
+        self.entry_paths()?
+            .into_iter()
+            .map(|(_, path)| {
+                let content = fs::read_to_string(&path)?;
+                serde_json::from_str(&content).map_err(io::Error::other)
+            })
+            .collect()
+    }
+
+    /// Revert the last `steps` entries as one atomic edit.
+    ///
+    /// Files must still match what the newest undone step wrote, unless
+    /// `force` is set. Returns the undone entries, newest first.
+    pub fn undo(&self, steps: usize, force: bool) -> Result<Vec<JournalEntry>, EditError> {
+        let read_error = |source: io::Error| EditError::Read {
+            path: self.dir.display().to_string(),
+            source,
+        };
+        let mut entries = self.entries().map_err(read_error)?;
+        let undone: Vec<JournalEntry> = entries
+            .drain(entries.len().saturating_sub(steps)..)
+            .rev()
+            .collect();
+
+        // Per file: what the newest step wrote and what the oldest step found
+        let mut order: Vec<&str> = Vec::new();
+        let mut plan: HashMap<&str, (Option<&str>, Option<&str>)> = HashMap::new();
+        for entry in &undone {
+            for file in &entry.files {
+                plan.entry(&file.path)
+                    .and_modify(|(_, before)| *before = file.before.as_deref())
+                    .or_insert_with(|| {
+                        order.push(&file.path);
+                        (file.after.as_deref(), file.before.as_deref())
+                    });
+            }
+        }
+
+        let conflicts: Vec<&str> = order
+            .iter()
+            .copied()
+            .filter(|path| fs::read_to_string(self.root.join(path)).ok().as_deref() != plan[path].0)
+            .collect();
+        if !conflicts.is_empty() && !force {
+            return Err(EditError::UndoConflict {
+                files: conflicts.join(", "),
+            });
+        }
+
+        let changes: Vec<FileChangeWithEdits> = order
+            .iter()
+            .map(|path| restore(self.root.join(path), plan[path].1))
+            .collect();
+        apply_workspace_edit(&changes)?;
+
+        for entry in &undone {
+            let _ = fs::remove_file(self.entry_path(entry.id));
+        }
+        Ok(undone)
+    }
+
+    fn entry_path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{:06}.json", id))
+    }
+
+    /// Entry ids and paths, oldest first
+    fn entry_paths(&self) -> io::Result<Vec<(u64, PathBuf)>> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut entries: Vec<(u64, PathBuf)> = dir
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let path = e.path();
+                let id = path
+                    .extension()
+                    .filter(|ext| *ext == "json")
+                    .and(path.file_stem())?
+                    .to_str()?
+                    .parse()
+                    .ok()?;
+                Some((id, path))
+            })
+            .collect();
+        entries.sort_by_key(|(id, _)| *id);
+        Ok(entries)
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+}
+
+/// Change that recreates a file with exactly `content`, or deletes it for
+/// `None`. The insert goes into an emptied file, so its line endings and byte
+/// order mark are written as recorded.
+fn restore(file: PathBuf, content: Option<&str>) -> FileChangeWithEdits {
+    match content {
+        Some(content) => FileChangeWithEdits {
+            operation: Some(ResourceOperation::Create {
+                overwrite: true,
+                ignore_if_exists: false,
+            }),
+            ..FileChangeWithEdits::new(
+                file,
+                vec![TextEdit {
+                    range: Range::point(Position::new(0, 0)),
+                    new_text: content.to_string(),
+                }],
+            )
+        },
+        None => FileChangeWithEdits::operation(
+            file,
+            ResourceOperation::Delete {
+                recursive: false,
+                ignore_if_not_exists: true,
+            },
+        ),
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Format Unix seconds as `YYYY-MM-DDTHH:MM:SSZ`
+fn format_utc(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn applied(path: PathBuf, before: &str, after: &str) -> AppliedFile {
+        fs::write(&path, after).unwrap();
+        AppliedFile {
+            path,
+            original: Some(before.to_string()),
+            updated: Some(after.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_utc(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_utc(1_767_225_599), "2025-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn test_record_and_undo() {
+        let dir = TempDir::new().unwrap();
+        let journal = Journal::new(dir.path());
+        let a = dir.path().join("a.rs");
+        let b = dir.path().join("b.rs");
+
+        let first = [applied(a.clone(), "v1\n", "v2\n")];
+        assert_eq!(journal.record("edit a", &first).unwrap(), Some(1));
+        let second = [
+            applied(a.clone(), "v2\n", "v3\n"),
+            applied(b.clone(), "b1\n", "b2\n"),
+        ];
+        assert_eq!(journal.record("rename", &second).unwrap(), Some(2));
+        assert_eq!(journal.record("noop", &[]).unwrap(), None);
+
+        let entries = journal.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].files[0].path, "a.rs");
+
+        let undone = journal.undo(2, false).unwrap();
+        assert_eq!(undone[0].command, "rename");
+        assert_eq!(fs::read_to_string(&a).unwrap(), "v1\n");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "b1\n");
+        assert!(journal.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_undo_created_and_deleted_files() {
+        let dir = TempDir::new().unwrap();
+        let journal = Journal::new(dir.path());
+        let created = dir.path().join("new.rs");
+        let deleted = dir.path().join("old.rs");
+        fs::write(&created, "new\n").unwrap();
+
+        let files = [
+            AppliedFile {
+                path: created.clone(),
+                original: None,
+                updated: Some("new\n".to_string()),
+            },
+            AppliedFile {
+                path: deleted.clone(),
+                original: Some("old\n".to_string()),
+                updated: None,
+            },
+        ];
+        journal.record("actions apply", &files).unwrap();
+        journal.undo(1, false).unwrap();
+
+        assert!(!created.exists());
+        assert_eq!(fs::read_to_string(&deleted).unwrap(), "old\n");
+    }
+
+    #[test]
+    fn test_undo_restores_bytes() {
+        let dir = TempDir::new().unwrap();
+        let journal = Journal::new(dir.path());
+        let crlf = dir.path().join("crlf.rs");
+        let mixed = dir.path().join("mixed.rs");
+
+        let before_crlf = "\u{feff}fn a() {}\r\nfn b() {}\r\n";
+        let before_mixed = "a\r\nb\nc\r\nd";
+        let files = [
+            applied(crlf.clone(), before_crlf, "fn a() {}\n"),
+            applied(mixed.clone(), before_mixed, "a\n"),
+        ];
+        journal.record("edit patch", &files).unwrap();
+        journal.undo(1, false).unwrap();
+
+        assert_eq!(fs::read(&crlf).unwrap(), before_crlf.as_bytes());
+        assert_eq!(fs::read(&mixed).unwrap(), before_mixed.as_bytes());
+    }
+
+    #[test]
+    fn test_undo_conflict() {
+        let dir = TempDir::new().unwrap();
+        let journal = Journal::new(dir.path());
+        let a = dir.path().join("a.rs");
+
+        journal
+            .record("edit a", &[applied(a.clone(), "old\n", "new\n")])
+            .unwrap();
+        fs::write(&a, "changed by hand\n").unwrap();
+
+        assert!(matches!(
+            journal.undo(1, false),
+            Err(EditError::UndoConflict { .. })
+        ));
+        assert_eq!(journal.entries().unwrap().len(), 1);
+
+        journal.undo(1, true).unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "old\n");
+    }
+}
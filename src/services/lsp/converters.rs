@@ -2,12 +2,17 @@
 
 use std::path::Path;
 
+use crate::error::LspError;
 use crate::infra::lsp::protocol::{
-    DocumentSymbol, HoverContents, LspLocation, LspSymbolKind, Range,
+    DocumentSymbol, HoverContents, LspDiagnostic, LspDiagnosticSeverity, LspDiagnosticTag,
+    LspLocation, LspSymbolKind, Range,
+};
+use crate::models::diagnostic::{
+    Diagnostic, DiagnosticRelatedInfo, DiagnosticSeverity, DiagnosticTag,
 };
 use crate::models::lsp::{
-    FileChangeWithEdits, FindSymbolsOptions, ParameterInfo, SignatureHelp, SignatureInfo,
-    uri_to_path,
+    CodeAction, CodeActionKind, FileChangeWithEdits, FindSymbolsOptions, ParameterInfo,
+    SignatureHelp, SignatureInfo, WorkspaceEdit, uri_to_path,
 };
 use crate::models::symbol::{Location, Symbol, SymbolKind};
 
@@ -62,6 +67,55 @@ pub(super) fn convert_location(loc: &LspLocation) -> Location {
     }
 }
 
+pub(super) fn convert_diagnostic(d: LspDiagnostic, file: &Path) -> Diagnostic {
+    use crate::models::lsp::{Position as LspPosition, Range as LspRange};
+
+    let severity = match d.severity {
+        Some(LspDiagnosticSeverity::Error) | None => DiagnosticSeverity::Error,
+        Some(LspDiagnosticSeverity::Warning) => DiagnosticSeverity::Warning,
+        Some(LspDiagnosticSeverity::Information) => DiagnosticSeverity::Information,
+        Some(LspDiagnosticSeverity::Hint) => DiagnosticSeverity::Hint,
+    };
+
+    let tags = d
+        .tags
+        .iter()
+        .map(|t| match t {
+            LspDiagnosticTag::Unnecessary => DiagnosticTag::Unnecessary,
+            LspDiagnosticTag::Deprecated => DiagnosticTag::Deprecated,
+        })
+        .collect();
+
+    let related_information = d
+        .related_information
+        .into_iter()
+        .map(|r| DiagnosticRelatedInfo {
+            location: convert_location(&r.location),
+            message: r.message,
+        })
+        .collect();
+
+    Diagnostic {
+        file_path: file.display().to_string(),
+        range: LspRange {
+            start: LspPosition {
+                line: d.range.start.line,
+                character: d.range.start.character,
+            },
+            end: LspPosition {
+                line: d.range.end.line,
+                character: d.range.end.character,
+            },
+        },
+        severity,
+        message: d.message,
+        code: d.code.map(|c| c.to_string()),
+        source: d.source,
+        tags,
+        related_information,
+    }
+}
+
 pub(super) fn range_to_location(file: &Path, range: &Range) -> Location {
     Location {
         file: file.to_path_buf(),
@@ -179,77 +233,43 @@ pub(super) fn extract_body_from_range(content: &str, range: &Range) -> Option<St
     Some(lines[start_line..=end_line].join("\n"))
 }
 
-pub(super) fn parse_workspace_edit(edit: &serde_json::Value) -> Vec<FileChangeWithEdits> {
-    let mut changes = Vec::new();
-
-    if let Some(file_changes) = edit.get("changes").and_then(|c| c.as_object()) {
-        for (uri, edits) in file_changes {
-            let file = uri_to_path(uri);
-            let text_edits = parse_text_edits(edits);
-            if !text_edits.is_empty() {
-                changes.push(FileChangeWithEdits {
-                    file,
-                    edits: text_edits,
-                });
-            }
-        }
-    }
-
-    if let Some(doc_changes) = edit.get("documentChanges").and_then(|c| c.as_array()) {
-        for change in doc_changes {
-            if let Some(text_doc) = change.get("textDocument") {
-                let uri = text_doc.get("uri").and_then(|u| u.as_str()).unwrap_or("");
-                let file = uri_to_path(uri);
-
-                if let Some(edits) = change.get("edits") {
-                    let text_edits = parse_text_edits(edits);
-                    if !text_edits.is_empty() {
-                        changes.push(FileChangeWithEdits {
-                            file,
-                            edits: text_edits,
-                        });
-                    }
-                }
-            }
-        }
-    }
-
-    changes
+/// Parse a WorkspaceEdit; any part that cannot be parsed rejects all of it
+pub(super) fn parse_workspace_edit(
+    edit: &serde_json::Value,
+) -> Result<Vec<FileChangeWithEdits>, LspError> {
+    serde_json::from_value::<WorkspaceEdit>(edit.clone())
+        .map_err(|e| e.to_string())
+        .and_then(|e| e.to_file_edits())
+        .map_err(|e| LspError::Protocol(format!("Invalid workspace edit: {}", e)))
 }
 
-pub(super) fn parse_text_edits(edits: &serde_json::Value) -> Vec<crate::models::lsp::TextEdit> {
-    use crate::models::lsp::{Position as LspPos, Range as LspRange, TextEdit as LspTextEdit};
-
-    let arr = match edits.as_array() {
-        Some(a) => a,
-        None => return Vec::new(),
-    };
-
-    arr.iter()
-        .filter_map(|edit| {
-            let range = edit.get("range")?;
-            let start = range.get("start")?;
-            let end = range.get("end")?;
-
-            Some(LspTextEdit {
-                range: LspRange {
-                    start: LspPos {
-                        line: start.get("line")?.as_u64()? as u32,
-                        character: start.get("character")?.as_u64()? as u32,
-                    },
-                    end: LspPos {
-                        line: end.get("line")?.as_u64()? as u32,
-                        character: end.get("character")?.as_u64()? as u32,
-                    },
-                },
-                new_text: edit
-                    .get("newText")
-                    .and_then(|t| t.as_str())
-                    .unwrap_or("")
-                    .to_string(),
-            })
+/// Parse a CodeAction or Command, keeping the raw value for resolve and apply
+pub(super) fn parse_code_action(v: serde_json::Value) -> Option<CodeAction> {
+    let title = v.get("title")?.as_str()?.to_string();
+    let kind = v.get("kind").and_then(|k| k.as_str());
+    let is_preferred = v
+        .get("isPreferred")
+        .and_then(|p| p.as_bool())
+        .unwrap_or(false);
+    let diagnostics = v
+        .get("diagnostics")
+        .and_then(|d| d.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|d| d.get("message").and_then(|m| m.as_str()))
+                .map(|s| s.to_string())
+                .collect()
         })
-        .collect()
+        .unwrap_or_default();
+
+    Some(CodeAction {
+        title,
+        kind: CodeActionKind::from(kind),
+        is_preferred,
+        diagnostics,
+        edit: None,
+        data: Some(v),
+    })
 }
 
 pub(super) fn parse_signature_help(value: &serde_json::Value) -> Option<SignatureHelp> {
@@ -10,6 +10,7 @@ use crate::infra::lsp::{
 };
 use crate::models::lsp::{TypeHierarchyItem, uri_to_path};
 use crate::models::symbol::{Language, Location, Symbol, SymbolKind};
+use crate::services::text_buffer::strip_bom;
 
 /// Read file content with validation in a single pass
 ///
@@ -45,8 +46,14 @@ pub(super) async fn read_file_validated(file: &Path) -> Result<String, LspError>
     }
 
     // Convert to string (validates UTF-8)
-    String::from_utf8(bytes)
-        .map_err(|_| LspError::Protocol(format!("Cannot process binary file: {}", file.display())))
+    let mut content = String::from_utf8(bytes).map_err(|_| {
+        LspError::Protocol(format!("Cannot process binary file: {}", file.display()))
+    })?;
+
+    // Positions address the text after a byte order mark, as edits do
+    let bom = content.len() - strip_bom(&content).len();
+    content.drain(..bom);
+    Ok(content)
 }
 
 pub(super) async fn read_line_streaming(file: &Path, target_line: u32) -> Option<String> {
@@ -235,41 +242,19 @@ pub(super) fn filter_locations_within_project(
         .collect()
 }
 
-/// Select the best definition from multiple locations.
-/// For TypeScript/JavaScript: prefer source files over node_modules and .d.ts files.
-pub(super) fn select_best_definition(
-    locations: &[LspLocation],
+/// Order definition candidates best first.
+/// For TypeScript/JavaScript: prefer source files over node_modules and .d.ts files;
+/// other languages keep the server's order.
+pub(super) fn rank_definitions(
+    mut locations: Vec<LspLocation>,
     language: Language,
-) -> Option<&LspLocation> {
-    if locations.is_empty() {
-        return None;
-    }
-
-    // Only apply filtering for TypeScript/JavaScript
-    if !matches!(language, Language::TypeScript | Language::JavaScript) {
-        return locations.first();
-    }
-
-    // Priority 1: Source files outside node_modules (not .d.ts)
-    if let Some(loc) = locations.iter().find(|l| {
-        let uri = &l.uri;
-        !uri.contains("node_modules") && !uri.ends_with(".d.ts")
-    }) {
-        return Some(loc);
-    }
-
-    // Priority 2: Any file outside node_modules
-    if let Some(loc) = locations.iter().find(|l| !l.uri.contains("node_modules")) {
-        return Some(loc);
+) -> Vec<LspLocation> {
+    if matches!(language, Language::TypeScript | Language::JavaScript) {
+        // Stable: source, then .d.ts outside node_modules, then node_modules
+        // sources, then node_modules .d.ts
+        locations.sort_by_key(|l| (l.uri.contains("node_modules"), l.uri.ends_with(".d.ts")));
     }
-
-    // Priority 3: Non-.d.ts file in node_modules (actual source)
-    if let Some(loc) = locations.iter().find(|l| !l.uri.ends_with(".d.ts")) {
-        return Some(loc);
-    }
-
-    // Fallback: first result
-    locations.first()
+    locations
 }
 
 #[cfg(test)]
@@ -282,4 +267,33 @@ mod tests {
         assert_eq!(pos.line, 9);
         assert_eq!(pos.character, 4);
     }
+
+    #[test]
+    fn test_rank_definitions() {
+        let location = |uri: &str| LspLocation {
+            uri: uri.to_string(),
+            range: Default::default(),
+        };
+        let uris = |locs: Vec<LspLocation>| locs.into_iter().map(|l| l.uri).collect::<Vec<_>>();
+        let locations = vec![
+            location("file:///p/node_modules/lib/index.d.ts"),
+            location("file:///p/node_modules/lib/index.js"),
+            location("file:///p/types/api.d.ts"),
+            location("file:///p/src/api.ts"),
+        ];
+
+        assert_eq!(
+            uris(rank_definitions(locations.clone(), Language::TypeScript)),
+            vec![
+                "file:///p/src/api.ts",
+                "file:///p/types/api.d.ts",
+                "file:///p/node_modules/lib/index.js",
+                "file:///p/node_modules/lib/index.d.ts",
+            ]
+        );
+        assert_eq!(
+            uris(rank_definitions(locations.clone(), Language::Rust)),
+            uris(locations)
+        );
+    }
 }
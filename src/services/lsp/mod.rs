@@ -3,21 +3,23 @@
 mod cache;
 mod converters;
 mod helpers;
+mod positions;
 mod service;
 
 pub use cache::{SymbolCache, WorkspaceSymbolCache};
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use async_trait::async_trait;
 
 use crate::error::LspError;
 use crate::infra::lsp::ServerStatus as InfraServerStatus;
-use crate::models::diagnostic::Diagnostic;
+use crate::models::diagnostic::{Diagnostic, DiagnosticsReport};
 use crate::models::lsp::{
-    ApplyActionResult, CallHierarchyItem, CodeAction, CodeLens, FindSymbolsOptions, FoldingRange,
-    HoverInfo, InlayHint, PrepareRenameResult, Range, RenameResult, SelectionRange, ServerStatus,
-    SignatureHelp, TypeHierarchyItem,
+    ApplyActionResult, CallHierarchyItem, CodeAction, CodeActionKind, CodeLens,
+    DefinitionCandidate, DocumentHighlight, FindSymbolsOptions, FoldingRange, FormattingOptions,
+    HoverInfo, InlayHint, PrepareRenameResult, Range, RenameResult, SelectionRange, SemanticToken,
+    ServerStatus, SignatureHelp, TextEdit, TypeHierarchyItem,
 };
 use crate::models::symbol::{Language, Location, Symbol};
 
@@ -51,6 +53,21 @@ pub trait LspService: Send + Sync {
         column: u32,
     ) -> Result<Option<Location>, LspError>;
 
+    /// Every definition candidate, best first, with the reason for its rank
+    async fn definitions(
+        &self,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<DefinitionCandidate>, LspError>;
+
+    async fn goto_declaration(
+        &self,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<Location>, LspError>;
+
     async fn goto_type_definition(
         &self,
         file: &Path,
@@ -65,6 +82,14 @@ pub trait LspService: Send + Sync {
         column: u32,
     ) -> Result<Vec<Location>, LspError>;
 
+    /// Occurrences of the symbol at a position within its own file
+    async fn document_highlights(
+        &self,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<DocumentHighlight>, LspError>;
+
     async fn hover(
         &self,
         file: &Path,
@@ -79,7 +104,23 @@ pub trait LspService: Send + Sync {
         column: u32,
     ) -> Result<Option<SignatureHelp>, LspError>;
 
-    async fn diagnostics(&self, file: &Path) -> Result<Vec<Diagnostic>, LspError>;
+    /// Diagnostics for one file, pulled with `textDocument/diagnostic` when
+    /// supported and otherwise awaited for the synced document version
+    async fn diagnostics(&self, file: &Path) -> Result<DiagnosticsReport, LspError>;
+
+    /// Diagnostics as if each file had the given content, without writing it.
+    ///
+    /// All documents are synced before any report is collected, so
+    /// cross-file diagnostics see the whole set. Reports follow `documents`.
+    async fn content_diagnostics(
+        &self,
+        documents: &[(PathBuf, String)],
+    ) -> Result<Vec<DiagnosticsReport>, LspError>;
+
+    /// Diagnostics for files served by one language server, pulled with
+    /// `workspace/diagnostic` when supported and otherwise collected from
+    /// diagnostics published after opening each file
+    async fn workspace_diagnostics(&self, files: &[PathBuf]) -> Result<Vec<Diagnostic>, LspError>;
 
     async fn prepare_rename(
         &self,
@@ -96,6 +137,14 @@ pub trait LspService: Send + Sync {
         new_name: &str,
     ) -> Result<RenameResult, LspError>;
 
+    /// The call hierarchy item at a position, located at its selection range
+    async fn prepare_call_hierarchy(
+        &self,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Option<CallHierarchyItem>, LspError>;
+
     async fn incoming_calls(
         &self,
         file: &Path,
@@ -128,6 +177,7 @@ pub trait LspService: Send + Sync {
 
     async fn folding_ranges(&self, file: &Path) -> Result<Vec<FoldingRange>, LspError>;
 
+    /// Selection ranges at 1-indexed `(line, column)` positions
     async fn selection_ranges(
         &self,
         file: &Path,
@@ -136,6 +186,14 @@ pub trait LspService: Send + Sync {
 
     async fn code_lens(&self, file: &Path) -> Result<Vec<CodeLens>, LspError>;
 
+    /// Semantic tokens decoded with the server's legend, limited to `range`
+    /// when given
+    async fn semantic_tokens(
+        &self,
+        file: &Path,
+        range: Option<Range>,
+    ) -> Result<Vec<SemanticToken>, LspError>;
+
     async fn code_actions(
         &self,
         file: &Path,
@@ -143,12 +201,33 @@ pub trait LspService: Send + Sync {
         column: u32,
     ) -> Result<Vec<CodeAction>, LspError>;
 
+    /// Whole-document code actions of the given kinds (organize imports, fix all)
+    async fn source_actions(
+        &self,
+        file: &Path,
+        kinds: &[CodeActionKind],
+    ) -> Result<Vec<CodeAction>, LspError>;
+
+    /// Resolve a code action into file changes.
+    ///
+    /// The action's edit comes first. With `run_command`, its command then
+    /// runs against the edited text and the edits it requests follow in
+    /// `changes`; otherwise the command is only reported.
     async fn apply_code_action(
         &self,
         file: &Path,
         action: &CodeAction,
+        run_command: bool,
     ) -> Result<ApplyActionResult, LspError>;
 
+    /// Format a document, or only `range` when given
+    async fn format(
+        &self,
+        file: &Path,
+        range: Option<Range>,
+        options: &FormattingOptions,
+    ) -> Result<Vec<TextEdit>, LspError>;
+
     async fn is_available(&self, language: Language) -> bool;
 
     async fn server_status(&self, language: Language) -> ServerStatus;
@@ -0,0 +1,166 @@
+//! Column conversion between Symora and language servers
+//!
+//! Columns in Symora's models count characters. Servers count
+//! `Position.character` in the encoding negotiated at initialization
+//! (UTF-16 unless stated otherwise), so every position crossing the service
+//! boundary is converted here using the text of the line it points into.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::infra::lsp::LspClient;
+use crate::models::diagnostic::Diagnostic;
+use crate::models::lsp::{FileChangeWithEdits, Position, PositionEncoding, Range};
+use crate::models::symbol::{Location, Symbol};
+use crate::services::text_buffer::strip_bom;
+
+/// Encoding of Symora's own columns: one unit per character
+const CHARS: PositionEncoding = PositionEncoding::Utf32;
+
+/// Converts columns for one request, reading each file's lines at most once
+pub(super) struct Columns {
+    encoding: PositionEncoding,
+    lines: HashMap<PathBuf, Vec<String>>,
+}
+
+impl Columns {
+    pub(super) fn new(encoding: PositionEncoding) -> Self {
+        Self {
+            encoding,
+            lines: HashMap::new(),
+        }
+    }
+
+    pub(super) async fn of(client: &LspClient) -> Self {
+        Self::new(client.position_encoding().await)
+    }
+
+    /// Use `content` as the text of `file` instead of reading it from disk
+    pub(super) fn with_content(mut self, file: &Path, content: &str) -> Self {
+        if self.encoding != CHARS {
+            self.lines.insert(
+                file.to_path_buf(),
+                content.lines().map(String::from).collect(),
+            );
+        }
+        self
+    }
+
+    fn convert(
+        &mut self,
+        file: &Path,
+        line: u32,
+        offset: u32,
+        from: PositionEncoding,
+        to: PositionEncoding,
+    ) -> u32 {
+        if from == to {
+            return offset;
+        }
+        if !self.lines.contains_key(file) {
+            let lines = std::fs::read_to_string(file)
+                .map(|c| strip_bom(&c).lines().map(String::from).collect())
+                .unwrap_or_default();
+            self.lines.insert(file.to_path_buf(), lines);
+        }
+        match self.lines[file].get(line as usize) {
+            Some(text) => from.convert(text, offset, to),
+            None => offset,
+        }
+    }
+
+    /// Server position of a 1-indexed line and character column
+    pub(super) fn cli_position(&mut self, file: &Path, line: u32, column: u32) -> Position {
+        self.server_position(file, super::helpers::to_lsp_position(line, column))
+    }
+
+    pub(super) fn server_position(&mut self, file: &Path, pos: Position) -> Position {
+        let character = self.convert(file, pos.line, pos.character, CHARS, self.encoding);
+        Position::new(pos.line, character)
+    }
+
+    pub(super) fn char_position(&mut self, file: &Path, pos: Position) -> Position {
+        let character = self.convert(file, pos.line, pos.character, self.encoding, CHARS);
+        Position::new(pos.line, character)
+    }
+
+    pub(super) fn server_range(&mut self, file: &Path, range: &Range) -> Range {
+        Range::new(
+            self.server_position(file, range.start),
+            self.server_position(file, range.end),
+        )
+    }
+
+    pub(super) fn char_range(&mut self, file: &Path, range: &Range) -> Range {
+        Range::new(
+            self.char_position(file, range.start),
+            self.char_position(file, range.end),
+        )
+    }
+
+    /// Location (1-indexed) whose columns were counted by the server
+    pub(super) fn location(&mut self, mut location: Location) -> Location {
+        let file = location.file.clone();
+        let mut column = |line: u32, column: u32| {
+            self.convert(
+                &file,
+                line.saturating_sub(1),
+                column.saturating_sub(1),
+                self.encoding,
+                CHARS,
+            ) + 1
+        };
+        location.column = column(location.line, location.column);
+        if let (Some(line), Some(end)) = (location.end_line, location.end_column) {
+            location.end_column = Some(column(line, end));
+        }
+        location
+    }
+
+    pub(super) fn symbols(&mut self, symbols: &mut [Symbol]) {
+        for symbol in symbols {
+            symbol.location = self.location(symbol.location.clone());
+            self.symbols(&mut symbol.children);
+        }
+    }
+
+    pub(super) fn diagnostic(&mut self, file: &Path, mut diagnostic: Diagnostic) -> Diagnostic {
+        diagnostic.range = self.char_range(file, &diagnostic.range);
+        for related in &mut diagnostic.related_information {
+            related.location = self.location(related.location.clone());
+        }
+        diagnostic
+    }
+
+    pub(super) fn file_edits(&mut self, changes: &mut [FileChangeWithEdits]) {
+        for change in changes {
+            for edit in &mut change.edits {
+                edit.range = self.char_range(&change.file, &edit.range);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns_round_trip() {
+        let file = Path::new("/virtual/lib.rs");
+        let mut columns = Columns::new(PositionEncoding::Utf16)
+            .with_content(file, "// 주석\nlet s = \"🦀\"; let x = 1;\n");
+
+        // `x` is character 17 but UTF-16 offset 18 after the surrogate pair
+        let pos = columns.cli_position(file, 2, 18);
+        assert_eq!(pos, Position::new(1, 18));
+        assert_eq!(columns.char_position(file, pos), Position::new(1, 17));
+
+        let location = columns.location(Location::new(file.to_path_buf(), 2, 19, 2, 20));
+        assert_eq!((location.column, location.end_column), (18, Some(19)));
+
+        // Columns before any wide character and lines past the end are unchanged
+        assert_eq!(columns.cli_position(file, 1, 3), Position::new(0, 2));
+        assert_eq!(columns.cli_position(file, 9, 4), Position::new(8, 3));
+    }
+}
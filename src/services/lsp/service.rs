@@ -1,6 +1,7 @@
 //! DefaultLspService implementation
 
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
@@ -10,21 +11,29 @@ use super::LspService;
 use super::cache::{SymbolCache, WorkspaceSymbolCache};
 use super::converters::*;
 use super::helpers::*;
+use super::positions::Columns;
 use crate::error::LspError;
 use crate::infra::lsp::protocol::{
     CallHierarchyIncomingCall, CallHierarchyOutgoingCall, DocumentSymbol, Hover,
-    LspCallHierarchyItem, LspDiagnosticSeverity, LspLocation, Position, SymbolInformation,
-    TextDocumentIdentifier, TextDocumentPositionParams, WorkspaceEdit,
+    LspCallHierarchyItem, LspDiagnostic, LspDocumentHighlight, LspLocation, Position,
+    SemanticTokens, SymbolInformation, TextDocumentIdentifier, TextDocumentPositionParams,
 };
-use crate::infra::lsp::{HealthMonitor, LspClient, LspFeature, LspManager};
-use crate::models::diagnostic::{Diagnostic, DiagnosticSeverity};
+use crate::infra::lsp::{HealthMonitor, LspClient, LspFeature, LspManager, language_server_name};
+use crate::models::diagnostic::{Diagnostic, DiagnosticsReport};
 use crate::models::lsp::{
     ApplyActionResult, CallHierarchyItem, CodeAction, CodeActionKind, CodeLens, CodeLensCommand,
-    FileChange, FindSymbolsOptions, FoldingRange, FoldingRangeKind, HoverInfo, InlayHint,
-    InlayHintKind, PrepareRenameResult, Range, RenameResult, SelectionRange, ServerStatus,
-    SignatureHelp, TypeHierarchyItem, path_to_uri, uri_to_path,
+    DefinitionCandidate, DefinitionReason, DocumentHighlight, DocumentHighlightKind,
+    FileChangeWithEdits, FindSymbolsOptions, FoldingRange, FoldingRangeKind, FormattingOptions,
+    HoverInfo, InlayHint, InlayHintKind, PositionEncoding, PrepareRenameResult, Range,
+    RenameResult, SelectionRange, SemanticToken, ServerStatus, SignatureHelp, TextEdit,
+    TypeHierarchyItem, path_to_uri, uri_to_path,
 };
 use crate::models::symbol::{Language, Location, Symbol};
+use crate::services::text_buffer::strip_bom;
+use crate::services::workspace_edit::prepare_workspace_edit;
+
+/// Documents opened at a time when collecting published diagnostics
+const WORKSPACE_DIAGNOSTICS_BATCH: usize = 50;
 
 pub struct DefaultLspService {
     manager: Arc<LspManager>,
@@ -140,22 +149,177 @@ impl DefaultLspService {
         Ok((client, uri))
     }
 
+    /// `textDocument/definition` locations, best first
+    async fn definition_locations(
+        &self,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<Location>, LspError> {
+        let (client, uri) = self.prepare_for_cross_file_request(file).await?;
+        let mut columns = Columns::of(&client).await;
+
+        let params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier::new(&uri),
+            position: columns.cli_position(file, line, column),
+        };
+
+        let result: serde_json::Value = client
+            .request(
+                "textDocument/definition",
+                Some(serde_json::to_value(params)?),
+            )
+            .await?;
+
+        let locations = parse_location_response(&result).unwrap_or_default();
+        Ok(rank_definitions(locations, Language::from_path(file))
+            .iter()
+            .map(|l| columns.location(convert_location(l)))
+            .collect())
+    }
+
+    /// Diagnostics published for the current document version, or the last
+    /// published set flagged stale if none arrive in time
     async fn wait_for_diagnostics(
         client: &LspClient,
         uri: &str,
-    ) -> Vec<crate::infra::lsp::protocol::LspDiagnostic> {
+    ) -> (Vec<crate::infra::lsp::protocol::LspDiagnostic>, bool) {
         const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(20);
-        const MAX_WAIT: std::time::Duration = std::time::Duration::from_millis(200);
+        const MAX_WAIT: std::time::Duration = std::time::Duration::from_secs(3);
 
         let start = std::time::Instant::now();
         while start.elapsed() < MAX_WAIT {
-            let diags = client.get_diagnostics(uri).await;
-            if !diags.is_empty() {
-                return diags;
+            if let Some(diags) = client.fresh_diagnostics(uri).await {
+                return (diags, false);
             }
             tokio::time::sleep(POLL_INTERVAL).await;
         }
-        client.get_diagnostics(uri).await
+        (client.get_diagnostics(uri).await, true)
+    }
+
+    /// Diagnostics for a synced document, pulled when the server supports it
+    async fn document_diagnostics(
+        client: &LspClient,
+        uri: &str,
+        file: &Path,
+        mut columns: Columns,
+    ) -> DiagnosticsReport {
+        let version = client.document_version(uri).await;
+        let mut convert = |diagnostics: Vec<LspDiagnostic>| {
+            diagnostics
+                .into_iter()
+                .map(|d| columns.diagnostic(file, convert_diagnostic(d, file)))
+                .collect()
+        };
+
+        if client.supports_pull_diagnostics().await {
+            match Self::pull_diagnostics(client, uri).await {
+                Ok(diagnostics) => {
+                    return DiagnosticsReport {
+                        diagnostics: convert(diagnostics),
+                        version,
+                        stale: false,
+                    };
+                }
+                Err(e) => tracing::debug!("Pull diagnostics failed for {}: {}", uri, e),
+            }
+        }
+
+        let (diagnostics, stale) = Self::wait_for_diagnostics(client, uri).await;
+        DiagnosticsReport {
+            diagnostics: convert(diagnostics),
+            version,
+            stale,
+        }
+    }
+
+    /// `textDocument/diagnostic` for one document
+    async fn pull_diagnostics(
+        client: &LspClient,
+        uri: &str,
+    ) -> Result<Vec<LspDiagnostic>, LspError> {
+        let report: serde_json::Value = client
+            .request(
+                "textDocument/diagnostic",
+                Some(serde_json::json!({ "textDocument": { "uri": uri } })),
+            )
+            .await?;
+        if report["kind"] != "full" {
+            return Err(LspError::Protocol(format!(
+                "Unexpected diagnostic report kind: {}",
+                report["kind"]
+            )));
+        }
+        serde_json::from_value(report["items"].clone())
+            .map_err(|e| LspError::Protocol(e.to_string()))
+    }
+
+    /// Wait until every document has diagnostics for its current version, or
+    /// until the server has been quiet for a while
+    async fn wait_for_published(client: &LspClient, uris: &[&str]) {
+        const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+        const QUIET_PERIOD: std::time::Duration = std::time::Duration::from_millis(500);
+        const MAX_WAIT: std::time::Duration = std::time::Duration::from_secs(10);
+
+        let start = std::time::Instant::now();
+        let mut generation = client.diagnostics_generation();
+        let mut last_change = start;
+        while start.elapsed() < MAX_WAIT && last_change.elapsed() < QUIET_PERIOD {
+            let mut all_published = true;
+            for uri in uris {
+                if client.fresh_diagnostics(uri).await.is_none() {
+                    all_published = false;
+                    break;
+                }
+            }
+            if all_published {
+                return;
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let current = client.diagnostics_generation();
+            if current != generation {
+                generation = current;
+                last_change = std::time::Instant::now();
+            }
+        }
+    }
+
+    /// `workspace/diagnostic` restricted to `files`; unreported files are clean
+    async fn pull_workspace_diagnostics(
+        client: &LspClient,
+        files: &[PathBuf],
+    ) -> Result<Vec<Diagnostic>, LspError> {
+        let result: serde_json::Value = client
+            .request(
+                "workspace/diagnostic",
+                Some(serde_json::json!({ "previousResultIds": [] })),
+            )
+            .await?;
+
+        let wanted: HashSet<&Path> = files.iter().map(PathBuf::as_path).collect();
+        let mut columns = Columns::of(client).await;
+        let mut diagnostics = Vec::new();
+        for report in result["items"].as_array().into_iter().flatten() {
+            if report["kind"] != "full" {
+                continue;
+            }
+            let Some(uri) = report["uri"].as_str() else {
+                continue;
+            };
+            let file = uri_to_path(uri);
+            if !wanted.contains(file.as_path()) {
+                continue;
+            }
+            let items: Vec<LspDiagnostic> =
+                serde_json::from_value(report["items"].clone()).unwrap_or_default();
+            diagnostics.extend(
+                items
+                    .into_iter()
+                    .map(|d| columns.diagnostic(&file, convert_diagnostic(d, &file))),
+            );
+        }
+        Ok(diagnostics)
     }
 
     fn filter_by_depth(symbols: Vec<Symbol>, max_depth: u32) -> Vec<Symbol> {
@@ -210,6 +374,9 @@ impl LspService for DefaultLspService {
                     let client = client_fut.await?;
                     let uri = path_to_uri(&file_clone);
                     client.sync_document(&uri, &content_clone).await?;
+                    let mut columns = Columns::of(&client)
+                        .await
+                        .with_content(&file_clone, &content_clone);
 
                     let params = serde_json::json!({
                         "textDocument": { "uri": uri }
@@ -221,14 +388,16 @@ impl LspService for DefaultLspService {
                         .await;
 
                     if let Ok(doc_symbols) = result {
-                        return Ok(convert_document_symbols(
+                        let mut symbols = convert_document_symbols(
                             &doc_symbols,
                             &file_clone,
                             &base_options,
                             None,
                             None,
                             0,
-                        ));
+                        );
+                        columns.symbols(&mut symbols);
+                        return Ok(symbols);
                     }
 
                     // Fallback to flat symbols
@@ -242,7 +411,7 @@ impl LspService for DefaultLspService {
                             let mut sym = Symbol::new(
                                 s.name,
                                 convert_symbol_kind(s.kind),
-                                convert_location(&s.location),
+                                columns.location(convert_location(&s.location)),
                             );
                             if let Some(container) = s.container_name
                                 && !container.is_empty()
@@ -323,12 +492,13 @@ impl LspService for DefaultLspService {
                     );
 
                     let mut seen = std::collections::HashSet::new();
+                    let mut columns = Columns::of(&client).await;
 
                     Ok(symbols
                         .unwrap_or_default()
                         .into_iter()
                         .filter_map(|s| {
-                            let location = convert_location(&s.location);
+                            let location = columns.location(convert_location(&s.location));
                             let key = (
                                 s.name.clone(),
                                 s.kind as u32,
@@ -394,10 +564,11 @@ impl LspService for DefaultLspService {
                 let content = read_file_validated(&file).await?;
                 let uri = path_to_uri(&file);
                 client.sync_document(&uri, &content).await?;
+                let mut columns = Columns::of(&client).await.with_content(&file, &content);
 
                 let params = serde_json::json!({
                     "textDocument": { "uri": uri },
-                    "position": to_lsp_position(line, column),
+                    "position": columns.cli_position(&file, line, column),
                     "context": { "includeDeclaration": true }
                 });
 
@@ -412,7 +583,10 @@ impl LspService for DefaultLspService {
                 let locations: Vec<LspLocation> = serde_json::from_value(result)
                     .map_err(|e| LspError::Protocol(e.to_string()))?;
 
-                let all_locations: Vec<Location> = locations.iter().map(convert_location).collect();
+                let all_locations: Vec<Location> = locations
+                    .iter()
+                    .map(|l| columns.location(convert_location(l)))
+                    .collect();
 
                 Ok(filter_locations_within_project(
                     all_locations,
@@ -429,23 +603,56 @@ impl LspService for DefaultLspService {
         line: u32,
         column: u32,
     ) -> Result<Option<Location>, LspError> {
+        let locations = self.definition_locations(file, line, column).await?;
+        Ok(locations.into_iter().next())
+    }
+
+    async fn definitions(
+        &self,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<DefinitionCandidate>, LspError> {
+        let locations = self.definition_locations(file, line, column).await?;
+        let root = self.manager.root();
+        Ok(locations
+            .into_iter()
+            .map(|location| {
+                let reason = DefinitionReason::classify(&location.file, root);
+                DefinitionCandidate { location, reason }
+            })
+            .collect())
+    }
+
+    async fn goto_declaration(
+        &self,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<Location>, LspError> {
         let (client, uri) = self.prepare_for_cross_file_request(file).await?;
-        let language = Language::from_path(file);
+
+        let mut columns = Columns::of(&client).await;
 
         let params = TextDocumentPositionParams {
             text_document: TextDocumentIdentifier::new(&uri),
-            position: to_lsp_position(line, column),
+            position: columns.cli_position(file, line, column),
         };
 
         let result: serde_json::Value = client
             .request(
-                "textDocument/definition",
+                "textDocument/declaration",
                 Some(serde_json::to_value(params)?),
             )
             .await?;
 
         Ok(parse_location_response(&result)
-            .and_then(|locs| select_best_definition(&locs, language).map(convert_location)))
+            .map(|locs| {
+                locs.iter()
+                    .map(|l| columns.location(convert_location(l)))
+                    .collect()
+            })
+            .unwrap_or_default())
     }
 
     async fn goto_type_definition(
@@ -458,9 +665,11 @@ impl LspService for DefaultLspService {
 
         let (client, uri) = self.prepare_for_cross_file_request(file).await?;
 
+        let mut columns = Columns::of(&client).await;
+
         let params = TextDocumentPositionParams {
             text_document: TextDocumentIdentifier::new(&uri),
-            position: to_lsp_position(line, column),
+            position: columns.cli_position(file, line, column),
         };
 
         let result: serde_json::Value = client
@@ -470,7 +679,8 @@ impl LspService for DefaultLspService {
             )
             .await?;
 
-        Ok(parse_location_response(&result).and_then(|locs| locs.first().map(convert_location)))
+        Ok(parse_location_response(&result)
+            .and_then(|locs| locs.first().map(|l| columns.location(convert_location(l)))))
     }
 
     async fn find_implementations(
@@ -483,9 +693,11 @@ impl LspService for DefaultLspService {
 
         let (client, uri) = self.prepare_for_cross_file_request(file).await?;
 
+        let mut columns = Columns::of(&client).await;
+
         let params = TextDocumentPositionParams {
             text_document: TextDocumentIdentifier::new(&uri),
-            position: to_lsp_position(line, column),
+            position: columns.cli_position(file, line, column),
         };
 
         let result: serde_json::Value = client
@@ -496,10 +708,46 @@ impl LspService for DefaultLspService {
             .await?;
 
         Ok(parse_location_response(&result)
-            .map(|locs| locs.iter().map(convert_location).collect())
+            .map(|locs| {
+                locs.iter()
+                    .map(|l| columns.location(convert_location(l)))
+                    .collect()
+            })
             .unwrap_or_default())
     }
 
+    async fn document_highlights(
+        &self,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<DocumentHighlight>, LspError> {
+        let (client, uri) = self.prepare_for_request(file).await?;
+
+        let mut columns = Columns::of(&client).await;
+
+        let params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier::new(&uri),
+            position: columns.cli_position(file, line, column),
+        };
+
+        let highlights: Option<Vec<LspDocumentHighlight>> = client
+            .request(
+                "textDocument/documentHighlight",
+                Some(serde_json::to_value(params)?),
+            )
+            .await?;
+
+        Ok(highlights
+            .unwrap_or_default()
+            .into_iter()
+            .map(|h| DocumentHighlight {
+                range: columns.char_range(file, &h.range),
+                kind: DocumentHighlightKind::from_lsp(h.kind),
+            })
+            .collect())
+    }
+
     async fn hover(
         &self,
         file: &Path,
@@ -535,10 +783,11 @@ impl LspService for DefaultLspService {
                 let content = read_file_validated(&file).await?;
                 let uri = path_to_uri(&file);
                 client.sync_document(&uri, &content).await?;
+                let mut columns = Columns::of(&client).await.with_content(&file, &content);
 
                 let params = TextDocumentPositionParams {
                     text_document: TextDocumentIdentifier::new(&uri),
-                    position: to_lsp_position(line, column),
+                    position: columns.cli_position(&file, line, column),
                 };
 
                 let result: Option<Hover> = client
@@ -547,7 +796,9 @@ impl LspService for DefaultLspService {
 
                 Ok(result.map(|h| {
                     let content = extract_hover_content(&h.contents);
-                    let range = h.range.map(|r| range_to_location(&file, &r));
+                    let range = h
+                        .range
+                        .map(|r| columns.location(range_to_location(&file, &r)));
                     HoverInfo { content, range }
                 }))
             }
@@ -563,9 +814,11 @@ impl LspService for DefaultLspService {
     ) -> Result<Option<SignatureHelp>, LspError> {
         let (client, uri) = self.prepare_for_request(file).await?;
 
+        let mut columns = Columns::of(&client).await;
+
         let params = TextDocumentPositionParams {
             text_document: TextDocumentIdentifier::new(&uri),
-            position: to_lsp_position(line, column),
+            position: columns.cli_position(file, line, column),
         };
 
         let result: Option<serde_json::Value> = client
@@ -578,69 +831,69 @@ impl LspService for DefaultLspService {
         Ok(result.and_then(|v| parse_signature_help(&v)))
     }
 
-    async fn diagnostics(&self, file: &Path) -> Result<Vec<Diagnostic>, LspError> {
-        use crate::infra::lsp::protocol::LspDiagnosticTag;
-        use crate::models::diagnostic::DiagnosticTag;
-
+    async fn diagnostics(&self, file: &Path) -> Result<DiagnosticsReport, LspError> {
         let client = self.get_client_for_file(file).await?;
         let uri = self.sync_document(&client, file).await?;
+        let columns = Columns::of(&client).await;
+        Ok(Self::document_diagnostics(&client, &uri, file, columns).await)
+    }
 
-        let lsp_diagnostics = Self::wait_for_diagnostics(&client, &uri).await;
+    async fn content_diagnostics(
+        &self,
+        documents: &[(PathBuf, String)],
+    ) -> Result<Vec<DiagnosticsReport>, LspError> {
+        let mut synced = Vec::with_capacity(documents.len());
+        for (file, content) in documents {
+            let client = self.get_client_for_file(file).await?;
+            let uri = path_to_uri(file);
+            let content = strip_bom(content);
+            client.sync_document(&uri, content).await?;
+            synced.push((client, uri, file, content));
+        }
 
-        let diagnostics = lsp_diagnostics
-            .into_iter()
-            .map(|d| {
-                use crate::models::lsp::{Position as LspPosition, Range as LspRange};
-
-                let severity = match d.severity {
-                    Some(s) => match s {
-                        LspDiagnosticSeverity::Error => DiagnosticSeverity::Error,
-                        LspDiagnosticSeverity::Warning => DiagnosticSeverity::Warning,
-                        LspDiagnosticSeverity::Information => DiagnosticSeverity::Information,
-                        LspDiagnosticSeverity::Hint => DiagnosticSeverity::Hint,
-                    },
-                    None => DiagnosticSeverity::Error,
-                };
+        let mut reports = Vec::with_capacity(synced.len());
+        for (client, uri, file, content) in &synced {
+            let columns = Columns::of(client).await.with_content(file, content);
+            reports.push(Self::document_diagnostics(client, uri, file, columns).await);
+        }
+        Ok(reports)
+    }
 
-                let tags: Vec<DiagnosticTag> = d
-                    .tags
-                    .iter()
-                    .map(|t| match t {
-                        LspDiagnosticTag::Unnecessary => DiagnosticTag::Unnecessary,
-                        LspDiagnosticTag::Deprecated => DiagnosticTag::Deprecated,
-                    })
-                    .collect();
+    async fn workspace_diagnostics(&self, files: &[PathBuf]) -> Result<Vec<Diagnostic>, LspError> {
+        let Some(first) = files.first() else {
+            return Ok(Vec::new());
+        };
+        let client = self.get_client_for_file(first).await?;
+        self.ensure_workspace_indexed(&client, first).await;
 
-                let related_information = d
-                    .related_information
-                    .into_iter()
-                    .map(|r| crate::models::diagnostic::DiagnosticRelatedInfo {
-                        location: convert_location(&r.location),
-                        message: r.message,
-                    })
-                    .collect();
+        if client.supports_workspace_diagnostics().await {
+            return Self::pull_workspace_diagnostics(&client, files).await;
+        }
 
-                Diagnostic {
-                    file_path: file.display().to_string(),
-                    range: LspRange {
-                        start: LspPosition {
-                            line: d.range.start.line,
-                            character: d.range.start.character,
-                        },
-                        end: LspPosition {
-                            line: d.range.end.line,
-                            character: d.range.end.character,
-                        },
-                    },
-                    severity,
-                    message: d.message,
-                    code: d.code.map(|c| c.to_string()),
-                    source: d.source,
-                    tags,
-                    related_information,
+        let mut columns = Columns::of(&client).await;
+        let mut diagnostics = Vec::new();
+        for batch in files.chunks(WORKSPACE_DIAGNOSTICS_BATCH) {
+            let mut opened = Vec::with_capacity(batch.len());
+            for file in batch {
+                match self.sync_document(&client, file).await {
+                    Ok(uri) => opened.push((file, uri)),
+                    Err(e) => tracing::debug!("Skipping {}: {}", file.display(), e),
                 }
-            })
-            .collect();
+            }
+
+            let uris: Vec<&str> = opened.iter().map(|(_, uri)| uri.as_str()).collect();
+            Self::wait_for_published(&client, &uris).await;
+
+            for (file, uri) in &opened {
+                diagnostics.extend(
+                    client
+                        .get_diagnostics(uri)
+                        .await
+                        .into_iter()
+                        .map(|d| columns.diagnostic(file, convert_diagnostic(d, file))),
+                );
+            }
+        }
 
         Ok(diagnostics)
     }
@@ -653,10 +906,11 @@ impl LspService for DefaultLspService {
     ) -> Result<Option<PrepareRenameResult>, LspError> {
         let client = self.get_client_for_file(file).await?;
         let uri = self.sync_document(&client, file).await?;
+        let mut columns = Columns::of(&client).await;
 
         let params = serde_json::json!({
             "textDocument": { "uri": uri },
-            "position": to_lsp_position(line, column)
+            "position": columns.cli_position(file, line, column)
         });
 
         // Use proper error handling - only certain errors indicate "not renameable"
@@ -688,7 +942,7 @@ impl LspService for DefaultLspService {
             if let (Some(start), Some(end)) = (start, end) {
                 return Ok(Some(PrepareRenameResult {
                     placeholder: placeholder.to_string(),
-                    range: crate::models::lsp::Range { start, end },
+                    range: columns.char_range(file, &Range::new(start, end)),
                 }));
             }
         }
@@ -698,28 +952,21 @@ impl LspService for DefaultLspService {
             let start_pos = extract_position(start);
             let end_pos = extract_position(end);
             if let (Some(start_pos), Some(end_pos)) = (start_pos, end_pos) {
+                let range = columns.char_range(file, &Range::new(start_pos, end_pos));
+
                 // Extract placeholder from source file
                 let placeholder =
-                    read_line_streaming(file, start_pos.line)
+                    read_line_streaming(file, range.start.line)
                         .await
                         .and_then(|line| {
-                            let s = start_pos.character as usize;
-                            let e = end_pos.character as usize;
-                            if s < line.len() && e <= line.len() && s < e {
-                                Some(line[s..e].to_string())
-                            } else {
-                                None
-                            }
+                            let s =
+                                PositionEncoding::Utf32.byte_index(&line, range.start.character);
+                            let e = PositionEncoding::Utf32.byte_index(&line, range.end.character);
+                            (s < e).then(|| line[s..e].to_string())
                         });
 
                 if let Some(placeholder) = placeholder {
-                    return Ok(Some(PrepareRenameResult {
-                        placeholder,
-                        range: crate::models::lsp::Range {
-                            start: start_pos,
-                            end: end_pos,
-                        },
-                    }));
+                    return Ok(Some(PrepareRenameResult { placeholder, range }));
                 }
             }
         }
@@ -756,19 +1003,28 @@ impl LspService for DefaultLspService {
         let uri = path_to_uri(file);
         let new_name = new_name.to_string();
 
-        let result: serde_json::Value = self
+        let (result, mut changes, mut columns) = self
             .execute_with_retry(file, |client| {
                 let uri = uri.clone();
                 let content = content.clone();
                 let new_name = new_name.clone();
                 async move {
                     client.sync_document(&uri, &content).await?;
+                    let mut columns = Columns::of(&client).await.with_content(file, &content);
                     let params = serde_json::json!({
                         "textDocument": { "uri": uri },
-                        "position": to_lsp_position(line, column),
+                        "position": columns.cli_position(file, line, column),
                         "newName": new_name
                     });
-                    client.request("textDocument/rename", Some(params)).await
+                    let result: serde_json::Value =
+                        client.request("textDocument/rename", Some(params)).await?;
+                    let mut changes = if result.is_null() {
+                        Vec::new()
+                    } else {
+                        parse_workspace_edit(&result)?
+                    };
+                    stamp_synced_versions(&client, &mut changes).await;
+                    Ok((result, changes, columns))
                 }
             })
             .await?;
@@ -780,41 +1036,43 @@ impl LspService for DefaultLspService {
             ));
         }
 
-        let edit: WorkspaceEdit = serde_json::from_value(result)
-            .map_err(|e| LspError::Protocol(format!("Invalid rename response: {}", e)))?;
-
-        let changes = if let Some(changes_map) = edit.changes {
-            changes_map
-                .into_iter()
-                .map(|(uri, edits)| FileChange {
-                    file: uri_to_path(&uri),
-                    edit_count: edits.len(),
-                })
-                .collect()
-        } else if let Some(doc_changes) = edit.document_changes {
-            doc_changes
-                .as_array()
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|item| {
-                            let text_doc = item.get("textDocument")?;
-                            let uri = text_doc.get("uri")?.as_str()?;
-                            let edits = item.get("edits")?.as_array()?;
-                            Some(FileChange {
-                                file: uri_to_path(uri),
-                                edit_count: edits.len(),
-                            })
-                        })
-                        .collect()
-                })
-                .unwrap_or_default()
-        } else {
-            Vec::new()
-        };
+        columns.file_edits(&mut changes);
 
         Ok(RenameResult { changes })
     }
 
+    async fn prepare_call_hierarchy(
+        &self,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Option<CallHierarchyItem>, LspError> {
+        check_feature_support(file, LspFeature::IncomingCalls)?;
+
+        let (client, uri) = self.prepare_for_cross_file_request(file).await?;
+        let mut columns = Columns::of(&client).await;
+
+        let prepare_params = serde_json::json!({
+            "textDocument": { "uri": uri },
+            "position": columns.cli_position(file, line, column)
+        });
+
+        let items: Option<Vec<LspCallHierarchyItem>> = client
+            .request("textDocument/prepareCallHierarchy", Some(prepare_params))
+            .await?;
+
+        Ok(items
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            .map(|item| CallHierarchyItem {
+                name: item.name,
+                kind: convert_symbol_kind(item.kind),
+                location: columns.location(uri_range_to_location(&item.uri, &item.selection_range)),
+                call_site: None,
+            }))
+    }
+
     async fn incoming_calls(
         &self,
         file: &Path,
@@ -824,10 +1082,11 @@ impl LspService for DefaultLspService {
         check_feature_support(file, LspFeature::IncomingCalls)?;
 
         let (client, uri) = self.prepare_for_cross_file_request(file).await?;
+        let mut columns = Columns::of(&client).await;
 
         let prepare_params = serde_json::json!({
             "textDocument": { "uri": uri },
-            "position": to_lsp_position(line, column)
+            "position": columns.cli_position(file, line, column)
         });
 
         let items: Option<Vec<LspCallHierarchyItem>> = client
@@ -851,11 +1110,12 @@ impl LspService for DefaultLspService {
             .map(|c| CallHierarchyItem {
                 name: c.from.name,
                 kind: convert_symbol_kind(c.from.kind),
-                location: uri_range_to_location(&c.from.uri, &c.from.selection_range),
+                location: columns
+                    .location(uri_range_to_location(&c.from.uri, &c.from.selection_range)),
                 call_site: c
                     .from_ranges
                     .first()
-                    .map(|r| uri_range_to_location(&c.from.uri, r)),
+                    .map(|r| columns.location(uri_range_to_location(&c.from.uri, r))),
             })
             .collect())
     }
@@ -869,10 +1129,11 @@ impl LspService for DefaultLspService {
         check_feature_support(file, LspFeature::OutgoingCalls)?;
 
         let (client, uri) = self.prepare_for_cross_file_request(file).await?;
+        let mut columns = Columns::of(&client).await;
 
         let prepare_params = serde_json::json!({
             "textDocument": { "uri": uri },
-            "position": to_lsp_position(line, column)
+            "position": columns.cli_position(file, line, column)
         });
 
         let items: Option<Vec<LspCallHierarchyItem>> = client
@@ -896,11 +1157,11 @@ impl LspService for DefaultLspService {
             .map(|c| CallHierarchyItem {
                 name: c.to.name,
                 kind: convert_symbol_kind(c.to.kind),
-                location: uri_range_to_location(&c.to.uri, &c.to.selection_range),
+                location: columns.location(uri_range_to_location(&c.to.uri, &c.to.selection_range)),
                 call_site: c
                     .from_ranges
                     .first()
-                    .map(|r| uri_range_to_location(&c.to.uri, r)),
+                    .map(|r| columns.location(uri_range_to_location(&c.to.uri, r))),
             })
             .collect())
     }
@@ -914,10 +1175,11 @@ impl LspService for DefaultLspService {
         check_feature_support(file, LspFeature::TypeHierarchy)?;
 
         let (client, uri) = self.prepare_for_request(file).await?;
+        let mut columns = Columns::of(&client).await;
 
         let prepare_params = serde_json::json!({
             "textDocument": { "uri": uri },
-            "position": to_lsp_position(line, column)
+            "position": columns.cli_position(file, line, column)
         });
 
         let items: Option<Vec<serde_json::Value>> = client
@@ -939,6 +1201,10 @@ impl LspService for DefaultLspService {
             .unwrap_or_default()
             .into_iter()
             .filter_map(|item| parse_type_hierarchy_item(&item))
+            .map(|mut item| {
+                item.location = columns.location(item.location);
+                item
+            })
             .collect())
     }
 
@@ -951,10 +1217,11 @@ impl LspService for DefaultLspService {
         check_feature_support(file, LspFeature::TypeHierarchy)?;
 
         let (client, uri) = self.prepare_for_request(file).await?;
+        let mut columns = Columns::of(&client).await;
 
         let prepare_params = serde_json::json!({
             "textDocument": { "uri": uri },
-            "position": to_lsp_position(line, column)
+            "position": columns.cli_position(file, line, column)
         });
 
         let items: Option<Vec<serde_json::Value>> = client
@@ -976,6 +1243,10 @@ impl LspService for DefaultLspService {
             .unwrap_or_default()
             .into_iter()
             .filter_map(|item| parse_type_hierarchy_item(&item))
+            .map(|mut item| {
+                item.location = columns.location(item.location);
+                item
+            })
             .collect())
     }
 
@@ -984,13 +1255,11 @@ impl LspService for DefaultLspService {
 
         let client = self.get_client_for_file(file).await?;
         let uri = self.sync_document(&client, file).await?;
+        let mut columns = Columns::of(&client).await;
 
         let params = serde_json::json!({
             "textDocument": { "uri": uri },
-            "range": {
-                "start": { "line": range.start.line, "character": range.start.character },
-                "end": { "line": range.end.line, "character": range.end.character }
-            }
+            "range": columns.server_range(file, &range)
         });
 
         let hints: Option<Vec<serde_json::Value>> = client
@@ -1002,9 +1271,12 @@ impl LspService for DefaultLspService {
             .into_iter()
             .filter_map(|h| {
                 let position = h.get("position")?;
-                let pos = crate::models::lsp::Position::new(
-                    position.get("line")?.as_u64()? as u32,
-                    position.get("character")?.as_u64()? as u32,
+                let pos = columns.char_position(
+                    file,
+                    Position::new(
+                        position.get("line")?.as_u64()? as u32,
+                        position.get("character")?.as_u64()? as u32,
+                    ),
                 );
 
                 let label = match h.get("label")? {
@@ -1043,6 +1315,7 @@ impl LspService for DefaultLspService {
     async fn folding_ranges(&self, file: &Path) -> Result<Vec<FoldingRange>, LspError> {
         let client = self.get_client_for_file(file).await?;
         let uri = self.sync_document(&client, file).await?;
+        let mut columns = Columns::of(&client).await;
 
         let params = serde_json::json!({
             "textDocument": { "uri": uri }
@@ -1058,14 +1331,16 @@ impl LspService for DefaultLspService {
             .filter_map(|r| {
                 let start_line = r.get("startLine")?.as_u64()? as u32;
                 let end_line = r.get("endLine")?.as_u64()? as u32;
-                let start_character = r
-                    .get("startCharacter")
-                    .and_then(|v| v.as_u64())
-                    .map(|v| v as u32);
-                let end_character = r
-                    .get("endCharacter")
-                    .and_then(|v| v.as_u64())
-                    .map(|v| v as u32);
+                let mut character = |key: &str, line: u32| {
+                    let character = r.get(key)?.as_u64()? as u32;
+                    Some(
+                        columns
+                            .char_position(file, Position::new(line, character))
+                            .character,
+                    )
+                };
+                let start_character = character("startCharacter", start_line);
+                let end_character = character("endCharacter", end_line);
                 let kind = FoldingRangeKind::from_lsp(r.get("kind").and_then(|k| k.as_str()));
                 let collapsed_text = r
                     .get("collapsedText")
@@ -1091,10 +1366,11 @@ impl LspService for DefaultLspService {
     ) -> Result<Vec<SelectionRange>, LspError> {
         let client = self.get_client_for_file(file).await?;
         let uri = self.sync_document(&client, file).await?;
+        let mut columns = Columns::of(&client).await;
 
         let lsp_positions: Vec<_> = positions
             .iter()
-            .map(|(line, col)| to_lsp_position(*line, *col))
+            .map(|(line, col)| columns.cli_position(file, *line, *col))
             .collect();
 
         let params = serde_json::json!({
@@ -1130,16 +1406,31 @@ impl LspService for DefaultLspService {
             })
         }
 
+        fn convert_selection_range(
+            columns: &mut Columns,
+            file: &Path,
+            range: SelectionRange,
+        ) -> SelectionRange {
+            SelectionRange {
+                range: columns.char_range(file, &range.range),
+                parent: range
+                    .parent
+                    .map(|p| Box::new(convert_selection_range(columns, file, *p))),
+            }
+        }
+
         Ok(ranges
             .unwrap_or_default()
             .iter()
             .filter_map(parse_selection_range)
+            .map(|r| convert_selection_range(&mut columns, file, r))
             .collect())
     }
 
     async fn code_lens(&self, file: &Path) -> Result<Vec<CodeLens>, LspError> {
         let client = self.get_client_for_file(file).await?;
         let uri = self.sync_document(&client, file).await?;
+        let mut columns = Columns::of(&client).await;
 
         let params = serde_json::json!({
             "textDocument": { "uri": uri }
@@ -1149,8 +1440,24 @@ impl LspService for DefaultLspService {
             .request("textDocument/codeLens", Some(params))
             .await?;
 
-        Ok(lenses
-            .unwrap_or_default()
+        // Servers such as rust-analyzer return lenses without a command and
+        // fill in titles (e.g. reference counts) only on resolve
+        let mut resolved = Vec::new();
+        for lens in lenses.unwrap_or_default() {
+            if lens.get("command").is_none() && lens.get("data").is_some() {
+                match client
+                    .request::<serde_json::Value>("codeLens/resolve", Some(lens.clone()))
+                    .await
+                {
+                    Ok(r) if r.get("command").is_some() => resolved.push(r),
+                    _ => resolved.push(lens),
+                }
+            } else {
+                resolved.push(lens);
+            }
+        }
+
+        Ok(resolved
             .into_iter()
             .filter_map(|lens| {
                 let range = lens.get("range")?;
@@ -1181,7 +1488,7 @@ impl LspService for DefaultLspService {
                 let data = lens.get("data").cloned();
 
                 Some(CodeLens {
-                    range: Range::new(start_pos, end_pos),
+                    range: columns.char_range(file, &Range::new(start_pos, end_pos)),
                     command,
                     data,
                 })
@@ -1189,6 +1496,68 @@ impl LspService for DefaultLspService {
             .collect())
     }
 
+    async fn semantic_tokens(
+        &self,
+        file: &Path,
+        range: Option<Range>,
+    ) -> Result<Vec<SemanticToken>, LspError> {
+        let (client, uri) = self.prepare_for_request(file).await?;
+
+        let language = client.language();
+        let Some(legend) = client.semantic_tokens_legend().await else {
+            return Err(LspError::feature_not_supported(
+                language,
+                language_server_name(language),
+                "semantic tokens",
+                "Use 'symora search ast' for syntax-based classification.",
+            ));
+        };
+
+        let mut columns = Columns::of(&client).await;
+
+        // Without range support the whole document is requested and filtered
+        let (method, params) = match &range {
+            Some(range) if client.supports_semantic_tokens_range().await => (
+                "textDocument/semanticTokens/range",
+                serde_json::json!({
+                    "textDocument": { "uri": uri },
+                    "range": columns.server_range(file, range)
+                }),
+            ),
+            _ => (
+                "textDocument/semanticTokens/full",
+                serde_json::json!({
+                    "textDocument": { "uri": uri }
+                }),
+            ),
+        };
+
+        let tokens: Option<SemanticTokens> = client.request(method, Some(params)).await?;
+        let tokens = legend
+            .decode(&tokens.map(|t| t.data).unwrap_or_default())
+            .into_iter()
+            .map(|mut t| {
+                let end = Position::new(t.position.line, t.position.character + t.length);
+                t.position = columns.char_position(file, t.position);
+                t.length = columns
+                    .char_position(file, end)
+                    .character
+                    .saturating_sub(t.position.character);
+                t
+            });
+
+        Ok(match range {
+            Some(range) => {
+                let start = (range.start.line, range.start.character);
+                let end = (range.end.line, range.end.character);
+                tokens
+                    .filter(|t| (start..end).contains(&(t.position.line, t.position.character)))
+                    .collect()
+            }
+            None => tokens.collect(),
+        })
+    }
+
     async fn code_actions(
         &self,
         file: &Path,
@@ -1198,15 +1567,14 @@ impl LspService for DefaultLspService {
         check_feature_support(file, LspFeature::CodeActions)?;
 
         let (client, uri) = self.prepare_for_request(file).await?;
+        let mut columns = Columns::of(&client).await;
 
-        let position = Position::new(line.saturating_sub(1), column.saturating_sub(1));
+        let position = Position::from_cli(line, column);
+        let next = Position::new(position.line, position.character + 1);
 
         let params = serde_json::json!({
             "textDocument": { "uri": uri },
-            "range": {
-                "start": { "line": position.line, "character": position.character },
-                "end": { "line": position.line, "character": position.character + 1 }
-            },
+            "range": columns.server_range(file, &Range::new(position, next)),
             "context": { "diagnostics": [] }
         });
 
@@ -1214,71 +1582,169 @@ impl LspService for DefaultLspService {
             .request("textDocument/codeAction", Some(params))
             .await?;
 
-        let actions = response
+        Ok(response
             .unwrap_or_default()
             .into_iter()
-            .filter_map(|v| {
-                let title = v.get("title")?.as_str()?.to_string();
-                let kind = v.get("kind").and_then(|k| k.as_str());
-                let is_preferred = v
-                    .get("isPreferred")
-                    .and_then(|p| p.as_bool())
-                    .unwrap_or(false);
-                let diagnostics = v
-                    .get("diagnostics")
-                    .and_then(|d| d.as_array())
-                    .map(|arr| {
-                        arr.iter()
-                            .filter_map(|d| d.get("message").and_then(|m| m.as_str()))
-                            .map(|s| s.to_string())
-                            .collect()
-                    })
-                    .unwrap_or_default();
-
-                Some(CodeAction {
-                    title,
-                    kind: CodeActionKind::from(kind),
-                    is_preferred,
-                    diagnostics,
-                    edit: None,
-                    data: Some(v),
-                })
-            })
-            .collect();
+            .filter_map(parse_code_action)
+            .collect())
+    }
+
+    async fn source_actions(
+        &self,
+        file: &Path,
+        kinds: &[CodeActionKind],
+    ) -> Result<Vec<CodeAction>, LspError> {
+        check_feature_support(file, LspFeature::CodeActions)?;
+
+        let (client, uri) = self.prepare_for_request(file).await?;
+        let content = read_file_validated(file).await?;
+        let line_count = content.lines().count();
+
+        let params = serde_json::json!({
+            "textDocument": { "uri": uri },
+            "range": {
+                "start": { "line": 0, "character": 0 },
+                "end": { "line": line_count, "character": 0 }
+            },
+            "context": {
+                "diagnostics": [],
+                "only": kinds,
+                "triggerKind": 1
+            }
+        });
+
+        let response: Option<Vec<serde_json::Value>> = client
+            .request("textDocument/codeAction", Some(params))
+            .await?;
 
-        Ok(actions)
+        Ok(response
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(parse_code_action)
+            .filter(|a| kinds.contains(&a.kind))
+            .collect())
     }
 
     async fn apply_code_action(
         &self,
         file: &Path,
         action: &CodeAction,
+        run_command: bool,
     ) -> Result<ApplyActionResult, LspError> {
         let client = self.get_client_for_file(file).await?;
         let _ = self.sync_document(&client, file).await?;
 
-        let raw_data = action.data.as_ref().unwrap_or(&serde_json::Value::Null);
-        let edit = raw_data.get("edit").cloned();
-
-        let edit = if edit.is_none() && action.data.is_some() {
-            let resolved: Option<serde_json::Value> = client
-                .request("codeAction/resolve", action.data.clone())
-                .await
-                .ok()
-                .flatten();
+        let Some(raw) = action.data.clone() else {
+            return Ok(ApplyActionResult::default());
+        };
 
-            resolved.and_then(|r| r.get("edit").cloned())
+        // Servers may return a bare Command instead of a CodeAction
+        let (edit, command) = if raw.get("command").is_some_and(|c| c.is_string()) {
+            (None, Some(raw))
         } else {
-            edit
+            let raw = if raw.get("edit").is_none() {
+                // Lazily computed edits are filled in by codeAction/resolve
+                client
+                    .request::<Option<serde_json::Value>>("codeAction/resolve", Some(raw.clone()))
+                    .await
+                    .ok()
+                    .flatten()
+                    .unwrap_or(raw)
+            } else {
+                raw
+            };
+            (raw.get("edit").cloned(), raw.get("command").cloned())
         };
 
-        let changes = if let Some(edit) = edit {
-            parse_workspace_edit(&edit)
-        } else {
-            Vec::new()
+        let mut changes = match edit {
+            Some(edit) => parse_workspace_edit(&edit)?,
+            None => Vec::new(),
+        };
+        stamp_synced_versions(&client, &mut changes).await;
+        Columns::of(&client).await.file_edits(&mut changes);
+
+        let command = command.and_then(|c| {
+            let name = c.get("command")?.as_str()?.to_string();
+            let arguments = c
+                .get("arguments")
+                .and_then(|a| a.as_array())
+                .cloned()
+                .unwrap_or_default();
+            Some((name, arguments))
+        });
+        let command_executed = run_command && command.is_some();
+        if let Some((name, arguments)) = command.as_ref().filter(|_| run_command) {
+            // The command runs after the edit, so the server must see the
+            // edited text; the edits it requests are relative to that text
+            let staged = prepare_workspace_edit(&changes).map_err(|e| {
+                LspError::Protocol(format!("Code action edit cannot be applied: {}", e))
+            })?;
+            let mut columns = Columns::of(&client).await;
+            for file in &staged {
+                let Some(updated) = &file.updated else {
+                    continue;
+                };
+                if Language::from_path(&file.path) == client.language() {
+                    client
+                        .sync_document(&path_to_uri(&file.path), updated)
+                        .await?;
+                }
+                columns = columns.with_content(&file.path, updated);
+            }
+
+            let mut command_changes = Vec::new();
+            for edit in client.execute_command(name, arguments).await? {
+                command_changes.extend(parse_workspace_edit(&edit)?);
+            }
+            stamp_synced_versions(&client, &mut command_changes).await;
+            columns.file_edits(&mut command_changes);
+            changes.extend(command_changes);
+        }
+
+        Ok(ApplyActionResult {
+            changes,
+            command: command.map(|(name, _)| name),
+            command_executed,
+        })
+    }
+
+    async fn format(
+        &self,
+        file: &Path,
+        range: Option<Range>,
+        options: &FormattingOptions,
+    ) -> Result<Vec<TextEdit>, LspError> {
+        let client = self.get_client_for_file(file).await?;
+        let uri = self.sync_document(&client, file).await?;
+        let mut columns = Columns::of(&client).await;
+
+        let (method, params) = match range {
+            Some(range) => (
+                "textDocument/rangeFormatting",
+                serde_json::json!({
+                    "textDocument": { "uri": uri },
+                    "range": columns.server_range(file, &range),
+                    "options": options
+                }),
+            ),
+            None => (
+                "textDocument/formatting",
+                serde_json::json!({
+                    "textDocument": { "uri": uri },
+                    "options": options
+                }),
+            ),
         };
 
-        Ok(ApplyActionResult { changes })
+        let edits: Option<Vec<TextEdit>> = client.request(method, Some(params)).await?;
+        Ok(edits
+            .unwrap_or_default()
+            .into_iter()
+            .map(|mut edit| {
+                edit.range = columns.char_range(file, &edit.range);
+                edit
+            })
+            .collect())
     }
 
     async fn is_available(&self, language: Language) -> bool {
@@ -1298,3 +1764,11 @@ impl LspService for DefaultLspService {
         self.manager.cleanup_idle(timeout).await
     }
 }
+
+/// Record the client's synced version of every document an edit was
+/// computed for, so the applier can refuse edits for an older version
+async fn stamp_synced_versions(client: &LspClient, changes: &mut [FileChangeWithEdits]) {
+    for change in changes.iter_mut().filter(|c| c.version.is_some()) {
+        change.synced_version = client.document_version(&path_to_uri(&change.file)).await;
+    }
+}
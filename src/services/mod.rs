@@ -3,8 +3,14 @@
 pub mod ast_query;
 pub mod config;
 pub mod daemon_lsp;
+pub mod diff;
+pub mod editorconfig;
+pub mod journal;
 pub mod lsp;
+pub mod patch;
 pub mod project;
+pub mod text_buffer;
+pub mod workspace_edit;
 
 pub use ast_query::{AstQueryService, DefaultAstQueryService};
 pub use config::{ConfigService, DefaultConfigService};
@@ -0,0 +1,504 @@
+//! Unified diff patches
+//!
+//! Parses multi-file unified diffs and turns their hunks into text edits.
+//! Hunks are located by content rather than trusted line numbers: each is
+//! searched for nearest to its expected line (shifted by how far earlier
+//! hunks moved), first exactly, then ignoring whitespace, then with up to
+//! [`MAX_FUZZ`] context lines dropped from either end.
+
+use serde::Serialize;
+
+use crate::models::lsp::{Position, Range, TextEdit};
+use crate::services::text_buffer::TextBuffer;
+
+/// Context lines a hunk may lose at each end and still apply
+pub const MAX_FUZZ: usize = 2;
+
+const NULL_PATH: &str = "/dev/null";
+
+/// Changes to one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePatch {
+    /// Path before the change; `None` when the patch creates the file
+    pub old_path: Option<String>,
+    /// Path after the change; `None` when the patch deletes the file
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    Context,
+    Delete,
+    Insert,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// 1-indexed first line of the old side (0 for an empty old side)
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<(LineKind, String)>,
+    /// The old side's last line has no newline
+    pub old_no_newline: bool,
+    /// The new side's last line has no newline
+    pub new_no_newline: bool,
+}
+
+impl Hunk {
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_len, self.new_start, self.new_len
+        )
+    }
+
+    /// Apply a `\ No newline at end of file` marker following the last line
+    fn mark_no_newline(&mut self) {
+        match self.lines.last().map(|(kind, _)| *kind) {
+            Some(LineKind::Delete) => self.old_no_newline = true,
+            Some(LineKind::Insert) => self.new_no_newline = true,
+            _ => {
+                self.old_no_newline = true;
+                self.new_no_newline = true;
+            }
+        }
+    }
+
+    fn old_lines(&self) -> impl Iterator<Item = &str> {
+        self.lines
+            .iter()
+            .filter(|(kind, _)| *kind != LineKind::Insert)
+            .map(|(_, line)| line.as_str())
+    }
+
+    /// Leading and trailing context line counts
+    fn context(&self) -> (usize, usize) {
+        let is_context = |(kind, _): &&(LineKind, String)| *kind == LineKind::Context;
+        let leading = self.lines.iter().take_while(is_context).count();
+        let trailing = self.lines.iter().rev().take_while(is_context).count();
+        (leading, trailing.min(self.lines.len() - leading))
+    }
+}
+
+/// Outcome of one hunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HunkReport {
+    pub header: String,
+    pub applied: bool,
+    /// 1-indexed line where the hunk's matched lines start
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// Lines between the expected and the actual position
+    #[serde(skip_serializing_if = "is_zero")]
+    pub offset: i64,
+    /// Context lines dropped to make the hunk apply
+    #[serde(skip_serializing_if = "is_zero")]
+    pub fuzz: usize,
+    /// Matched only when ignoring whitespace
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub whitespace: bool,
+}
+
+fn is_zero<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// Parse a unified diff, stripping `strip` leading path components (or the
+/// `a/` and `b/` prefixes of git diffs when `None`)
+pub fn parse(diff: &str, strip: Option<usize>) -> Result<Vec<FilePatch>, String> {
+    let lines: Vec<&str> = diff.lines().map(|l| l.trim_end_matches('\r')).collect();
+    let mut patches = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let (Some(old), Some(new)) = (
+            lines[i].strip_prefix("--- "),
+            lines.get(i + 1).and_then(|l| l.strip_prefix("+++ ")),
+        ) else {
+            i += 1;
+            continue;
+        };
+        let (old_path, new_path) = strip_paths(header_path(old), header_path(new), strip);
+        i += 2;
+
+        let mut hunks = Vec::new();
+        while let Some(header) = lines.get(i).and_then(|l| l.strip_prefix("@@ ")) {
+            let (hunk, next) =
+                parse_hunk(header, &lines, i + 1).map_err(|e| format!("line {}: {}", i + 1, e))?;
+            hunks.push(hunk);
+            i = next;
+        }
+        if hunks.is_empty() {
+            return Err(format!("line {}: no hunks for {}", i, old));
+        }
+        patches.push(FilePatch {
+            old_path,
+            new_path,
+            hunks,
+        });
+    }
+
+    if patches.is_empty() {
+        return Err("no file changes found (expected '---' and '+++' headers)".to_string());
+    }
+    Ok(patches)
+}
+
+/// Path of a `---`/`+++` header, without a trailing timestamp
+fn header_path(header: &str) -> Option<&str> {
+    let path = header.split('\t').next().unwrap_or(header).trim();
+    (path != NULL_PATH).then_some(path)
+}
+
+fn strip_paths(
+    old: Option<&str>,
+    new: Option<&str>,
+    strip: Option<usize>,
+) -> (Option<String>, Option<String>) {
+    let strip_n = |path: &str, n: usize| path.splitn(n + 1, '/').nth(n).unwrap_or(path).to_string();
+    match strip {
+        Some(n) => (old.map(|p| strip_n(p, n)), new.map(|p| strip_n(p, n))),
+        None => {
+            let git =
+                old.is_none_or(|p| p.starts_with("a/")) && new.is_none_or(|p| p.starts_with("b/"));
+            let n = usize::from(git);
+            (old.map(|p| strip_n(p, n)), new.map(|p| strip_n(p, n)))
+        }
+    }
+}
+
+/// Parse `-a,b +c,d @@` and the body starting at `start`; returns the hunk
+/// and the index of the first line after it
+fn parse_hunk(header: &str, lines: &[&str], start: usize) -> Result<(Hunk, usize), String> {
+    let range = |spec: Option<&str>, sign: char| -> Result<(usize, usize), String> {
+        let spec = spec
+            .and_then(|s| s.strip_prefix(sign))
+            .ok_or_else(|| format!("invalid hunk header '@@ {}'", header))?;
+        let (start, len) = spec.split_once(',').unwrap_or((spec, "1"));
+        match (start.parse(), len.parse()) {
+            (Ok(start), Ok(len)) => Ok((start, len)),
+            _ => Err(format!("invalid hunk header '@@ {}'", header)),
+        }
+    };
+    let mut parts = header.split_whitespace();
+    let (old_start, old_len) = range(parts.next(), '-')?;
+    let (new_start, new_len) = range(parts.next(), '+')?;
+
+    let mut hunk = Hunk {
+        old_start,
+        old_len,
+        new_start,
+        new_len,
+        lines: Vec::new(),
+        old_no_newline: false,
+        new_no_newline: false,
+    };
+    let (mut old, mut new) = (0, 0);
+    let mut i = start;
+    while old < old_len || new < new_len {
+        let Some(line) = lines.get(i) else {
+            return Err(format!("hunk '@@ {}' ends early", header));
+        };
+        let (kind, text) = match line.chars().next() {
+            Some('-') => (LineKind::Delete, &line[1..]),
+            Some('+') => (LineKind::Insert, &line[1..]),
+            Some(' ') => (LineKind::Context, &line[1..]),
+            // Some tools drop the space of empty context lines
+            None => (LineKind::Context, ""),
+            Some('\\') => {
+                hunk.mark_no_newline();
+                i += 1;
+                continue;
+            }
+            Some(_) => return Err(format!("unexpected line in hunk: '{}'", line)),
+        };
+        if kind != LineKind::Insert {
+            old += 1;
+        }
+        if kind != LineKind::Delete {
+            new += 1;
+        }
+        hunk.lines.push((kind, text.to_string()));
+        i += 1;
+    }
+    if old != old_len || new != new_len {
+        return Err(format!("hunk '@@ {}' has wrong line counts", header));
+    }
+
+    while lines.get(i).is_some_and(|l| l.starts_with('\\')) {
+        hunk.mark_no_newline();
+        i += 1;
+    }
+    Ok((hunk, i))
+}
+
+/// Edits for the hunks of one file, from [`apply_hunks`]
+#[derive(Debug)]
+pub struct HunkEdits {
+    pub edits: Vec<TextEdit>,
+    /// Whether the file ends with a newline, when a hunk reaches its end
+    pub final_newline: Option<bool>,
+    pub reports: Vec<HunkReport>,
+}
+
+/// Locate every hunk in `content` and return the edits for those that apply,
+/// with a report per hunk
+pub fn apply_hunks(content: &str, hunks: &[Hunk]) -> HunkEdits {
+    let buffer = TextBuffer::new(content);
+    let lines: Vec<&str> = buffer.lines().collect();
+    let mut edits = Vec::new();
+    let mut final_newline = None;
+    let mut reports = Vec::new();
+    // Shift observed on the previous hunk, and the first line still free
+    let mut delta: i64 = 0;
+    let mut min_start = 0;
+
+    for hunk in hunks {
+        let mut report = HunkReport {
+            header: hunk.header(),
+            applied: false,
+            line: None,
+            offset: 0,
+            fuzz: 0,
+            whitespace: false,
+        };
+        let Some(found) = locate(&lines, hunk, delta, min_start) else {
+            reports.push(report);
+            continue;
+        };
+
+        let mut new_lines = Vec::new();
+        let mut file_line = found.start;
+        for (kind, text) in &hunk.lines[found.skip..hunk.lines.len() - found.trim] {
+            match kind {
+                // Context keeps the file's text when matched loosely
+                LineKind::Context => {
+                    new_lines.push(lines[file_line]);
+                    file_line += 1;
+                }
+                LineKind::Delete => file_line += 1,
+                LineKind::Insert => new_lines.push(text),
+            }
+        }
+
+        let mut new_text: String = new_lines.iter().map(|l| format!("{}\n", l)).collect();
+        if file_line == lines.len() {
+            let newline = if hunk.new_no_newline {
+                false
+            } else {
+                hunk.old_no_newline || buffer.has_final_newline() || lines.is_empty()
+            };
+            if !newline {
+                new_text.pop();
+            }
+            final_newline = Some(newline);
+        }
+        edits.push(TextEdit {
+            range: Range::new(
+                Position::new(found.start as u32, 0),
+                Position::new(file_line as u32, 0),
+            ),
+            new_text,
+        });
+
+        let expected = expected_start(hunk, found.skip);
+        delta = found.start as i64 - expected as i64;
+        min_start = file_line;
+        report.applied = true;
+        report.line = Some(found.start + 1);
+        report.offset = delta;
+        report.fuzz = found.skip.max(found.trim);
+        report.whitespace = found.whitespace;
+        reports.push(report);
+    }
+    HunkEdits {
+        edits,
+        final_newline,
+        reports,
+    }
+}
+
+struct Found {
+    /// 0-indexed file line where the matched part of the hunk starts
+    start: usize,
+    /// Context lines dropped from the start and end of the hunk
+    skip: usize,
+    trim: usize,
+    whitespace: bool,
+}
+
+/// 0-indexed line where the hunk's old side, without `skip` leading lines,
+/// is expected. An empty old side inserts after its start line.
+fn expected_start(hunk: &Hunk, skip: usize) -> usize {
+    match hunk.old_len {
+        0 => hunk.old_start,
+        _ => hunk.old_start.saturating_sub(1),
+    }
+    .saturating_add(skip)
+}
+
+fn locate(lines: &[&str], hunk: &Hunk, delta: i64, min_start: usize) -> Option<Found> {
+    let old: Vec<&str> = hunk.old_lines().collect();
+    let (leading, trailing) = hunk.context();
+
+    for fuzz in 0..=MAX_FUZZ {
+        let skip = fuzz.min(leading);
+        let trim = fuzz.min(trailing);
+        if fuzz > 0 && skip + trim == 0 {
+            break;
+        }
+        if skip + trim > old.len() || (fuzz > 0 && skip + trim == old.len()) {
+            continue;
+        }
+        let block = &old[skip..old.len() - trim];
+        let expected = (expected_start(hunk, skip) as i64 + delta).max(0) as usize;
+
+        for whitespace in [false, true] {
+            let same = |a: &str, b: &str| {
+                a == b || (whitespace && a.split_whitespace().eq(b.split_whitespace()))
+            };
+            let fits = |start: usize| {
+                start >= min_start
+                    && start + block.len() <= lines.len()
+                    && block.iter().zip(&lines[start..]).all(|(a, b)| same(a, b))
+            };
+            if let Some(start) = nearest(expected, lines.len(), fits) {
+                return Some(Found {
+                    start,
+                    skip,
+                    trim,
+                    whitespace,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Start closest to `expected` (earlier first on ties) satisfying `fits`
+fn nearest(expected: usize, len: usize, fits: impl Fn(usize) -> bool) -> Option<usize> {
+    let expected = expected.min(len);
+    (0..=len).find_map(|distance| {
+        [expected.checked_sub(distance), Some(expected + distance)]
+            .into_iter()
+            .flatten()
+            .filter(|&start| start <= len)
+            .find(|&start| fits(start))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ fn a() {
+-    1
++    2
+ }
+--- /dev/null\t2024-01-01 00:00:00
++++ b/NEW.md
+@@ -0,0 +1,1 @@
++# New
+\\ No newline at end of file
+";
+
+    #[test]
+    fn test_parse() {
+        let patches = parse(DIFF, None).unwrap();
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].old_path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(patches[0].hunks[0].lines.len(), 4);
+        assert_eq!(patches[1].old_path, None);
+        assert_eq!(patches[1].new_path.as_deref(), Some("NEW.md"));
+        assert!(patches[1].hunks[0].new_no_newline);
+
+        assert_eq!(
+            parse(DIFF, Some(0)).unwrap()[0].new_path.as_deref(),
+            Some("b/src/lib.rs")
+        );
+        assert!(parse("--- a\n+++ b\n@@ -1,2 +1,1 @@\n-x\n", None).is_err());
+        assert!(parse("no diff here", None).is_err());
+    }
+
+    #[test]
+    fn test_apply_with_offset_and_fuzz() {
+        let patches = parse(DIFF, None).unwrap();
+        let hunks = &patches[0].hunks;
+
+        // Two lines were added above the hunk since the diff was made
+        let content = "// a\n// b\nfn a() {\n    1\n}\n";
+        let HunkEdits { edits, reports, .. } = apply_hunks(content, hunks);
+        assert_eq!(
+            TextBuffer::new(content).apply(&edits).unwrap(),
+            "// a\n// b\nfn a() {\n    2\n}\n"
+        );
+        assert_eq!((reports[0].line, reports[0].offset), (Some(3), 2));
+
+        // Reindented context matches ignoring whitespace and is kept as is
+        let content = "fn  a() {\n    1\n}";
+        let HunkEdits { edits, reports, .. } = apply_hunks(content, hunks);
+        assert!(reports[0].whitespace);
+        assert_eq!(
+            TextBuffer::new(content).apply(&edits).unwrap(),
+            "fn  a() {\n    2\n}"
+        );
+
+        // A changed context line is dropped as fuzz
+        let content = "fn b() {\n    1\n}\n";
+        let HunkEdits { reports, .. } = apply_hunks(content, hunks);
+        assert_eq!((reports[0].applied, reports[0].fuzz), (true, 1));
+
+        // Changed lines the hunk removes cannot be fuzzed away
+        let HunkEdits { edits, reports, .. } = apply_hunks("fn a() {\n    3\n}\n", hunks);
+        assert!(edits.is_empty());
+        assert!(!reports[0].applied);
+    }
+
+    #[test]
+    fn test_apply_creates_content() {
+        let patches = parse(DIFF, None).unwrap();
+        let HunkEdits { edits, reports, .. } = apply_hunks("", &patches[1].hunks);
+        assert!(reports[0].applied);
+        assert_eq!(TextBuffer::new("").apply(&edits).unwrap(), "# New");
+
+        let patches = parse("--- /dev/null\n+++ b/x\n@@ -0,0 +1 @@\n+x\n", None).unwrap();
+        let HunkEdits { edits, .. } = apply_hunks("", &patches[0].hunks);
+        assert_eq!(TextBuffer::new("").apply(&edits).unwrap(), "x\n");
+    }
+
+    /// Content after applying the single-file `diff`
+    fn patched(content: &str, diff: &str) -> String {
+        let patches = parse(diff, None).unwrap();
+        let result = apply_hunks(content, &patches[0].hunks);
+        let mut buffer = TextBuffer::new(content);
+        if let Some(final_newline) = result.final_newline {
+            buffer = buffer.with_final_newline(final_newline);
+        }
+        buffer.apply(&result.edits).unwrap()
+    }
+
+    #[test]
+    fn test_apply_removes_final_newline() {
+        let diff = "--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n a\n-b\n+b\n\\ No newline at end of file\n";
+        assert_eq!(patched("a\nb\n", diff), "a\nb");
+        assert_eq!(patched("a\r\nb\r\n", diff), "a\r\nb");
+    }
+
+    #[test]
+    fn test_apply_adds_final_newline() {
+        let diff = "--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n";
+        assert_eq!(patched("a\nb", diff), "a\nb\n");
+
+        // Hunks away from the end leave the final newline as it is
+        let diff = "--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n-a\n+A\n b\n";
+        assert_eq!(patched("a\nb\nc", diff), "A\nb\nc");
+    }
+}
@@ -0,0 +1,261 @@
+//! Text buffer for file mutations
+//!
+//! Edits address text by line and character column, but files carry
+//! conventions the edits know nothing about: a byte order mark, CRLF line
+//! endings and whether the last line is terminated. [`TextBuffer`] separates
+//! those from the text so every mutation keeps them and changes only the
+//! edited spans.
+
+use std::borrow::Cow;
+
+use crate::models::lsp::{Position, PositionEncoding, TextEdit};
+
+const BOM: char = '\u{feff}';
+
+/// Line terminator used by a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    /// Style used by most line breaks in `text`; LF when there are none
+    pub fn detect(text: &str) -> Self {
+        let breaks = text.matches('\n').count();
+        let crlf = text.matches("\r\n").count();
+        if crlf * 2 > breaks {
+            Self::CrLf
+        } else {
+            Self::Lf
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+}
+
+/// File content split into its text and the conventions it was written with
+#[derive(Debug, Clone)]
+pub struct TextBuffer {
+    /// Content without the byte order mark
+    text: String,
+    bom: bool,
+    line_ending: LineEnding,
+    final_newline: bool,
+    /// Byte offset at which each line starts
+    line_starts: Vec<usize>,
+}
+
+impl TextBuffer {
+    pub fn new(content: &str) -> Self {
+        let (text, bom) = match content.strip_prefix(BOM) {
+            Some(text) => (text, true),
+            None => (content, false),
+        };
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self {
+            text: text.to_string(),
+            bom,
+            line_ending: LineEnding::detect(text),
+            final_newline: text.ends_with('\n'),
+            line_starts,
+        }
+    }
+
+    /// Content without the byte order mark, as positions address it
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn has_bom(&self) -> bool {
+        self.bom
+    }
+
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    pub fn has_final_newline(&self) -> bool {
+        self.final_newline
+    }
+
+    /// Override whether rendered text gets a final newline restored
+    pub fn with_final_newline(mut self, final_newline: bool) -> Self {
+        self.final_newline = final_newline;
+        self
+    }
+
+    /// Lines without their terminators
+    pub fn lines(&self) -> std::str::Lines<'_> {
+        self.text.lines()
+    }
+
+    /// Byte offset of a position whose column counts characters, clamped to
+    /// the line (excluding its terminator) and to the end of the text
+    pub fn offset(&self, pos: Position) -> usize {
+        let line = pos.line as usize;
+        let Some(&start) = self.line_starts.get(line) else {
+            return self.text.len();
+        };
+        let mut end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.text.len(), |&next| next - 1);
+        if self.text[start..end].ends_with('\r') {
+            end -= 1;
+        }
+
+        start + PositionEncoding::Utf32.byte_index(&self.text[start..end], pos.character)
+    }
+
+    /// Position, with a character column, of a byte offset into the text
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        Position::new(line as u32, self.text[start..offset].chars().count() as u32)
+    }
+
+    /// `text` with its line breaks in this buffer's style.
+    ///
+    /// An empty buffer has no style of its own, so text inserted into it (a
+    /// new or recreated file) is kept byte for byte.
+    pub fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.text.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        match self.line_ending {
+            LineEnding::Lf if text.contains("\r\n") => Cow::Owned(text.replace("\r\n", "\n")),
+            LineEnding::CrLf if text.contains('\n') => {
+                Cow::Owned(text.replace("\r\n", "\n").replace('\n', "\r\n"))
+            }
+            _ => Cow::Borrowed(text),
+        }
+    }
+
+    /// Apply text edits and return the full file content.
+    ///
+    /// Edits refer to the original text, count columns in characters and
+    /// must not overlap; inserts at the same position keep their order.
+    /// Positions past the end of a line or of the text are clamped. Inserted
+    /// line breaks follow the file's style, and the byte order mark and a
+    /// final newline are kept.
+    pub fn apply<'a>(
+        &self,
+        edits: impl IntoIterator<Item = &'a TextEdit>,
+    ) -> Result<String, String> {
+        let mut spans = Vec::new();
+        for edit in edits {
+            let start = self.offset(edit.range.start);
+            let end = self.offset(edit.range.end);
+            if end < start {
+                return Err(format!(
+                    "range end {}:{} is before start {}:{}",
+                    edit.range.end.line + 1,
+                    edit.range.end.character + 1,
+                    edit.range.start.line + 1,
+                    edit.range.start.character + 1
+                ));
+            }
+            spans.push((start, end, self.normalize(&edit.new_text)));
+        }
+
+        spans.sort_by_key(|(start, end, _)| (*start, *end));
+        if spans.windows(2).any(|w| w[1].0 < w[0].1) {
+            return Err("overlapping edits".to_string());
+        }
+
+        let mut text = String::with_capacity(self.text.len());
+        let mut last = 0;
+        for (start, end, new_text) in &spans {
+            text.push_str(&self.text[last..*start]);
+            text.push_str(new_text);
+            last = *end;
+        }
+        text.push_str(&self.text[last..]);
+        Ok(self.render(text))
+    }
+
+    /// Full file content for edited text, restoring the byte order mark and a
+    /// final newline an edit removed
+    pub fn render(&self, mut text: String) -> String {
+        if self.final_newline && !text.is_empty() && !text.ends_with('\n') {
+            text.push_str(self.line_ending.as_str());
+        }
+        if self.bom {
+            text.insert(0, BOM);
+        }
+        text
+    }
+}
+
+/// `content` without a leading byte order mark
+pub fn strip_bom(content: &str) -> &str {
+    content.strip_prefix(BOM).unwrap_or(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::lsp::Range;
+
+    fn edit(start: (u32, u32), end: (u32, u32), text: &str) -> TextEdit {
+        TextEdit {
+            range: Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1)),
+            new_text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_detect_conventions() {
+        let buffer = TextBuffer::new("\u{feff}a\r\nb\r\nc\n");
+        assert!(buffer.has_bom());
+        assert_eq!(buffer.line_ending(), LineEnding::CrLf);
+        assert!(buffer.has_final_newline());
+        assert_eq!(buffer.text(), "a\r\nb\r\nc\n");
+        assert_eq!(buffer.lines().collect::<Vec<_>>(), ["a", "b", "c"]);
+        assert_eq!(buffer.position(3), Position::new(1, 0));
+        assert_eq!(buffer.offset(buffer.position(7)), 7);
+
+        let buffer = TextBuffer::new("a\nb");
+        assert!(!buffer.has_bom());
+        assert_eq!(buffer.line_ending(), LineEnding::Lf);
+        assert!(!buffer.has_final_newline());
+    }
+
+    #[test]
+    fn test_apply_keeps_conventions() {
+        // BOM is not part of the addressed text; inserted breaks become CRLF
+        let buffer = TextBuffer::new("\u{feff}fn a() {}\r\nfn b() {}\r\n");
+        let edits = [edit((0, 3), (0, 4), "x"), edit((1, 9), (1, 9), "\n// end")];
+        assert_eq!(
+            buffer.apply(&edits).unwrap(),
+            "\u{feff}fn x() {}\r\nfn b() {}\r\n// end\r\n"
+        );
+
+        // Replacing the whole text keeps the final newline
+        let edits = [edit((0, 0), (99, 0), "fn c() {}")];
+        assert_eq!(buffer.apply(&edits).unwrap(), "\u{feff}fn c() {}\r\n");
+
+        // ...and does not add one that was not there
+        let buffer = TextBuffer::new("a\nb");
+        let edits = [edit((1, 0), (1, 1), "c\r\nd")];
+        assert_eq!(buffer.apply(&edits).unwrap(), "a\nc\nd");
+
+        // An empty file takes inserted text as is
+        let edits = [edit((0, 0), (0, 0), "\u{feff}a\r\nb\n")];
+        assert_eq!(
+            TextBuffer::new("").apply(&edits).unwrap(),
+            "\u{feff}a\r\nb\n"
+        );
+    }
+}
@@ -0,0 +1,524 @@
+//! Workspace edit application
+//!
+//! Applies text edits and file operations across several files as one
+//! transaction. Every change is validated and every new file is staged next
+//! to its target before any original is replaced; if a later step fails,
+//! files already replaced are restored.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::EditError;
+use crate::models::lsp::{FileChangeWithEdits, ResourceOperation, TextEdit};
+use crate::services::text_buffer::TextBuffer;
+
+/// A file written, created or deleted by a workspace edit
+#[derive(Debug, Clone)]
+pub struct AppliedFile {
+    pub path: PathBuf,
+    /// Content before the edit (`None` if the edit created the file)
+    pub original: Option<String>,
+    /// Content after the edit (`None` if the edit deleted the file)
+    pub updated: Option<String>,
+}
+
+/// Apply edits to all files, or to none of them.
+pub fn apply_workspace_edit(
+    changes: &[FileChangeWithEdits],
+) -> Result<Vec<AppliedFile>, EditError> {
+    let files = prepare_workspace_edit(changes)?;
+    commit(&files)?;
+    Ok(files)
+}
+
+/// Read every target file and compute the result without writing.
+///
+/// Changes apply in order, so edits may target a file created or renamed by
+/// an earlier change. Files left unchanged are omitted. Edits computed for a
+/// document version other than the one the client synced are refused.
+pub fn prepare_workspace_edit(
+    changes: &[FileChangeWithEdits],
+) -> Result<Vec<AppliedFile>, EditError> {
+    for change in changes {
+        if let (Some(version), Some(synced)) = (change.version, change.synced_version)
+            && version != synced
+        {
+            return Err(EditError::VersionMismatch {
+                path: change.file.display().to_string(),
+                version,
+                synced,
+            });
+        }
+    }
+
+    let mut files = Staging::default();
+    for change in changes {
+        if let Some(ref operation) = change.operation {
+            files.apply_operation(&change.file, operation)?;
+        }
+        if change.edits.is_empty() {
+            continue;
+        }
+
+        let current = files.current(&change.file)?;
+        let content = current
+            .as_deref()
+            .ok_or_else(|| invalid(&change.file, "file does not exist"))?;
+        let mut buffer = TextBuffer::new(content);
+        if let Some(final_newline) = change.final_newline {
+            buffer = buffer.with_final_newline(final_newline);
+        }
+        let updated = buffer
+            .apply(&change.edits)
+            .map_err(|message| EditError::InvalidEdit {
+                path: change.file.display().to_string(),
+                message,
+            })?;
+        *current = Some(updated);
+    }
+    Ok(files.into_changed())
+}
+
+fn invalid(path: &Path, message: &str) -> EditError {
+    EditError::InvalidEdit {
+        path: path.display().to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// In-memory view of the files touched by an edit
+#[derive(Default)]
+struct Staging {
+    order: Vec<PathBuf>,
+    /// Original and current content; `None` means the file does not exist
+    files: HashMap<PathBuf, (Option<String>, Option<String>)>,
+}
+
+impl Staging {
+    fn current(&mut self, path: &Path) -> Result<&mut Option<String>, EditError> {
+        match self.files.entry(path.to_path_buf()) {
+            Entry::Occupied(e) => Ok(&mut e.into_mut().1),
+            Entry::Vacant(e) => {
+                let original = match fs::read_to_string(path) {
+                    Ok(content) => Some(content),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                    Err(source) => {
+                        return Err(EditError::Read {
+                            path: path.display().to_string(),
+                            source,
+                        });
+                    }
+                };
+                self.order.push(path.to_path_buf());
+                Ok(&mut e.insert((original.clone(), original)).1)
+            }
+        }
+    }
+
+    fn apply_operation(
+        &mut self,
+        path: &Path,
+        operation: &ResourceOperation,
+    ) -> Result<(), EditError> {
+        match operation {
+            ResourceOperation::Create {
+                overwrite,
+                ignore_if_exists,
+            } => {
+                let current = self.current(path)?;
+                match current {
+                    Some(_) if *overwrite => *current = Some(String::new()),
+                    Some(_) if *ignore_if_exists => {}
+                    Some(_) => return Err(invalid(path, "file already exists")),
+                    None => *current = Some(String::new()),
+                }
+            }
+            ResourceOperation::Rename {
+                old_file,
+                overwrite,
+                ignore_if_exists,
+            } => {
+                if self.current(path)?.is_some() && !overwrite {
+                    if *ignore_if_exists {
+                        return Ok(());
+                    }
+                    return Err(invalid(path, "file already exists"));
+                }
+                let content = self
+                    .current(old_file)?
+                    .take()
+                    .ok_or_else(|| invalid(old_file, "file does not exist"))?;
+                *self.current(path)? = Some(content);
+            }
+            ResourceOperation::Delete {
+                ignore_if_not_exists,
+                ..
+            } => {
+                if self.current(path)?.take().is_none() && !ignore_if_not_exists {
+                    return Err(invalid(path, "file does not exist"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn into_changed(mut self) -> Vec<AppliedFile> {
+        self.order
+            .into_iter()
+            .filter_map(|path| {
+                let (original, updated) = self.files.remove(&path)?;
+                (original != updated).then_some(AppliedFile {
+                    path,
+                    original,
+                    updated,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Apply text edits to a document, keeping its line endings, byte order mark
+/// and final newline (see [`TextBuffer::apply`])
+pub fn apply_text_edits<'a>(
+    content: &str,
+    edits: impl IntoIterator<Item = &'a TextEdit>,
+) -> Result<String, String> {
+    TextBuffer::new(content).apply(edits)
+}
+
+/// Stage every written file, then replace the originals one by one
+fn commit(files: &[AppliedFile]) -> Result<(), EditError> {
+    let mut staged: Vec<Option<PathBuf>> = Vec::with_capacity(files.len());
+    for file in files {
+        let result = match file.updated {
+            Some(ref content) => stage(&file.path, content).map(Some),
+            None => Ok(None),
+        };
+        match result {
+            Ok(tmp) => staged.push(tmp),
+            Err(source) => {
+                discard(&staged);
+                return Err(EditError::Write {
+                    path: file.path.display().to_string(),
+                    source,
+                });
+            }
+        }
+    }
+
+    for (i, (file, tmp)) in files.iter().zip(&staged).enumerate() {
+        let result = verify_unchanged(file).and_then(|()| {
+            let replaced = match tmp {
+                Some(tmp) => fs::rename(tmp, &file.path),
+                None => fs::remove_file(&file.path),
+            };
+            replaced.map_err(|source| EditError::Write {
+                path: file.path.display().to_string(),
+                source,
+            })
+        });
+
+        if let Err(e) = result {
+            discard(&staged[i..]);
+            return Err(rollback(&files[..i], e));
+        }
+    }
+
+    Ok(())
+}
+
+/// Another process may have written the file since it was read
+fn verify_unchanged(file: &AppliedFile) -> Result<(), EditError> {
+    let path = file.path.display().to_string();
+    match (fs::read(&file.path), &file.original) {
+        (Ok(current), Some(original)) if current == original.as_bytes() => Ok(()),
+        (Err(e), None) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        (Err(source), Some(_)) if source.kind() != io::ErrorKind::NotFound => {
+            Err(EditError::Read { path, source })
+        }
+        _ => Err(EditError::Modified { path }),
+    }
+}
+
+/// Write content to a temporary file next to `path`
+fn stage(path: &Path, content: &str) -> io::Result<PathBuf> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{}.symora-{}.tmp", name, std::process::id()));
+
+    if let Err(e) = fs::write(&tmp, content) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    if let Ok(meta) = fs::metadata(path) {
+        let _ = fs::set_permissions(&tmp, meta.permissions());
+    }
+    Ok(tmp)
+}
+
+fn discard(staged: &[Option<PathBuf>]) {
+    for tmp in staged.iter().flatten() {
+        let _ = fs::remove_file(tmp);
+    }
+}
+
+/// Restore files that were already replaced
+fn rollback(replaced: &[AppliedFile], cause: EditError) -> EditError {
+    let failed: Vec<String> = replaced
+        .iter()
+        .filter(|f| {
+            let restored = match f.original {
+                Some(ref content) => {
+                    stage(&f.path, content).and_then(|tmp| fs::rename(tmp, &f.path))
+                }
+                None => fs::remove_file(&f.path),
+            };
+            restored.is_err()
+        })
+        .map(|f| f.path.display().to_string())
+        .collect();
+
+    if failed.is_empty() {
+        cause
+    } else {
+        EditError::RollbackFailed {
+            cause: cause.to_string(),
+            files: failed.join(", "),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::lsp::{Position, Range};
+    use tempfile::TempDir;
+
+    fn edit(start: (u32, u32), end: (u32, u32), text: &str) -> TextEdit {
+        TextEdit {
+            range: Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1)),
+            new_text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_apply_text_edits() {
+        let content = "fn foo() {\r\n    foo();\r\n}\r\n";
+        let edits = [
+            edit((0, 3), (0, 6), "bar"),
+            edit((1, 4), (1, 7), "bar"),
+            edit((1, 100), (1, 100), " // x"),
+        ];
+        assert_eq!(
+            apply_text_edits(content, &edits).unwrap(),
+            "fn bar() {\r\n    bar(); // x\r\n}\r\n"
+        );
+
+        // Full-document replacement with an end past the last line keeps the
+        // final newline
+        let edits = [edit((0, 0), (99, 0), "x")];
+        assert_eq!(apply_text_edits(content, &edits).unwrap(), "x\r\n");
+
+        // Columns count characters, not bytes
+        let edits = [
+            edit((1, 4), (1, 6), "name"),
+            edit((1, 13), (1, 13), " // 🦀"),
+        ];
+        assert_eq!(
+            apply_text_edits("// 주석\nlet 이름 = \"🦀\";\n", &edits).unwrap(),
+            "// 주석\nlet name = \"🦀\"; // 🦀\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_text_edits_rejects_overlap() {
+        let edits = [edit((0, 0), (0, 5), "a"), edit((0, 3), (0, 8), "b")];
+        assert!(apply_text_edits("0123456789", &edits).is_err());
+
+        let edits = [edit((0, 5), (0, 2), "a")];
+        assert!(apply_text_edits("0123456789", &edits).is_err());
+    }
+
+    #[test]
+    fn test_apply_workspace_edit() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.rs");
+        let b = dir.path().join("b.rs");
+        fs::write(&a, "let foo = 1;\n").unwrap();
+        fs::write(&b, "use foo;\n").unwrap();
+
+        let changes = vec![
+            FileChangeWithEdits::new(a.clone(), vec![edit((0, 4), (0, 7), "bar")]),
+            FileChangeWithEdits::new(b.clone(), vec![edit((0, 4), (0, 7), "bar")]),
+        ];
+        let applied = apply_workspace_edit(&changes).unwrap();
+
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied[0].original.as_deref(), Some("let foo = 1;\n"));
+        assert_eq!(fs::read_to_string(&a).unwrap(), "let bar = 1;\n");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "use bar;\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_apply_workspace_edit_rolls_back() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.rs");
+        let b = dir.path().join("b.rs");
+        fs::write(&a, "a\n").unwrap();
+        fs::write(&b, "b\n").unwrap();
+
+        let changes = vec![
+            FileChangeWithEdits::new(a.clone(), vec![edit((0, 0), (0, 1), "x")]),
+            FileChangeWithEdits::new(b.clone(), vec![edit((0, 0), (0, 1), "y")]),
+        ];
+        let files = prepare_workspace_edit(&changes).unwrap();
+
+        // b changes after the edits were computed
+        fs::write(&b, "changed\n").unwrap();
+        let err = commit(&files).unwrap_err();
+
+        assert!(matches!(err, EditError::Modified { .. }));
+        assert_eq!(fs::read_to_string(&a).unwrap(), "a\n");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "changed\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_prepare_workspace_edit_missing_file() {
+        let dir = TempDir::new().unwrap();
+        let changes = vec![FileChangeWithEdits::new(
+            dir.path().join("missing.rs"),
+            vec![edit((0, 0), (0, 0), "x")],
+        )];
+        assert!(matches!(
+            prepare_workspace_edit(&changes),
+            Err(EditError::InvalidEdit { .. })
+        ));
+    }
+
+    #[test]
+    fn test_prepare_workspace_edit_checks_versions() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.rs");
+        let b = dir.path().join("b.rs");
+        fs::write(&a, "a\n").unwrap();
+        fs::write(&b, "b\n").unwrap();
+
+        let versioned = |file: &PathBuf, version, synced| FileChangeWithEdits {
+            version: Some(version),
+            synced_version: synced,
+            ..FileChangeWithEdits::new(file.clone(), vec![edit((0, 0), (0, 1), "x")])
+        };
+
+        // Matching versions, and documents the client never synced, apply
+        let changes = [versioned(&a, 3, Some(3)), versioned(&b, 1, None)];
+        assert_eq!(prepare_workspace_edit(&changes).unwrap().len(), 2);
+
+        // Edits for an older version are refused before anything is read
+        let changes = [versioned(&a, 3, Some(3)), versioned(&b, 2, Some(5))];
+        assert!(matches!(
+            apply_workspace_edit(&changes),
+            Err(EditError::VersionMismatch {
+                version: 2,
+                synced: 5,
+                ..
+            })
+        ));
+        assert_eq!(fs::read_to_string(&a).unwrap(), "a\n");
+    }
+
+    fn operation(file: PathBuf, operation: ResourceOperation) -> FileChangeWithEdits {
+        FileChangeWithEdits::operation(file, operation)
+    }
+
+    #[test]
+    fn test_apply_resource_operations() {
+        let dir = TempDir::new().unwrap();
+        let old = dir.path().join("Foo.java");
+        let new = dir.path().join("Bar.java");
+        let created = dir.path().join("sub").join("new.ts");
+        let gone = dir.path().join("gone.rs");
+        fs::write(&old, "class Foo {}\n").unwrap();
+        fs::write(&gone, "x\n").unwrap();
+
+        let changes = vec![
+            FileChangeWithEdits::new(old.clone(), vec![edit((0, 6), (0, 9), "Bar")]),
+            operation(
+                new.clone(),
+                ResourceOperation::Rename {
+                    old_file: old.clone(),
+                    overwrite: false,
+                    ignore_if_exists: false,
+                },
+            ),
+            operation(
+                created.clone(),
+                ResourceOperation::Create {
+                    overwrite: false,
+                    ignore_if_exists: false,
+                },
+            ),
+            FileChangeWithEdits::new(created.clone(), vec![edit((0, 0), (0, 0), "export {}\n")]),
+            operation(
+                gone.clone(),
+                ResourceOperation::Delete {
+                    recursive: false,
+                    ignore_if_not_exists: false,
+                },
+            ),
+        ];
+        let applied = apply_workspace_edit(&changes).unwrap();
+
+        assert_eq!(applied.len(), 4);
+        assert!(!old.exists() && !gone.exists());
+        assert_eq!(fs::read_to_string(&new).unwrap(), "class Bar {}\n");
+        assert_eq!(fs::read_to_string(&created).unwrap(), "export {}\n");
+    }
+
+    #[test]
+    fn test_resource_operation_conflicts() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.rs");
+        fs::write(&a, "a\n").unwrap();
+
+        let create = |overwrite, ignore_if_exists| {
+            vec![operation(
+                a.clone(),
+                ResourceOperation::Create {
+                    overwrite,
+                    ignore_if_exists,
+                },
+            )]
+        };
+        assert!(prepare_workspace_edit(&create(false, false)).is_err());
+        assert!(
+            prepare_workspace_edit(&create(false, true))
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            prepare_workspace_edit(&create(true, false)).unwrap()[0]
+                .updated
+                .as_deref(),
+            Some("")
+        );
+
+        let delete_missing = vec![operation(
+            dir.path().join("missing.rs"),
+            ResourceOperation::Delete {
+                recursive: false,
+                ignore_if_not_exists: true,
+            },
+        )];
+        assert!(prepare_workspace_edit(&delete_missing).unwrap().is_empty());
+    }
+}